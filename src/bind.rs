@@ -0,0 +1,221 @@
+//! Drive the brightness of lights from the result of a shell command
+//!
+//! A [`Binding`] periodically runs a command and maps its result to a brightness level, which is useful for
+//! status LEDs showing things the kernel has no trigger for (CI build status, system load, unread mail, etc.).
+//!
+//! The result of the command can be mapped in two ways (see [`Mapping`]):
+//! - [`Mapping::Status`]: the light is turned on if the command succeeds (exit status 0), and off otherwise.
+//! - [`Mapping::Value`]: the number printed by the command is scaled from the given range to the brightness range of the light.
+//!
+//! # Usage
+//! ```no_run
+//! use std::{slice, time::Duration};
+//! use blight::{bind::{Binding, Mapping}, led::Led};
+//!
+//! fn main() -> blight::Result<()> {
+//!     let mut led = Led::new("platform::kbd_backlight".into())?;
+//!     // Show the 1 minute load average on the keyboard backlight, reaching max brightness at a load of 4
+//!     let binding = Binding::new("cut -d ' ' -f 1 /proc/loadavg")
+//!         .mapping(Mapping::Value { min: 0.0, max: 4.0 })
+//!         .interval(Duration::from_secs(2));
+//!     binding.run(slice::from_mut(&mut led))?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::{
+    process::{Command, Stdio},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use crate::{
+    err::{Error, ErrorKind},
+    utils, Light,
+};
+
+/// How the result of a bound command is mapped to a brightness level
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mapping {
+    /// Max brightness if the command exits successfully, 0 otherwise
+    Status,
+    /// Scale the number printed by the command from `min..=max` to the brightness range of the light
+    ///
+    /// The first word of the output is used, and values outside the range are clamped.
+    /// The exit status of the command is ignored (e.g. `grep -c` exits with 1 when it prints 0).
+    Value { min: f64, max: f64 },
+}
+
+/// A shell command bound to the brightness of one or more lights
+///
+/// See [module][self] level docs for usage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binding {
+    command: String,
+    interval: Duration,
+    mapping: Mapping,
+    invert: bool,
+}
+
+impl Binding {
+    /// Create a new binding for a command, which is run with `sh -c`
+    ///
+    /// The command is run every 5 seconds and its exit status is used as the result ([`Mapping::Status`]) by default.
+    #[must_use]
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            interval: Duration::from_secs(5),
+            mapping: Mapping::Status,
+            invert: false,
+        }
+    }
+
+    /// Time to wait between each run of the command
+    #[must_use]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// How the result of the command is mapped to a brightness level
+    #[must_use]
+    pub fn mapping(mut self, mapping: Mapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
+    /// Invert the brightness level, e.g. to turn a light on when a command fails
+    #[must_use]
+    pub fn invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    /// Run the command once and return the resulting brightness level, ranging from `0.0` (off) to `1.0` (max)
+    ///
+    /// # Errors
+    /// - [`ErrorKind::RunCommand`] - if the command could not be started
+    /// - [`ErrorKind::CommandOutput`] - if the output isn't a number while using [`Mapping::Value`]
+    pub fn level(&self) -> crate::Result<f64> {
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(Stdio::null())
+            .stderr(Stdio::inherit())
+            .output()
+            .map_err(|err| {
+                Error::from(ErrorKind::RunCommand {
+                    command: self.command.clone(),
+                })
+                .with_source(err)
+            })?;
+        let level = match self.mapping {
+            Mapping::Status if output.status.success() => 1.0,
+            Mapping::Status => 0.0,
+            Mapping::Value { min, max } => {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let word = stdout.split_whitespace().next().unwrap_or_default();
+                let value: f64 = word.parse().map_err(|_| ErrorKind::CommandOutput {
+                    output: stdout.trim().to_owned(),
+                })?;
+                scale(value, min, max)
+            }
+        };
+        Ok(if self.invert { 1.0 - level } else { level })
+    }
+
+    /// Run the command once and set the brightness of the lights accordingly, returning the brightness level
+    ///
+    /// # Errors
+    /// - All possible errors returned by [`Binding::level`] and [`Light::write_value`]
+    pub fn update<L: Light>(&self, lights: &mut [L]) -> crate::Result<f64> {
+        let level = self.level()?;
+        for light in lights {
+            let value = utils::percent_to_value(level * 100.0, light.max().into());
+            let value = L::Value::try_from(value).unwrap_or_default();
+            if value != light.current() {
+                light.write_value(value)?;
+            }
+        }
+        Ok(level)
+    }
+
+    /// Keep updating the lights at the configured interval, blocking forever or until an error occurs
+    ///
+    /// # Errors
+    /// - All possible errors returned by [`Binding::update`]
+    pub fn run<L: Light>(&self, lights: &mut [L]) -> crate::Result<()> {
+        self.run_until(lights, &AtomicBool::new(false))
+    }
+
+    /// Keep updating the lights at the configured interval until `stop` is set to `true` or an error occurs
+    ///
+    /// # Errors
+    /// - All possible errors returned by [`Binding::update`]
+    pub fn run_until<L: Light>(&self, lights: &mut [L], stop: &AtomicBool) -> crate::Result<()> {
+        while !stop.load(Ordering::Relaxed) {
+            self.update(lights)?;
+            utils::sleep_until_stopped(self.interval, stop);
+        }
+        Ok(())
+    }
+}
+
+/// Linearly scale `value` from `min..=max` to `0.0..=1.0`
+fn scale(value: f64, min: f64, max: f64) -> f64 {
+    if (max - min).abs() < f64::EPSILON {
+        return if value >= max { 1.0 } else { 0.0 };
+    }
+    ((value - min) / (max - min)).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{with_test_env, MockInterface};
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn status_mapping() {
+        assert_eq!(Binding::new("true").level().unwrap(), 1.0);
+        assert_eq!(Binding::new("exit 3").level().unwrap(), 0.0);
+        assert_eq!(Binding::new("false").invert(true).level().unwrap(), 1.0);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn value_mapping() {
+        let value = |cmd: &str, min, max| {
+            Binding::new(cmd)
+                .mapping(Mapping::Value { min, max })
+                .level()
+        };
+        assert_eq!(value("echo 2 extra words", 0.0, 4.0).unwrap(), 0.5);
+        assert_eq!(value("echo 0.5; exit 1", 0.0, 1.0).unwrap(), 0.5);
+        assert_eq!(value("echo 12", 0.0, 4.0).unwrap(), 1.0);
+        assert_eq!(value("echo 3", 1.0, 1.0).unwrap(), 1.0);
+        assert_eq!(value("echo 25", 100.0, 0.0).unwrap(), 0.75);
+        let err = value("echo none", 0.0, 1.0).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &ErrorKind::CommandOutput {
+                output: "none".into()
+            }
+        );
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn updates_lights() {
+        with_test_env(&["a", "b"], || {
+            let mut lights = [MockInterface::new("a"), MockInterface::new("b")];
+            let binding = Binding::new("echo 30").mapping(Mapping::Value {
+                min: 0.0,
+                max: 100.0,
+            });
+            assert_eq!(binding.update(&mut lights).unwrap(), 0.3);
+            assert!(lights.iter().all(|l| l.current() == 30));
+        });
+    }
+}
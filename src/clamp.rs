@@ -0,0 +1,155 @@
+//! Brightness floor and ceiling for backlight devices
+
+use crate::utils;
+
+/// A brightness limit, either as a raw value or as a percentage of the max brightness
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bound {
+    Raw(u32),
+    Percent(f64),
+}
+
+impl Bound {
+    /// Raw value of the bound for a device with the given max brightness
+    #[must_use]
+    pub fn resolve(self, max: u32) -> u32 {
+        match self {
+            Bound::Raw(value) => value.min(max),
+            Bound::Percent(percent) => utils::percent_to_value(percent, max),
+        }
+    }
+}
+
+/// Lowest and highest brightness a [`Device`][crate::Device] can be set to
+///
+/// Some panels turn fully black at a brightness of `0`, which is easily mistaken for a broken screen, and a ceiling can be
+/// used to save power (e.g. on battery). Once a policy is set with [`Device::set_clamp_policy`][crate::Device::set_clamp_policy],
+/// [`Light::write_value`][crate::Light::write_value], [`Light::sweep_write`][crate::Light::sweep_write] and
+/// [`Light::calculate_change`][crate::Light::calculate_change] keep the brightness within it. Values outside of the policy
+/// are silently moved to the closest bound, while values larger than the max brightness of the device are still an error.
+///
+/// Use [`Light::write_value_unclamped`][crate::Light::write_value_unclamped] or
+/// [`Light::sweep_write_unclamped`][crate::Light::sweep_write_unclamped] to bypass the policy for a single write.
+///
+/// When the floor is higher than the ceiling, the floor wins.
+///
+/// # Examples
+/// ```no_run
+/// use blight::{Bound, ClampPolicy, Device, Direction, Light};
+///
+/// fn main() -> blight::Result<()> {
+///     let mut dev = Device::new(None)?
+///         .with_clamp_policy(ClampPolicy::new().min(Bound::Percent(5.0)).max(Bound::Percent(80.0)));
+///     let new = dev.calculate_change(100, Direction::Dec); // 5% instead of 0
+///     dev.write_value(new)?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ClampPolicy {
+    min: Option<Bound>,
+    max: Option<Bound>,
+}
+
+impl ClampPolicy {
+    /// A policy without any limits, same as [`ClampPolicy::default`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the lowest brightness
+    #[must_use]
+    pub fn min(mut self, bound: Bound) -> Self {
+        self.min = Some(bound);
+        self
+    }
+
+    /// Set the highest brightness
+    #[must_use]
+    pub fn max(mut self, bound: Bound) -> Self {
+        self.max = Some(bound);
+        self
+    }
+
+    /// The lowest brightness, if set
+    #[must_use]
+    pub fn floor(&self) -> Option<Bound> {
+        self.min
+    }
+
+    /// The highest brightness, if set
+    #[must_use]
+    pub fn ceiling(&self) -> Option<Bound> {
+        self.max
+    }
+
+    /// Whether neither a floor nor a ceiling is set
+    #[must_use]
+    pub fn is_unlimited(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    /// Raw floor and ceiling for a device with the given max brightness
+    #[must_use]
+    pub fn range(&self, max: u32) -> (u32, u32) {
+        let floor = self.min.map_or(0, |bound| bound.resolve(max));
+        let ceiling = self.max.map_or(max, |bound| bound.resolve(max));
+        (floor, ceiling.max(floor))
+    }
+
+    /// Move a raw value within the policy, for a device with the given max brightness
+    #[must_use]
+    pub fn apply(&self, value: u32, max: u32) -> u32 {
+        let (floor, ceiling) = self.range(max);
+        value.clamp(floor, ceiling)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::with_test_env, Delay, Device, Direction, Light};
+
+    #[test]
+    fn range() {
+        let policy = ClampPolicy::new()
+            .min(Bound::Percent(5.0))
+            .max(Bound::Raw(80));
+        assert_eq!(policy.range(100), (5, 80));
+        assert_eq!(policy.range(50), (3, 50));
+        assert_eq!(policy.apply(0, 100), 5);
+        assert_eq!(policy.apply(90, 100), 80);
+        // The floor wins over a lower ceiling
+        let policy = ClampPolicy::new().min(Bound::Raw(60)).max(Bound::Raw(40));
+        assert_eq!(policy.apply(50, 100), 60);
+        assert_eq!(ClampPolicy::new().range(100), (0, 100));
+    }
+
+    #[test]
+    fn clamped_writes() {
+        with_test_env(&["generic"], || {
+            let policy = ClampPolicy::new()
+                .min(Bound::Percent(10.0))
+                .max(Bound::Percent(90.0));
+            let mut d = Device::new(Some("generic".into()))
+                .unwrap()
+                .with_clamp_policy(policy);
+
+            assert_eq!(d.calculate_change(100, Direction::Dec), 10);
+            assert_eq!(d.calculate_change(100, Direction::Inc), 90);
+            d.write_value(0).unwrap();
+            d.reload();
+            assert_eq!(d.current(), 10);
+            d.sweep_write(100, Delay::from_millis(0)).unwrap();
+            d.reload();
+            assert_eq!(d.current(), 90);
+            // Values above the max brightness are still an error
+            assert!(d.write_value(101).is_err());
+
+            d.write_value_unclamped(0).unwrap();
+            d.reload();
+            assert_eq!(d.current(), 0);
+        });
+    }
+}
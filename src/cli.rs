@@ -1,16 +1,46 @@
 use blight::{
+    bind::{Binding, Mapping},
+    effects::{Effect, Waveform},
     led::{self, Color, Function},
+    watch::Watcher,
     Change, Device,
     Direction::{self, Dec, Inc},
-    Light, BLDIR,
+    Level, Light, BLDIR,
 };
 use colored::Colorize;
-use std::{borrow::Cow, env, env::Args, fs, iter::Skip, path::PathBuf};
+use std::{
+    borrow::Cow,
+    env::Args,
+    fs,
+    io::{self, Write},
+    iter::Skip,
+    path::PathBuf,
+    process::ExitCode,
+    time::Duration,
+};
 
+mod args;
+mod bar;
+mod completions;
+mod config;
+mod history;
+mod output;
+mod paths;
+mod presets;
+mod saves;
 mod setup;
+mod signal;
+mod stream;
+mod template;
+
+use args::Matches;
+use output::Format;
+use template::Template;
 
-const SAVEDIR: &str = "/.local/share/blight";
 const LOCKFILE: &str = "/tmp/blight.lock";
+const WATCH_FORMAT: &str = "{name} {percent}%";
+const BAR_FORMAT: &str = "{percent}%";
+const BAR_MODULE: &str = "blight";
 
 type DynError = Box<dyn std::error::Error + 'static>;
 
@@ -25,31 +55,211 @@ enum Command {
     Setup,
     Help,
     ShortHelp,
+    Usage(&'static args::Spec),
     Status,
-    Save,
-    Restore,
+    Get(GetKind),
+    Watch {
+        interval: Option<Duration>,
+    },
+    Bar(bar::Bar),
+    Completions(completions::Shell),
+    /// Hidden command used by the completion scripts
+    Complete(Vec<String>),
+    Save {
+        slot: String,
+        leds: Option<led::LedFilter>,
+    },
+    Restore(String),
+    Saves(SavesCommand),
+    Configure(ConfigCommand),
+    Preset(PresetCommand),
+    Cycle(Vec<String>),
+    Exec {
+        level: Level,
+        command: Vec<String>,
+    },
+    Undo(usize),
+    History,
     List,
-    Adjust { dir: Direction, value: u32 },
-    Set(u32),
+    Adjust {
+        dir: Direction,
+        value: Option<u32>,
+    },
+    Set(Level),
+    /// Levels read from stdin
+    SetStream {
+        coalesce: Option<Duration>,
+    },
+    Effect {
+        waveform: Waveform,
+        opts: EffectOptions,
+    },
     Led(LedCommand),
 }
 
+#[derive(Debug)]
+enum SavesCommand {
+    List,
+    Delete(String),
+}
+
+#[derive(Debug)]
+enum ConfigCommand {
+    Show,
+    Get(String),
+    Set(String, String),
+    Unset(String),
+    Edit,
+    Path,
+}
+
+#[derive(Debug)]
+enum PresetCommand {
+    List,
+    Set(String, Level),
+    Delete(String),
+    Apply(String),
+}
+
 #[derive(Debug)]
 enum LedCommand {
-    Toggle { led: String, kind: ToggleKind },
-    Set { led: String, value: u8 },
-    Info(String),
-    List { raw: bool, filter: Option<LedListFilter> },
+    Toggle {
+        led: LedTarget,
+        kind: ToggleKind,
+    },
+    Set {
+        led: LedTarget,
+        value: Level,
+    },
+    SetStream {
+        led: LedTarget,
+        coalesce: Option<Duration>,
+    },
+    Preset {
+        led: LedTarget,
+        name: String,
+    },
+    Cycle {
+        led: LedTarget,
+        names: Vec<String>,
+    },
+    Effect {
+        led: LedTarget,
+        waveform: Waveform,
+        opts: EffectOptions,
+    },
+    Play {
+        source: String,
+        filter: Option<led::LedFilter>,
+        names: Vec<String>,
+        check: bool,
+    },
+    Bind {
+        led: LedTarget,
+        binding: Binding,
+        once: bool,
+    },
+    Info {
+        led: Option<LedTarget>,
+        format: Format,
+    },
+    List(LedListOptions),
     Help,
     ShortHelp,
 }
 
+#[derive(Debug, Default, Clone, Copy)]
+enum GetKind {
+    #[default]
+    Raw,
+    Percent,
+    Max,
+}
+
+#[derive(Debug, Default)]
+struct LedListOptions {
+    format: Format,
+    raw: bool,
+    group: bool,
+    device: Option<String>,
+    filter: Option<LedListFilter>,
+}
+
 #[derive(Debug)]
 enum LedListFilter {
     Index(usize),
     Function(String),
-    Color(String), 
-    FunctionColor(String, String)
+    Color(String),
+    FunctionColor(String, String),
+}
+
+#[derive(Debug, Default)]
+struct EffectOptions {
+    period: Option<Duration>,
+    times: Option<u32>,
+    forever: bool,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl EffectOptions {
+    /// Parse the options shared by all effect commands
+    fn from_matches(m: &Matches) -> Result<Self, BlightError> {
+        let err = |msg| Err(BlightError::BadEffectOptions(msg));
+        let mut opts = EffectOptions {
+            forever: m.flag("forever"),
+            ..Default::default()
+        };
+        if let Some(period) = m.value("period") {
+            let Some(period) = parse_duration(period) else {
+                return err("invalid period (use a unit, e.g. 500ms or 2s)");
+            };
+            opts.period = Some(period);
+        }
+        if let Some(times) = m.value("times") {
+            let Ok(times) = times.parse() else {
+                return err("invalid number of repetitions");
+            };
+            opts.times = Some(times);
+        }
+        if let Some(min) = m.value("min") {
+            let Some(min) = parse_percent(min) else {
+                return err("invalid min brightness (should be a percentage between 0 and 100)");
+            };
+            opts.min = Some(min);
+        }
+        if let Some(max) = m.value("max") {
+            let Some(max) = parse_percent(max) else {
+                return err("invalid max brightness (should be a percentage between 0 and 100)");
+            };
+            opts.max = Some(max);
+        }
+        if opts.forever && opts.times.is_some() {
+            return err("--times and --forever can't be used together");
+        }
+        Ok(opts)
+    }
+
+    fn build(&self, waveform: Waveform) -> Effect {
+        let mut effect =
+            Effect::new(waveform).amplitude(self.min.unwrap_or(0.0), self.max.unwrap_or(100.0));
+        if let Some(period) = self.period {
+            effect = effect.period(period);
+        }
+        if let Some(times) = self.times {
+            effect = effect.repeat(times);
+        }
+        if self.forever {
+            effect = effect.forever();
+        }
+        effect
+    }
+}
+
+#[derive(Debug)]
+enum LedTarget {
+    Name(String),
+    Group(led::LedFilter),
 }
 
 #[derive(Debug)]
@@ -62,111 +272,340 @@ enum ToggleKind {
 #[derive(Default, Debug)]
 struct Options<'a> {
     device: Option<Cow<'a, str>>,
-    sweep: Change,
+    /// Set by --sweep or --no-sweep, `None` to use the config
+    sweep: Option<Change>,
+    format: Format,
+    template: Option<String>,
 }
 
 impl Options<'_> {
-    fn set(mut self, arg: String) -> Self {
-        match arg.as_str() {
-            "-d" | "--device" => self.device = Some("".into()),
-            "-s" | "--sweep" => self.sweep = Change::Sweep,
-            _ => {
-                if let Some(d) = &mut self.device {
-                    if d.is_empty() {
-                        *d = Cow::from(arg);
-                    }
-                }
-            }
+    fn from_matches(m: &Matches) -> Self {
+        Options {
+            device: m.value("device").map(|d| Cow::Owned(d.to_owned())),
+            sweep: m.iter().fold(None, |sweep, (flag, _)| match flag {
+                "sweep" => Some(Change::Sweep),
+                "no-sweep" => Some(Change::Regular),
+                _ => sweep,
+            }),
+            template: m.value("format").map(str::to_owned),
+            format: output_format(m),
         }
-        self
     }
 }
 
+/// The output format selected with --json or --porcelain, the last one taking precedence
+fn output_format(m: &Matches) -> Format {
+    m.iter()
+        .fold(Format::Human, |format, (flag, _)| match flag {
+            "json" => Format::Json,
+            "porcelain" => Format::Porcelain,
+            _ => format,
+        })
+}
+
 pub fn parse<'a>(mut args: Skip<Args>) -> Result<Config<'a>, DynError> {
     use BlightError::*;
     use Command::*;
 
-    let option_parser =
-        |args: Skip<Args>| -> Options { args.fold(Options::default(), |op, arg| op.set(arg)) };
-
-    let no_opt = |cm: Command| (cm, Options::default());
-
-    let (command, options) = if let Some(arg) = args.next() {
-        match arg.as_str() {
-            "setup" => no_opt(Setup),
-            "help" => no_opt(Help),
-            "restore" => no_opt(Restore),
-            "list" => no_opt(List),
-            "status" => (Status, option_parser(args)),
-            "save" => (Save, option_parser(args)),
-
-            "set" => {
-                let val: u32 = args
-                    .next()
-                    .ok_or(MissingValue)?
-                    .parse()
-                    .or(Err(InvalidValue))?;
-
-                (Set(val), option_parser(args))
+    let Some(name) = args.next() else {
+        return Ok(Config {
+            command: ShortHelp,
+            options: Options::default(),
+        });
+    };
+    let name = if matches!(name.as_str(), "--help" | "-h") {
+        "help".to_owned()
+    } else {
+        name
+    };
+    if name == "__complete" {
+        return Ok(Config {
+            command: Complete(args.collect()),
+            options: Options::default(),
+        });
+    }
+    let spec = args::find(args::COMMANDS, &name).map_err(Args)?;
+    if spec.name == "led" {
+        return Ok(Config {
+            command: parse_led(args)?,
+            options: Options::default(),
+        });
+    }
+    let m = args::parse(spec, args).map_err(Args)?;
+    if m.help {
+        return Ok(Config {
+            command: Usage(spec),
+            options: Options::default(),
+        });
+    }
+    let options = Options::from_matches(&m);
+    let mut positional = m.positional.iter();
+
+    let command = match spec.name {
+        "setup" => Setup,
+        "restore" => Restore(saves::validate_slot(
+            positional
+                .next()
+                .map_or(saves::DEFAULT_SLOT, String::as_str),
+        )?),
+        "saves" => match positional.next().map(String::as_str) {
+            None | Some("list") => Saves(SavesCommand::List),
+            Some("delete") => Saves(SavesCommand::Delete(saves::validate_slot(
+                positional.next().ok_or(MissingValue)?,
+            )?)),
+            Some(other) => Err(UnknownSavesCommand(other.to_owned()))?,
+        },
+        "config" => Configure(
+            match (
+                positional.next().map(String::as_str),
+                positional.next(),
+                positional.next(),
+            ) {
+                (None, ..) => ConfigCommand::Show,
+                (Some("get"), Some(key), None) => ConfigCommand::Get(key.clone()),
+                (Some("set"), Some(key), Some(value)) => {
+                    ConfigCommand::Set(key.clone(), value.clone())
+                }
+                (Some("unset"), Some(key), None) => ConfigCommand::Unset(key.clone()),
+                (Some("edit"), None, _) => ConfigCommand::Edit,
+                (Some("path"), None, _) => ConfigCommand::Path,
+                (Some("get" | "unset"), Some(_), Some(arg))
+                | (Some("edit" | "path"), Some(arg), _) => {
+                    Err(Args(args::ArgError::UnexpectedArgument {
+                        command: spec.name,
+                        arg: arg.clone(),
+                    }))?
+                }
+                (Some("get" | "set" | "unset"), ..) => Err(MissingValue)?,
+                (Some(other), ..) => Err(UnknownConfigCommand(other.to_owned()))?,
+            },
+        ),
+        "preset" => Preset(
+            match (
+                positional.next().map(String::as_str),
+                positional.next(),
+                positional.next(),
+            ) {
+                (None, ..) | (Some("list"), None, _) => PresetCommand::List,
+                (Some("set"), Some(name), Some(level)) => {
+                    PresetCommand::Set(presets::validate_name(name)?, presets::parse_level(level)?)
+                }
+                (Some("delete"), Some(name), None) => PresetCommand::Delete(name.clone()),
+                (Some("set" | "delete"), None, _) | (Some("set"), Some(_), None) => {
+                    Err(MissingValue)?
+                }
+                (Some(name), None, _) => PresetCommand::Apply(name.to_owned()),
+                (Some("delete"), Some(_), Some(arg)) | (Some(_), Some(arg), _) => {
+                    Err(Args(args::ArgError::UnexpectedArgument {
+                        command: spec.name,
+                        arg: arg.clone(),
+                    }))?
+                }
+            },
+        ),
+        "cycle" => Cycle(positional.cloned().collect()),
+        "undo" => Undo(
+            positional
+                .next()
+                .map_or(Some(1), |n| n.parse().ok().filter(|n| *n > 0))
+                .ok_or(InvalidValue)?,
+        ),
+        "history" => History,
+        "exec" => {
+            let level = m
+                .value("set")
+                .ok_or(BadExecOptions("--set <level> is required"))?;
+            let command: Vec<String> = positional.cloned().collect();
+            if command.is_empty() {
+                Err(BadExecOptions("no command to run"))?;
             }
+            Exec {
+                level: level.parse().map_err(InvalidLevel)?,
+                command,
+            }
+        }
+        "list" => List,
+        "status" => Status,
+        "save" => Save {
+            slot: saves::validate_slot(m.value("as").unwrap_or(saves::DEFAULT_SLOT))?,
+            leds: LedSelection::from_matches(&m).filter,
+        },
+        "completions" => {
+            let shell = positional.next().ok_or(MissingValue)?;
+            Completions(
+                completions::Shell::from_name(shell).ok_or_else(|| UnknownShell(shell.clone()))?,
+            )
+        }
+        "help" => match (positional.next().map(String::as_str), positional.next()) {
+            (None, _) => Help,
+            (Some("led"), None) => Command::Led(LedCommand::Help),
+            (Some("led"), Some(sub)) => Usage(args::find(args::LED_COMMANDS, sub).map_err(Args)?),
+            (Some(cmd), _) => Usage(args::find(args::COMMANDS, cmd).map_err(Args)?),
+        },
+        "get" => Get(m
+            .iter()
+            .fold(GetKind::default(), |kind, (flag, _)| match flag {
+                "raw" => GetKind::Raw,
+                "percent" => GetKind::Percent,
+                "max" => GetKind::Max,
+                _ => kind,
+            })),
+        "watch" => Watch {
+            interval: interval(&m).ok_or(BadWatchInterval)?,
+        },
+        "bar" => Bar(parse_bar_options(&m, options.template.as_deref())?),
+        cmd @ ("breathe" | "pulse" | "blink") => Effect {
+            waveform: effect_waveform(cmd),
+            opts: EffectOptions::from_matches(&m)?,
+        },
+        "set" => match (
+            positional.next().ok_or(MissingValue)?.as_str(),
+            coalesce(&m)?,
+        ) {
+            ("-", coalesce) => SetStream { coalesce },
+            (_, Some(_)) => Err(BadStreamOptions(
+                "--coalesce only works when reading levels from stdin ('-')",
+            ))?,
+            (level, None) => Set(level.parse().map_err(InvalidLevel)?),
+        },
+        ch @ ("inc" | "dec") => {
+            let value = positional
+                .next()
+                .map(|value| value.parse::<u32>())
+                .transpose()
+                .map_err(|_| InvalidValue)?;
+            let dir = if ch == "inc" { Inc } else { Dec };
+            Adjust { dir, value }
+        }
+        _ => unreachable!("command '{}' is missing from the parser", spec.name),
+    };
 
-            ch @ ("inc" | "dec") => {
-                let value: u32 = args
-                    .next()
-                    .ok_or(MissingValue)?
-                    .parse()
-                    .map_err(|_| InvalidValue)?;
-
-                let dir = if ch == "inc" { Inc } else { Dec };
+    Ok(Config { command, options })
+}
 
-                (Adjust { dir, value }, option_parser(args))
+fn parse_led(mut args: Skip<Args>) -> Result<Command, BlightError> {
+    let led_err = BlightError::Led;
+    let Some(name) = args.next() else {
+        return Ok(Command::Led(LedCommand::ShortHelp));
+    };
+    if matches!(name.as_str(), "--help" | "-h") {
+        return Ok(Command::Led(LedCommand::Help));
+    }
+    let spec = args::find(args::LED_COMMANDS, &name).map_err(BlightError::Args)?;
+    let m = args::parse(spec, args).map_err(BlightError::Args)?;
+    if m.help {
+        return Ok(Command::Usage(spec));
+    }
+    let selection = LedSelection::from_matches(&m);
+    let parsed = match spec.name {
+        "led help" => LedCommand::Help,
+        "led list" => LedCommand::List(parse_led_list_options(&m).map_err(led_err)?),
+        "led info" => LedCommand::Info {
+            led: if selection.filter.is_some() || !selection.positional.is_empty() {
+                Some(selection.into_target(0)?.0)
+            } else {
+                config::Settings::load()?.led_filter().map(LedTarget::Group)
+            },
+            format: output_format(&m),
+        },
+        "led set" => {
+            let (led, mut values) = selection.into_target(1)?;
+            let value = values.next().ok_or(led_err(LedError::MissingValue))?;
+            if values.next().is_some() {
+                Err(led_err(LedError::BadSelection(
+                    "too many arguments provided",
+                )))?;
             }
-
-            "led" => 'led: {
-                let Some(cmd) = args.next() else {
-                    break 'led no_opt(Command::Led(LedCommand::ShortHelp));
-                };
-                let led_err = BlightError::Led;
-                let parsed = match (cmd.as_str(), args.next()) {
-                    ("info", Some(led)) => LedCommand::Info(led),
-                    ("set", Some(led)) => LedCommand::Set {
-                        led,
-                        value: args
-                            .next()
-                            .ok_or(led_err(LedError::MissingValue))?
-                            .parse()
-                            .map_err(|_| led_err(LedError::InvalidValue))?,
-                    },
-                    ("toggle", Some(led)) => LedCommand::Toggle {
-                        led,
-                        kind: args
-                            .next()
-                            .map(|kind| match kind.as_str() {
-                                "--on" => Ok(ToggleKind::On),
-                                "--off" => Ok(ToggleKind::Off),
-                                _ => Err(UnrecognisedCommand),
-                            })
-                            .unwrap_or(Ok(ToggleKind::Toggle))?,
-                    },
-                    ("toggle" | "set", None) => Err(led_err(LedError::MissingName))?,
-                    ("list", None) => LedCommand::List { raw: false, filter: None },
-                    ("list", Some(arg)) => {
-                        let (raw, filter) = parse_led_list_options(std::iter::once(arg).chain(args)).map_err(led_err)?;
-                        LedCommand::List { raw, filter }
-                    },
-                    ("help", _) => LedCommand::Help,
-                    _ => Err(UnrecognisedCommand)?,
-                };
-                no_opt(Command::Led(parsed))
+            match (value.as_str(), coalesce(&m)?) {
+                ("-", coalesce) => LedCommand::SetStream { led, coalesce },
+                (_, Some(_)) => Err(BlightError::BadStreamOptions(
+                    "--coalesce only works when reading levels from stdin ('-')",
+                ))?,
+                (value, None) => LedCommand::Set {
+                    led,
+                    value: value.parse().map_err(BlightError::InvalidLevel)?,
+                },
             }
-
-            _ => Err(UnrecognisedCommand)?,
         }
-    } else {
-        no_opt(Command::ShortHelp)
+        "led preset" => {
+            let (led, mut rest) = selection.into_target(1)?;
+            let name = rest.next().ok_or(BlightError::MissingValue)?;
+            if rest.next().is_some() {
+                Err(led_err(LedError::BadSelection(
+                    "too many arguments provided",
+                )))?;
+            }
+            LedCommand::Preset { led, name }
+        }
+        "led cycle" => {
+            let (led, rest) = selection.into_target(0)?;
+            LedCommand::Cycle {
+                led,
+                names: rest.collect(),
+            }
+        }
+        "led toggle" => {
+            let kind = match (m.flag("on"), m.flag("off")) {
+                (true, true) => Err(led_err(LedError::BadSelection(
+                    "--on and --off can't be used together",
+                )))?,
+                (true, false) => ToggleKind::On,
+                (false, true) => ToggleKind::Off,
+                (false, false) => ToggleKind::Toggle,
+            };
+            let (led, mut rest) = selection.into_target(0)?;
+            if rest.next().is_some() {
+                Err(led_err(LedError::BadSelection(
+                    "LED names can't be combined with selectors",
+                )))?;
+            }
+            LedCommand::Toggle { led, kind }
+        }
+        cmd @ ("led breathe" | "led blink" | "led pulse") => {
+            let opts = EffectOptions::from_matches(&m)?;
+            let (led, mut rest) = selection.into_target(0)?;
+            if rest.next().is_some() {
+                Err(led_err(LedError::BadSelection(
+                    "LED names can't be combined with selectors",
+                )))?;
+            }
+            LedCommand::Effect {
+                led,
+                waveform: effect_waveform(&cmd[4..]),
+                opts,
+            }
+        }
+        "led play" => {
+            let mut positional = selection.positional.into_iter();
+            let source = positional
+                .next()
+                .ok_or(led_err(LedError::MissingSequence))?;
+            let names: Vec<String> = positional.collect();
+            if selection.filter.is_some() && !names.is_empty() {
+                Err(led_err(LedError::BadSelection(
+                    "LED names can't be combined with selectors",
+                )))?;
+            }
+            LedCommand::Play {
+                source,
+                filter: selection.filter,
+                names,
+                check: m.flag("check"),
+            }
+        }
+        "led bind" => {
+            let (binding, once) = parse_bind_options(&m)?;
+            let (led, mut rest) = selection.into_target(0)?;
+            if rest.next().is_some() {
+                Err(led_err(LedError::BadSelection(
+                    "LED names can't be combined with selectors",
+                )))?;
+            }
+            LedCommand::Bind { led, binding, once }
+        }
+        _ => unreachable!("command '{}' is missing from the parser", spec.name),
     };
-
-    Ok(Config { command, options })
+    Ok(Command::Led(parsed))
 }
 
 type SuccessMessage = &'static str;
@@ -174,36 +613,229 @@ type SuccessMessage = &'static str;
 pub fn execute(mut conf: Config) -> Result<SuccessMessage, DynError> {
     use Command::*;
 
+    let settings = match conf.command {
+        Status
+        | Get(_)
+        | Watch { .. }
+        | Bar(_)
+        | Restore(_)
+        | Set(_)
+        | SetStream { .. }
+        | Adjust { .. }
+        | Effect { .. }
+        | Preset(PresetCommand::Apply(_))
+        | Cycle(_)
+        | Exec { .. }
+        | Undo(_) => config::Settings::load()?,
+        _ => config::Settings::default(),
+    };
+    if conf.options.device.is_none() {
+        conf.options.device = settings
+            .device()
+            .map(|device| Cow::Owned(device.to_owned()));
+    }
+
     match conf.command {
         Help => print_help(),
         ShortHelp => print_shelp(),
-        List => print_devices(),
-        Setup => setup::run(),
-        Status => print_status(conf.options.device)?,
-        Save => save(conf.options.device)?,
-        Restore => restore()?,
-        Set(v) => {
-            // Same impl as blight::set_bl but with file locking
-            let mut device = new_locked(conf.options.device)?;
-            if v != device.current() {
-                device.write_value(v)?;
+        Usage(spec) => args::print_help(spec),
+        List => print_devices(conf.options.format)?,
+        Setup => setup::run()?,
+        Completions(shell) => print!("{}", shell.script()),
+        Complete(ref words) => completions::print_candidates(words),
+        Status => {
+            let template = parse_template(conf.options.template.as_deref())?;
+            print_status(
+                conf.options.device.take(),
+                conf.options.format,
+                template.as_ref(),
+            )?;
+        }
+        Get(kind) => {
+            let template = parse_template(conf.options.template.as_deref())?;
+            let device = Device::new(conf.options.device.take())?;
+            if let Some(template) = template {
+                println!(
+                    "{}",
+                    template.render(device.name(), device.current(), device.max())
+                );
+                return Ok(gen_success_msg(&conf.command));
+            }
+            match kind {
+                GetKind::Raw => println!("{}", device.current()),
+                GetKind::Percent => println!("{}", device.current_percent().round()),
+                GetKind::Max => println!("{}", device.max()),
             }
         }
-        Adjust { dir, value } => {
-            // Same impl as blight::change_bl but with file locking
-            let mut device = new_locked(conf.options.device)?;
-            let change = device.calculate_change(value, dir);
-            if change != device.current() {
-                match conf.options.sweep {
-                    Change::Sweep => device.sweep_write(change, blight::Delay::default())?,
-                    Change::Regular => device.write_value(change)?,
+        Watch { interval } => {
+            let template = parse_template(conf.options.template.as_deref())?;
+            let device = Device::new(conf.options.device.take())?;
+            watch(device, interval, template, conf.options.format)?;
+        }
+        Bar(ref bar) => {
+            let mut device = Device::new(conf.options.device.take())?;
+            let policy = settings
+                .for_device(Some(device.name()))
+                .clamp_policy(device.max());
+            device.set_clamp_policy(policy);
+            bar.run(device, settings.history())?;
+        }
+        Save { ref slot, ref leds } => {
+            saves::save(slot, conf.options.device.as_deref(), leds.as_ref())?
+        }
+        Restore(ref slot) => {
+            let change = settings.for_device(None).change(conf.options.sweep);
+            saves::restore(slot, change, settings.history())?;
+        }
+        Saves(SavesCommand::List) => saves::print_slots()?,
+        Saves(SavesCommand::Delete(ref slot)) => saves::delete(slot)?,
+        Configure(ref cmd) => match cmd {
+            ConfigCommand::Show => config::Settings::load()?.print(),
+            ConfigCommand::Get(key) => match config::Settings::load()?.get(key)? {
+                Some(value) => println!("{value}"),
+                None => Err(BlightError::ConfigUnset(key.clone()))?,
+            },
+            ConfigCommand::Set(key, value) => config::write_key(key, Some(value))?,
+            ConfigCommand::Unset(key) => config::write_key(key, None)?,
+            ConfigCommand::Edit => config::edit()?,
+            ConfigCommand::Path => println!("{}", config::path()?.display()),
+        },
+        Set(level) => {
+            // Same impl as blight::set_bl_clamped but with file locking and the configured curve
+            let (mut device, device_settings) = open_configured(conf.options.device, &settings)?;
+            let v = device_settings.resolve(level, &device);
+            change_configured(
+                &mut device,
+                &device_settings,
+                &settings,
+                v,
+                conf.options.sweep,
+            )?;
+        }
+        SetStream { coalesce } => {
+            // Not locked, as a stream can run for a long time and shouldn't block other instances
+            let mut device = Device::new(conf.options.device.take())?;
+            let device_settings = settings.for_device(Some(device.name()));
+            device.set_clamp_policy(device_settings.clamp_policy(device.max()));
+            let old = device.current();
+            let result = stream::run(coalesce, signal::catch_interrupts(), |lines| {
+                // Another program may have changed the brightness since the last line
+                device.try_reload()?;
+                let mut value = device.current();
+                for line in lines {
+                    match device_settings.resolve_from(line.level, value, &device) {
+                        new if new > device.max() => {
+                            stream::skip(
+                                line.number,
+                                &format!(
+                                    "{new} is larger than the max brightness {}",
+                                    device.max()
+                                ),
+                            );
+                        }
+                        new => value = new,
+                    }
                 }
+                Ok(write_configured(
+                    &mut device,
+                    &device_settings,
+                    value,
+                    conf.options.sweep,
+                )?)
+            });
+            // The whole stream is a single change, recording every line would push out the rest of the history. It's
+            // recorded even if the stream failed midway, as the lines before the error were still written.
+            if settings.history() {
+                history::record_change(&device, old);
+            }
+            result?;
+        }
+        Preset(ref cmd) => match cmd {
+            PresetCommand::List => presets::print()?,
+            PresetCommand::Set(name, level) => presets::set(name, *level)?,
+            PresetCommand::Delete(name) => presets::delete(name)?,
+            PresetCommand::Apply(name) => {
+                let level = presets::get(name)?;
+                let (mut device, device_settings) =
+                    open_configured(conf.options.device.take(), &settings)?;
+                let v = device_settings.resolve(level, &device);
+                change_configured(
+                    &mut device,
+                    &device_settings,
+                    &settings,
+                    v,
+                    conf.options.sweep,
+                )?;
+            }
+        },
+        Cycle(ref names) => {
+            let presets = presets::select(names)?;
+            let (mut device, device_settings) =
+                open_configured(conf.options.device.take(), &settings)?;
+            let values: Vec<u32> = presets
+                .iter()
+                .map(|(_, level)| device_settings.resolve(*level, &device))
+                .collect();
+            let next = values[presets::next(&values, device.current(), device.max())];
+            change_configured(
+                &mut device,
+                &device_settings,
+                &settings,
+                next,
+                conf.options.sweep,
+            )?;
+        }
+        Adjust { dir, value } => {
+            // Same impl as blight::change_bl_clamped but with file locking and the configured step and curve
+            let (mut device, device_settings) = open_configured(conf.options.device, &settings)?;
+            let step = value.or(device_settings.step).ok_or(BlightError::NoStep)?;
+            let change = device_settings.adjust(&device, step, dir);
+            change_configured(
+                &mut device,
+                &device_settings,
+                &settings,
+                change,
+                conf.options.sweep,
+            )?;
+        }
+        Undo(n) => {
+            let mut device = new_locked(conf.options.device.take())?;
+            let device_settings = settings.for_device(Some(device.name()));
+            // The old value is written as it was, without the clamp policy
+            let value = history::before(device.name(), n)?;
+            write_configured(&mut device, &device_settings, value, conf.options.sweep)?;
+            history::forget(device.name(), n)?;
+        }
+        History => history::print(conf.options.device.as_deref())?,
+        Exec { level, ref command } => {
+            // Not locked, so the brightness can still be changed while the command runs
+            let mut device = Device::new(conf.options.device.take())?;
+            let device_settings = settings.for_device(Some(device.name()));
+            device.set_clamp_policy(device_settings.clamp_policy(device.max()));
+            let mut guard = device.guard();
+            if matches!(device_settings.change(conf.options.sweep), Change::Sweep) {
+                guard = guard.sweep(device_settings.sweep_delay);
             }
+            let value = device_settings.resolve(level, &guard);
+            write_configured(&mut guard, &device_settings, value, conf.options.sweep)?;
+            // Restore even if the command failed, but report its error first
+            let result = run_command(command);
+            let restored = guard.restore();
+            result?;
+            restored?;
+        }
+        Effect { waveform, ref opts } => {
+            let mut device = new_locked(conf.options.device.take())?;
+            opts.build(waveform)
+                .run_until(&mut device, signal::catch_interrupts())?;
         }
         Led(ref mut cmd) => {
             use blight::led;
             match cmd {
-                LedCommand::Toggle { led, kind } => {
+                LedCommand::Toggle {
+                    led: LedTarget::Name(led),
+                    kind,
+                } => {
                     let state = match kind {
                         ToggleKind::Toggle => !(led::get_led_state(led)?),
                         ToggleKind::On => true,
@@ -211,11 +843,110 @@ pub fn execute(mut conf: Config) -> Result<SuccessMessage, DynError> {
                     };
                     led::set_led_state(led, state)?;
                 }
-                LedCommand::Set { led, value } => {
-                    led::set_led_value(led, *value)?;
+                LedCommand::Toggle {
+                    led: LedTarget::Group(filter),
+                    kind,
+                } => {
+                    let mut group = led::LedGroup::new(filter)?;
+                    match kind {
+                        ToggleKind::Toggle => group.toggle()?,
+                        ToggleKind::On => group.set_state(true)?,
+                        ToggleKind::Off => group.set_state(false)?,
+                    }
+                }
+                LedCommand::Set {
+                    led: LedTarget::Name(led),
+                    value,
+                } => {
+                    let mut led = led::Led::new(led.as_str().into())?;
+                    let value = value.resolve_for(&led);
+                    if value != led.current() {
+                        led.write_value(value)?;
+                    }
+                }
+                LedCommand::Set {
+                    led: LedTarget::Group(filter),
+                    value,
+                } => {
+                    led::LedGroup::new(filter)?.set_level(*value)?;
                 }
-                LedCommand::Info(led) => print_led_info(led)?,
-                LedCommand::List { raw, filter } => print_led_list(*raw, filter.take())?,
+                LedCommand::SetStream { led, coalesce } => {
+                    let mut leds = led_targets(led)?.into_leds();
+                    stream::run(*coalesce, signal::catch_interrupts(), |lines| {
+                        for led in &mut leds {
+                            led.try_reload()?;
+                            let value = lines.iter().fold(led.current(), |value, line| {
+                                stream::led_value(line.level, value, led.max(), led.is_dimmable())
+                            });
+                            if value != led.current() {
+                                led.write_value(value)?;
+                            }
+                        }
+                        Ok(())
+                    })?;
+                }
+                LedCommand::Preset { led, name } => {
+                    let level = presets::get(name)?;
+                    led_targets(led)?.set_level(level)?;
+                }
+                LedCommand::Cycle { led, names } => {
+                    let presets = presets::select(names)?;
+                    let mut leds = led_targets(led)?;
+                    // The first LED decides which preset is next, so all of them end up at the same one
+                    let first = leds.iter().next().expect("LED groups are never empty");
+                    let values: Vec<u32> = presets
+                        .iter()
+                        .map(|(_, level)| level.resolve_for(first).min(first.max()))
+                        .collect();
+                    let next = presets::next(&values, first.current(), first.max());
+                    leds.set_level(presets[next].1)?;
+                }
+                LedCommand::Effect {
+                    led,
+                    waveform,
+                    opts,
+                } => {
+                    let mut leds = match led {
+                        LedTarget::Name(name) => match led::Led::new(name.as_str().into())? {
+                            led::LedType::Dimmable(led) => vec![led],
+                            led::LedType::NonDimmable(_) => {
+                                Err(BlightError::Led(LedError::NotDimmable))?
+                            }
+                        },
+                        LedTarget::Group(filter) => {
+                            led::LedGroup::new(&filter.clone().dimmable(true))?
+                                .into_iter()
+                                .filter_map(|led| match led {
+                                    led::LedType::Dimmable(led) => Some(led),
+                                    led::LedType::NonDimmable(_) => None,
+                                })
+                                .collect()
+                        }
+                    };
+                    opts.build(*waveform)
+                        .run_all_until(&mut leds, signal::catch_interrupts())?;
+                }
+                LedCommand::Play {
+                    source,
+                    filter,
+                    names,
+                    check,
+                } => {
+                    play_sequence(source, filter.as_ref(), names, *check)?;
+                }
+                LedCommand::Bind { led, binding, once } => {
+                    let mut leds = match led {
+                        LedTarget::Name(name) => vec![led::Led::new(name.as_str().into())?],
+                        LedTarget::Group(filter) => led::LedGroup::new(filter)?.into_leds(),
+                    };
+                    if *once {
+                        binding.update(&mut leds)?;
+                    } else {
+                        binding.run_until(&mut leds, signal::catch_interrupts())?;
+                    }
+                }
+                LedCommand::Info { led, format } => print_led_info(led.as_ref(), *format)?,
+                LedCommand::List(opts) => print_led_list(std::mem::take(opts))?,
                 LedCommand::Help => print_led_help(),
                 LedCommand::ShortHelp => print_led_shelp(),
             }
@@ -231,14 +962,66 @@ trait Tip: std::error::Error + 'static {
 
 #[derive(Debug)]
 pub enum BlightError {
-    UnrecognisedCommand,
+    Args(args::ArgError),
     MissingValue,
     InvalidValue,
+    InvalidLevel(blight::ParseLevelError),
+    NoDirectory {
+        var: &'static str,
+    },
     CreateSaveDir(PathBuf),
     WriteToSaveFile(PathBuf),
     ReadFromSave(std::io::Error),
-    NoSaveFound,
-    SaveParseErr,
+    DeleteSave(PathBuf),
+    NoSaveFound(String),
+    SaveParseErr(blight::snapshot::ParseSnapshotError),
+    BadSlotName(String),
+    UnknownSavesCommand(String),
+    BadConfig(config::ConfigError),
+    ReadConfig(PathBuf, std::io::Error),
+    CreateConfigDir(PathBuf),
+    WriteConfig(PathBuf),
+    RunEditor(String),
+    ConfigUnset(String),
+    UnknownConfigCommand(String),
+    NoStep,
+    BadPresetName(String),
+    RelativePreset(String),
+    NoPresetFound {
+        name: String,
+        suggestion: Option<String>,
+    },
+    NoPresets,
+    BadPresetsFile {
+        path: PathBuf,
+        line: usize,
+    },
+    ReadPresets(PathBuf, std::io::Error),
+    WritePresets(PathBuf),
+    BadEffectOptions(&'static str),
+    BadBindOptions(&'static str),
+    BadTemplate(String),
+    BadWatchInterval,
+    BadBarOptions(&'static str),
+    BadExecOptions(&'static str),
+    BadStreamOptions(&'static str),
+    ReadHistory(PathBuf, std::io::Error),
+    WriteHistory(PathBuf),
+    NoHistory(String),
+    NotEnoughHistory {
+        device: String,
+        available: usize,
+    },
+    ReadStream(std::io::Error),
+    RunCommand(String, std::io::Error),
+    CommandFailed {
+        command: String,
+        status: std::process::ExitStatus,
+    },
+    UnknownShell(String),
+    SetupFailed {
+        permission_denied: bool,
+    },
     Led(LedError),
 }
 
@@ -246,22 +1029,97 @@ pub enum BlightError {
 pub enum LedError {
     MissingName,
     MissingValue,
-    InvalidValue,
-    BadListOptions(Option<&'static str>),
+    BadListOptions(&'static str),
+    BadSelection(&'static str),
+    NotDimmable,
+    MissingSequence,
+    ReadSequence(String, std::io::Error),
+    BadSequence {
+        source: String,
+        line: String,
+        err: blight::sequence::ParseError,
+    },
+    UntargetedSequence,
 }
 
 impl Tip for BlightError {
     fn tip(&self) -> Option<Cow<'static, str>> {
         use BlightError::*;
         match self {
-            UnrecognisedCommand => Some("try 'blight help' to see all commands".into()),
+            Args(err) => Some(match (err.suggestion(), err.command()) {
+                (Some(suggestion), _) => format!("did you mean '{suggestion}'?").into(),
+                (None, Some(command)) if matches!(err, args::ArgError::UnknownCommand { .. }) => {
+                    format!("try 'blight {command} help' to see all commands").into()
+                }
+                (None, Some(command)) => format!("see 'blight {command} --help' for the available options").into(),
+                (None, None) => "try 'blight help' to see all commands".into(),
+            }),
             InvalidValue => Some("make sure the value is a valid positive integer".into()),
-            NoSaveFound => Some("try using 'blight save' first".into()),
+            NoSaveFound(slot) if slot == saves::DEFAULT_SLOT => Some("try using 'blight save' first".into()),
+            NoSaveFound(slot) => Some(format!("try using 'blight save --as {slot}' first, or 'blight saves' to see all saves").into()),
+            BadSlotName(_) => Some("use letters, digits, '-', '_' and '.' for slot names".into()),
+            UnknownSavesCommand(_) => Some("use 'blight saves list' or 'blight saves delete <slot>'".into()),
+            BadConfig(err) => Some(match (&err.kind, &err.origin) {
+                (config::ErrorKind::UnknownKey { suggestion: Some(key), .. }, _) => format!("did you mean '{key}'?").into(),
+                (config::ErrorKind::UnknownKey { .. }, _) => {
+                    let keys: Vec<_> = config::KEYS.iter().map(|key| key.name).collect();
+                    format!("valid keys are {}", keys.join(", ")).into()
+                }
+                (_, config::Origin::File { .. }) => "fix the config file with 'blight config edit'".into(),
+                (_, config::Origin::Env(var)) => format!("fix or unset ${var}").into(),
+                (_, config::Origin::Arg) => "see 'blight help' for all config keys and their values".into(),
+            }),
+            UnknownConfigCommand(_) => Some("use 'blight config get|set|unset|edit|path'".into()),
+            RunEditor(_) => Some("set $EDITOR to the editor you'd like to use".into()),
+            NoStep => Some("give a value, e.g. 'blight inc 5', or set a default with 'blight config set step 5'".into()),
+            BadPresetName(_) => Some("preset names start with a letter and use letters, digits, '-' and '_'".into()),
+            RelativePreset(_) => Some("use an absolute level such as '40%' or '120'".into()),
+            NoPresetFound { suggestion: Some(name), .. } => Some(format!("did you mean '{name}'?").into()),
+            NoPresetFound { .. } => Some("use 'blight preset list' to see all presets".into()),
+            NoPresets => Some("add one first, e.g. 'blight preset set night 10%'".into()),
+            BadPresetsFile { .. } => Some("presets are 'name = level' lines, e.g. 'night = 10%'".into()),
+            BadBarOptions(_) => Some("see 'blight help' for examples of status bar configs".into()),
+            NoHistory(_) => {
+                Some("changes made with set, inc, dec, preset, cycle and restore are recorded unless 'history = false' is configured".into())
+            }
+            NotEnoughHistory { .. } => Some("use 'blight history' to see the recorded changes".into()),
+            BadStreamOptions(_) => Some("e.g. 'sensor-script | blight set - --coalesce 100' (milliseconds)".into()),
+            BadExecOptions(_) => Some("put the command after '--', e.g. 'blight exec --set 100% -- mpv film.mkv'".into()),
+            RunCommand(_, err) if err.kind() == io::ErrorKind::NotFound => {
+                Some("make sure the command is installed and in your $PATH".into())
+            }
+            UnknownShell(_) => Some("supported shells are bash, zsh and fish".into()),
+            BadTemplate(_) => Some(
+                "use placeholders such as '{percent}%' or '[{bar:20}]', see 'blight help' for all of them".into(),
+            ),
+            Led(LedError::MissingName | LedError::BadSelection(_)) => Some(
+                "provide an LED name, select LEDs with --function, --color, --name or --dimmable, or set them in the config"
+                    .into(),
+            ),
+            Led(LedError::MissingValue) => Some(
+                "use 'blight led info <led>' to see the max brightness supported by the LED".into(),
+            ),
             MissingValue => {
                 Some("try 'blight help' to see all commands and their supported args".into())
             }
             ReadFromSave(_) => Some("make sure you have read permission for the save file".into()),
-            SaveParseErr => Some("delete the save file and try save-restore again".into()),
+            NoDirectory { var } => Some(format!("set ${var} or $HOME to the directory blight should use").into()),
+            SaveParseErr(_) => Some("delete the save file and try save-restore again".into()),
+            BadBindOptions(_) => Some("try 'blight led help' to see all bind options".into()),
+            BadEffectOptions(_) => {
+                Some("try 'blight help' to see all effect options and their supported values".into())
+            }
+            Led(LedError::MissingSequence | LedError::BadSequence { .. }) => {
+                Some("see 'blight led help' for the sequence syntax and examples".into())
+            }
+            Led(LedError::UntargetedSequence) => Some(
+                "provide LED names or selectors after the sequence, or limit steps to LEDs with '@<name>'"
+                    .into(),
+            ),
+            Led(LedError::NotDimmable) => Some(
+                "use 'blight led info <led>' to check if an LED is dimmable, or 'blight led toggle' to switch it on/off"
+                    .into(),
+            ),
             _ => None,
         }
     }
@@ -269,35 +1127,116 @@ impl Tip for BlightError {
 
 impl std::fmt::Display for BlightError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use std::os::unix::process::ExitStatusExt;
         use BlightError::*;
         match self {
-            UnrecognisedCommand => write!(f, "unrecognised command entered"),
+            Args(err) => write!(f, "{err}"),
             MissingValue => write!(f, "required argument was not provided for the command"),
             InvalidValue => write!(f, "invalid value provided"),
+            InvalidLevel(err) => write!(f, "{err}"),
+            NoDirectory { var } => write!(
+                f,
+                "could not find a directory for blight's files, neither ${var} nor $HOME is set"
+            ),
             CreateSaveDir(loc) => write!(f, "failed to create save directory at {}", loc.display()),
             WriteToSaveFile(loc) => write!(f, "failed to write to save file at {}", loc.display()),
             ReadFromSave(err) => write!(f, "failed to read from save file\n{err}"),
-            NoSaveFound => write!(f, "no save file found"),
-            SaveParseErr => write!(f, "failed to parse saved brightness value"),
+            DeleteSave(loc) => write!(f, "failed to delete save file at {}", loc.display()),
+            NoSaveFound(slot) if slot == saves::DEFAULT_SLOT => write!(f, "no save file found"),
+            NoSaveFound(slot) => write!(f, "no save named '{slot}' found"),
+            BadSlotName(slot) => write!(f, "invalid save slot name '{slot}'"),
+            UnknownSavesCommand(cmd) => write!(f, "unrecognised command '{cmd}' for 'saves'"),
+            SaveParseErr(err) => write!(f, "failed to parse save file: {err}"),
+            BadConfig(err) => write!(f, "{err}"),
+            ReadConfig(loc, err) => {
+                write!(f, "failed to read config file at {}\n{err}", loc.display())
+            }
+            CreateConfigDir(loc) => {
+                write!(f, "failed to create config directory at {}", loc.display())
+            }
+            WriteConfig(loc) => write!(f, "failed to write to config file at {}", loc.display()),
+            RunEditor(editor) => write!(f, "failed to run editor '{editor}'"),
+            ConfigUnset(key) => write!(f, "'{key}' is not set"),
+            UnknownConfigCommand(cmd) => write!(f, "unrecognised command '{cmd}' for 'config'"),
+            NoStep => write!(f, "no value provided and no default step configured"),
+            BadPresetName(name) => write!(f, "invalid preset name '{name}'"),
+            RelativePreset(level) => write!(f, "presets can't use relative levels like '{level}'"),
+            NoPresetFound { name, .. } => write!(f, "no preset named '{name}' found"),
+            NoPresets => write!(f, "no presets found"),
+            BadPresetsFile { path, line } => {
+                write!(f, "invalid preset on line {line} of {}", path.display())
+            }
+            ReadPresets(loc, err) => {
+                write!(f, "failed to read presets file at {}\n{err}", loc.display())
+            }
+            WritePresets(loc) => write!(f, "failed to write to presets file at {}", loc.display()),
+            BadEffectOptions(desc) => write!(f, "failed to parse effect options: {desc}"),
+            BadBindOptions(desc) => write!(f, "failed to parse options for 'led bind': {desc}"),
+            BadTemplate(desc) => write!(f, "invalid output format: {desc}"),
+            BadWatchInterval => write!(
+                f,
+                "missing/invalid interval for 'watch' (use a unit, e.g. 500ms or 2s)"
+            ),
+            BadBarOptions(desc) => write!(f, "failed to parse options for 'bar': {desc}"),
+            BadExecOptions(desc) => write!(f, "failed to parse options for 'exec': {desc}"),
+            BadStreamOptions(desc) => write!(f, "failed to parse stream options: {desc}"),
+            ReadHistory(loc, err) => {
+                write!(f, "failed to read history file at {}\n{err}", loc.display())
+            }
+            WriteHistory(loc) => write!(f, "failed to write to history file at {}", loc.display()),
+            NoHistory(device) => write!(f, "no brightness changes recorded for '{device}'"),
+            NotEnoughHistory {
+                device,
+                available: 1,
+            } => write!(f, "only 1 brightness change recorded for '{device}'"),
+            NotEnoughHistory { device, available } => {
+                write!(
+                    f,
+                    "only {available} brightness changes recorded for '{device}'"
+                )
+            }
+            ReadStream(err) => write!(f, "failed to read levels from stdin\n{err}"),
+            RunCommand(command, err) => write!(f, "failed to run '{command}'\n{err}"),
+            CommandFailed { command, status } => match (status.code(), status.signal()) {
+                (Some(code), _) => write!(f, "'{command}' exited with status {code}"),
+                (None, Some(signum)) => write!(f, "'{command}' was terminated by signal {signum}"),
+                (None, None) => write!(f, "'{command}' failed"),
+            },
+            UnknownShell(shell) => write!(f, "no completions available for shell '{shell}'"),
+            SetupFailed { .. } => write!(f, "setup did not complete, see the failed steps above"),
             Led(err) => match err {
                 LedError::MissingName => write!(f, "no LED name provided"),
                 LedError::MissingValue => {
+                    write!(f, "no LED brightness value was provided")
+                }
+                LedError::BadListOptions(desc) => {
+                    write!(f, "failed to parse options for 'led list' command: {desc}")
+                }
+                LedError::BadSelection(desc) => {
+                    write!(f, "failed to parse LED selection: {desc}")
+                }
+                LedError::NotDimmable => {
+                    write!(f, "effects can only be run on dimmable LEDs")
+                }
+                LedError::MissingSequence => {
                     write!(
                         f,
-                        "no valid LED brightness value between 0-255 was provided"
+                        "no LED sequence file provided (use '-' to read from stdin)"
                     )
                 }
-                LedError::InvalidValue => {
+                LedError::ReadSequence(source, err) => {
+                    write!(f, "failed to read LED sequence from {source}\n{err}")
+                }
+                LedError::BadSequence { source, line, err } => {
+                    let caret = format!("{:>1$}", "^", err.column());
                     write!(
                         f,
-                        "invalid LED brightness value. Value should be between 0-255"
+                        "invalid LED sequence in {source} at {err}\n    {line}\n    {}",
+                        caret.red().bold()
                     )
                 }
-                LedError::BadListOptions(Some(desc)) => {
-                    write!(f, "failed to parse options for 'led list' command: {desc}")
-                }
-                LedError::BadListOptions(None) => {
-                    write!(f, "unknown options/filters provided for 'led list' command")
+                LedError::UntargetedSequence => {
+                    write!(f, "no LEDs to play the sequence on")
                 }
             },
         }
@@ -341,6 +1280,109 @@ pub fn print_err(e: DynError) {
     }
 }
 
+/// Process exit codes for each class of errors, listed in 'blight help'
+#[derive(Debug, Clone, Copy)]
+enum ExitStatus {
+    Failure = 1,
+    Usage = 2,
+    NotFound = 3,
+    PermissionDenied = 4,
+    Locked = 5,
+    Io = 6,
+}
+
+impl ExitStatus {
+    fn of_blight_error(err: &blight::Error) -> Self {
+        use blight::ErrorKind::*;
+        use std::error::Error;
+        let source = err
+            .source()
+            .and_then(|s| s.downcast_ref::<std::io::Error>());
+        if source.is_some_and(|s| s.kind() == std::io::ErrorKind::PermissionDenied) {
+            return Self::PermissionDenied;
+        }
+        match err.kind() {
+            NotFound => Self::NotFound,
+            ValueTooLarge { .. } => Self::Usage,
+            LockError { .. } => Self::Locked,
+            ReadDir { .. }
+            | ReadMax
+            | ReadCurrent
+            | WriteValue { .. }
+            | SweepError
+            | ReadDeviceInfo => Self::Io,
+            RunCommand { .. } | CommandOutput { .. } => Self::Failure,
+            // Kinds added by newer versions of the library
+            _ => Self::Failure,
+        }
+    }
+
+    fn of_cli_error(err: &BlightError) -> Self {
+        use BlightError::*;
+        match err {
+            Args(_)
+            | MissingValue
+            | InvalidValue
+            | InvalidLevel(_)
+            | BadEffectOptions(_)
+            | BadBindOptions(_)
+            | BadTemplate(_)
+            | BadWatchInterval
+            | BadBarOptions(_)
+            | BadExecOptions(_)
+            | BadStreamOptions(_)
+            | UnknownShell(_)
+            | BadSlotName(_)
+            | UnknownSavesCommand(_)
+            | BadConfig(_)
+            | UnknownConfigCommand(_)
+            | NoStep
+            | BadPresetName(_)
+            | RelativePreset(_)
+            | BadPresetsFile { .. } => Self::Usage,
+            NoSaveFound(_) | NoPresetFound { .. } | NoPresets => Self::NotFound,
+            NoHistory(_) | NotEnoughHistory { .. } => Self::NotFound,
+            ReadHistory(..) | WriteHistory(_) => Self::Io,
+            ReadPresets(..) | WritePresets(_) => Self::Io,
+            CreateSaveDir(_) | WriteToSaveFile(_) | ReadFromSave(_) | DeleteSave(_) => Self::Io,
+            ReadConfig(..) | CreateConfigDir(_) | WriteConfig(_) => Self::Io,
+            RunEditor(_) | ConfigUnset(_) => Self::Failure,
+            SaveParseErr(_) | NoDirectory { .. } => Self::Failure,
+            RunCommand(_, err) => match err.kind() {
+                std::io::ErrorKind::NotFound => Self::NotFound,
+                std::io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+                _ => Self::Failure,
+            },
+            CommandFailed { .. } => Self::Failure,
+            ReadStream(_) => Self::Io,
+            SetupFailed {
+                permission_denied: true,
+            } => Self::PermissionDenied,
+            SetupFailed {
+                permission_denied: false,
+            } => Self::Failure,
+            Led(LedError::ReadSequence(_, err)) => match err.kind() {
+                std::io::ErrorKind::NotFound => Self::NotFound,
+                std::io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+                _ => Self::Io,
+            },
+            Led(_) => Self::Usage,
+        }
+    }
+}
+
+/// Exit code of the process for the given error
+pub fn exit_code(e: &DynError) -> ExitCode {
+    let status = if let Some(err) = e.downcast_ref::<blight::Error>() {
+        ExitStatus::of_blight_error(err)
+    } else if let Some(err) = e.downcast_ref::<BlightError>() {
+        ExitStatus::of_cli_error(err)
+    } else {
+        ExitStatus::Failure
+    };
+    ExitCode::from(status as u8)
+}
+
 pub fn print_ok(msg: &str) {
     if !msg.is_empty() {
         println!("{} {msg}", "Success".green().bold())
@@ -350,16 +1392,27 @@ pub fn print_ok(msg: &str) {
 fn gen_success_msg(cm: &Command) -> SuccessMessage {
     use Command::*;
     match cm {
-        Save => "Current backlight state saved",
-        Restore => "Saved backlight state restored",
+        Save { .. } => "Current backlight state saved",
+        Restore(_) => "Saved backlight state restored",
+        Saves(SavesCommand::Delete(_)) => "Save deleted",
+        Configure(ConfigCommand::Set(..) | ConfigCommand::Unset(_)) => "Config updated",
         Set(_) => "Backlight value set",
         Adjust { .. } => "Backlight changed",
+        Preset(PresetCommand::Apply(_)) | Cycle(_) => "Brightness preset applied",
+        Exec { .. } => "Brightness restored",
+        Undo(1) => "Last brightness change undone",
+        Undo(_) => "Brightness changes undone",
+        Preset(PresetCommand::Set(..)) => "Preset saved",
+        Preset(PresetCommand::Delete(_)) => "Preset deleted",
         Led(LedCommand::Toggle { kind, .. }) => match kind {
             ToggleKind::Toggle => "LED toggled",
             ToggleKind::On => "LED toggled on",
             ToggleKind::Off => "LED toggled off",
         },
         Led(LedCommand::Set { .. }) => "LED value set",
+        Led(LedCommand::Preset { .. } | LedCommand::Cycle { .. }) => "Brightness preset applied",
+        Led(LedCommand::Play { check: true, .. }) => "LED sequence is valid",
+        Led(LedCommand::Bind { once: true, .. }) => "LED updated",
         _ => "",
     }
 }
@@ -371,10 +1424,39 @@ fn check_write_perm(device_name: &str, bldir: &str) -> Result<(), std::io::Error
         .and(Ok(()))
 }
 
+fn parse_template(template: Option<&str>) -> Result<Option<Template>, BlightError> {
+    template
+        .map(Template::parse)
+        .transpose()
+        .map_err(BlightError::BadTemplate)
+}
 
-
-pub fn print_status(device_name: Option<Cow<str>>) -> blight::Result<()> {
+pub fn print_status(
+    device_name: Option<Cow<str>>,
+    format: Format,
+    template: Option<&Template>,
+) -> blight::Result<()> {
     let device = Device::new(device_name)?;
+    if let Some(template) = template {
+        println!(
+            "{}",
+            template.render(device.name(), device.current(), device.max())
+        );
+        return Ok(());
+    }
+    match format {
+        Format::Human => (),
+        Format::Json => {
+            let writable = check_write_perm(device.name(), BLDIR).is_ok();
+            output::print_json("device", output::device_json(&device, Some(writable)));
+            return Ok(());
+        }
+        Format::Porcelain => {
+            let writable = check_write_perm(device.name(), BLDIR).is_ok();
+            output::print_porcelain([output::device_record(&device, Some(writable))]);
+            return Ok(());
+        }
+    }
 
     let write_perm = match check_write_perm(device.name(), BLDIR) {
         Ok(_) => "Ok".green(),
@@ -393,43 +1475,317 @@ pub fn print_status(device_name: Option<Cow<str>>) -> blight::Result<()> {
     Ok(())
 }
 
-fn parse_led_list_options(args: impl IntoIterator<Item = String>) -> Result<(bool, Option<LedListFilter>), LedError> {
+/// Print the brightness of the device whenever it changes, until the output is closed or reading it fails
+fn watch(
+    device: Device,
+    interval: Option<Duration>,
+    template: Option<Template>,
+    format: Format,
+) -> Result<(), DynError> {
+    let template = match template {
+        Some(template) => template,
+        None => Template::parse(WATCH_FORMAT).map_err(BlightError::BadTemplate)?,
+    };
+    let mut out = io::stdout();
+    if format == Format::Porcelain && writeln!(out, "version\t{}", output::SCHEMA_VERSION).is_err()
+    {
+        return Ok(());
+    }
+    let mut watcher = Watcher::new(device);
+    if let Some(interval) = interval {
+        watcher = watcher.interval(interval);
+    }
+    while watcher.wait_until(signal::catch_interrupts())?.is_some() {
+        let device = watcher.light();
+        let line = match format {
+            Format::Json => {
+                output::json_document("device", output::device_json(device, None)).to_string()
+            }
+            Format::Porcelain => output::device_record(device, None).join("\t"),
+            Format::Human => template.render(device.name(), device.current(), device.max()),
+        };
+        // The reader went away (e.g. the output was piped to `head -n 1`)
+        if writeln!(out, "{line}").is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn parse_led_list_options(m: &Matches) -> Result<LedListOptions, LedError> {
     type Filter = LedListFilter;
-    let mut raw = false;
-    let mut filter = None;
-    let mut args = args.into_iter();
+    let mut opts = LedListOptions {
+        format: output_format(m),
+        raw: m.flag("raw"),
+        group: m.flag("group"),
+        device: m.value("device").map(str::to_lowercase),
+        filter: None,
+    };
+    let filter = &mut opts.filter;
     let err = |msg| Err(LedError::BadListOptions(msg));
-    while let Some(arg) = args.next() {
-        match arg.as_str() {
-            "--raw" | "-r" => raw = true,
-            "--function" | "-f" if let Some(func) = args.next() && !func.starts_with("--") => match filter {
-                None => filter = Some(Filter::Function(func.to_lowercase())),
-                Some(Filter::Color(clr)) => filter = Some(Filter::FunctionColor(func.to_lowercase(), clr.to_lowercase())),
-                Some(Filter::Function(_)) => return err(Some("function supplied twice")),
-                _ => return err(None)
+    for (flag, value) in m.iter() {
+        let value = value.unwrap_or_default();
+        match flag {
+            "function" => match filter.take() {
+                None => *filter = Some(Filter::Function(value.to_lowercase())),
+                Some(Filter::Color(clr)) => {
+                    *filter = Some(Filter::FunctionColor(value.to_lowercase(), clr))
+                }
+                Some(Filter::Function(_) | Filter::FunctionColor(..)) => {
+                    return err("function supplied twice")
+                }
+                Some(Filter::Index(_)) => {
+                    return err("indexing is incompatible with color or function filters")
+                }
             },
-            "--function" | "-f" => return err(Some("missing function")),
-            "--color" | "-c" if let Some(color) = args.next() && !color.starts_with("--") => match filter {
-                None => filter = Some(Filter::Color(color.to_lowercase())),
-                Some(Filter::Function(func)) => filter = Some(Filter::FunctionColor(func.to_lowercase(), color.to_lowercase())),
-                Some(Filter::Color(_)) => return err(Some("color supplied twice")),
-                _ => return err(None),
+            "color" => match filter.take() {
+                None => *filter = Some(Filter::Color(value.to_lowercase())),
+                Some(Filter::Function(func)) => {
+                    *filter = Some(Filter::FunctionColor(func, value.to_lowercase()))
+                }
+                Some(Filter::Color(_) | Filter::FunctionColor(..)) => {
+                    return err("color supplied twice")
+                }
+                Some(Filter::Index(_)) => {
+                    return err("indexing is incompatible with color or function filters")
+                }
             },
-            "--color" | "-c" => return err(Some("missing color")),
-            "--index" | "-i" if let Some(idx) = args.next().and_then(|idx| idx.parse::<usize>().ok()) => {
+            "index" => {
+                let Ok(idx) = value.parse() else {
+                    return err("invalid index");
+                };
                 if filter.is_some() {
-                    return err(Some("indexing is incompatible with color or function filters"));
+                    return err("indexing is incompatible with color or function filters");
                 }
-                filter = Some(Filter::Index(idx));
-            },
-            "--index" | "-i" => return err(Some("missing/invalid index")),
-            _ => return err(None),
+                *filter = Some(Filter::Index(idx));
+            }
+            _ => (),
+        }
+    }
+    Ok(opts)
+}
+
+#[derive(Default)]
+struct LedSelection {
+    filter: Option<led::LedFilter>,
+    positional: Vec<String>,
+}
+
+impl LedSelection {
+    /// Build the LED filter from the selector flags, keeping the positional args
+    fn from_matches(m: &Matches) -> Self {
+        let mut filter: Option<led::LedFilter> = None;
+        let mut narrow = |apply: &dyn Fn(led::LedFilter) -> led::LedFilter| {
+            filter = Some(apply(filter.take().unwrap_or_default()));
+        };
+        for (flag, value) in m.iter() {
+            let value = value.unwrap_or_default();
+            match flag {
+                "function" => {
+                    let Ok(func): Result<Function, _> = value.to_lowercase().parse();
+                    narrow(&|f| f.function(func.clone()));
+                }
+                "color" => {
+                    let Ok(color): Result<Color, _> = value.to_lowercase().parse();
+                    narrow(&|f| f.color(color.clone()));
+                }
+                "name" => narrow(&|f| f.device(value)),
+                "dimmable" => narrow(&|f| f.dimmable(true)),
+                _ => (),
+            }
+        }
+        Self {
+            filter,
+            positional: m.positional.clone(),
+        }
+    }
+
+    /// Resolve the target LED(s), returning the remaining positional args
+    ///
+    /// Without a name or selector, the LEDs selected in the config are used if only the `args` positional args of the
+    /// command itself were given, e.g. the level of `blight led set 50%`.
+    fn into_target(
+        self,
+        args: usize,
+    ) -> Result<(LedTarget, std::vec::IntoIter<String>), BlightError> {
+        let filter = match self.filter {
+            None if self.positional.len() == args => config::Settings::load()?.led_filter(),
+            filter => filter,
+        };
+        let mut positional = self.positional.into_iter();
+        let target = match filter {
+            Some(filter) => LedTarget::Group(filter),
+            None => LedTarget::Name(
+                positional
+                    .next()
+                    .ok_or(BlightError::Led(LedError::MissingName))?,
+            ),
+        };
+        Ok((target, positional))
+    }
+}
+
+fn effect_waveform(cmd: &str) -> Waveform {
+    match cmd {
+        "breathe" => Waveform::Breathe,
+        "blink" => Waveform::Blink,
+        "pulse" => Waveform::Pulse,
+        _ => unreachable!("unknown effect command"),
+    }
+}
+
+/// Parses a duration with a unit suffix (e.g. 500ms, 2s, 1.5s, 1m)
+fn parse_duration(arg: &str) -> Option<Duration> {
+    arg.parse::<blight::Delay>().ok().map(|delay| *delay)
+}
+
+/// Parses a brightness percentage between 0 and 100, with an optional % sign
+fn parse_percent(arg: &str) -> Option<f64> {
+    arg.trim_end_matches('%')
+        .parse()
+        .ok()
+        .filter(|p| (0.0..=100.0).contains(p))
+}
+
+/// The value of --interval, `Ok(None)` if it wasn't given and `None` if it's invalid
+fn coalesce(m: &Matches) -> Result<Option<Duration>, BlightError> {
+    m.value("coalesce")
+        .map(|window| {
+            stream::parse_coalesce(window)
+                .ok_or(BlightError::BadStreamOptions("invalid --coalesce interval"))
+        })
+        .transpose()
+}
+
+fn interval(m: &Matches) -> Option<Option<Duration>> {
+    match m.value("interval") {
+        Some(time) => parse_duration(time).map(Some),
+        None => Some(None),
+    }
+}
+
+fn parse_bar_options(m: &Matches, template: Option<&str>) -> Result<bar::Bar, BlightError> {
+    let err = |msg| Err(BlightError::BadBarOptions(msg));
+    let Some(protocol) = m.value("protocol") else {
+        return err("a protocol must be provided with --protocol");
+    };
+    let Some(protocol) = bar::Protocol::from_name(protocol) else {
+        return err("invalid protocol (should be waybar, i3bar or polybar)");
+    };
+    let Some(interval) = interval(m) else {
+        return err("invalid interval (use a unit, e.g. 500ms or 2s)");
+    };
+    let step = match m.value("step").map(|s| s.trim_end_matches('%').parse()) {
+        None => 5,
+        Some(Ok(step)) if (1..=100).contains(&step) => step,
+        Some(_) => return err("invalid step (should be a percentage between 1 and 100)"),
+    };
+    let module = match (protocol, m.value("module")) {
+        (bar::Protocol::Polybar, _) if template.is_some() => {
+            return err("polybar shows the output of its hook, pass --format to the command of the hook instead");
         }
+        (bar::Protocol::Polybar, module) => module.unwrap_or(BAR_MODULE).to_owned(),
+        (_, Some(_)) => return err("--module only works with polybar"),
+        (_, None) => String::new(),
+    };
+    let text = Template::parse(template.unwrap_or(BAR_FORMAT)).map_err(BlightError::BadTemplate)?;
+    Ok(bar::Bar {
+        protocol,
+        interval,
+        step,
+        text,
+        module,
+    })
+}
+
+/// Parse the options of 'led bind', returning the binding and whether to only update once
+fn parse_bind_options(m: &Matches) -> Result<(Binding, bool), BlightError> {
+    let err = |msg| Err(BlightError::BadBindOptions(msg));
+    let Some(command) = m.value("command") else {
+        return err("a command must be provided with --command");
+    };
+    let mut binding = Binding::new(command).invert(m.flag("invert"));
+    match interval(m) {
+        Some(Some(interval)) => binding = binding.interval(interval),
+        Some(None) => (),
+        None => return err("invalid interval (use a unit, e.g. 500ms or 5s)"),
+    }
+    if let Some(range) = m.value("range") {
+        let range = range
+            .split_once(':')
+            .and_then(|(min, max)| Some((min.parse().ok()?, max.parse().ok()?)));
+        let Some((min, max)) = range else {
+            return err("invalid range (e.g. 0:100 or 0:4.5)");
+        };
+        binding = binding.mapping(Mapping::Value { min, max });
+    }
+    Ok((binding, m.flag("once")))
+}
+
+fn play_sequence(
+    source: &str,
+    filter: Option<&led::LedFilter>,
+    names: &[String],
+    check: bool,
+) -> Result<(), DynError> {
+    use blight::sequence::Sequence;
+    let text = if source == "-" {
+        std::io::read_to_string(std::io::stdin())
+    } else {
+        fs::read_to_string(source)
+    }
+    .map_err(|err| BlightError::Led(LedError::ReadSequence(source.to_owned(), err)))?;
+    let bad_sequence = |err: blight::sequence::ParseError| {
+        BlightError::Led(LedError::BadSequence {
+            source: if source == "-" {
+                "stdin".to_owned()
+            } else {
+                source.to_owned()
+            },
+            line: text
+                .lines()
+                .nth(err.line() - 1)
+                .unwrap_or_default()
+                .to_owned(),
+            err,
+        })
+    };
+    let sequence = Sequence::parse(&text).map_err(bad_sequence)?;
+
+    let mut leds = match filter {
+        Some(filter) => led::LedGroup::new(filter)?.into_leds(),
+        None if !names.is_empty() => names
+            .iter()
+            .map(|name| led::Led::new(name.into()))
+            .collect::<Result<_, _>>()?,
+        // Play on all the LEDs named in the sequence itself
+        None if sequence.targets().next().is_some() => led::try_leds()?
+            .into_iter()
+            .filter_map(|(_, led)| led.ok())
+            .filter(|led| sequence.is_targeted(led.name()))
+            .collect(),
+        None => Err(BlightError::Led(LedError::UntargetedSequence))?,
+    };
+    sequence.check_targets(&leds).map_err(bad_sequence)?;
+    if leds.is_empty() {
+        Err(blight::Error::from(blight::ErrorKind::NotFound))?;
+    }
+    if !check {
+        sequence.run_until(&mut leds, signal::catch_interrupts())?;
+    }
+    Ok(())
+}
+
+/// Kernel LED names use an empty string for a missing color or function
+fn or_unknown(name: &str) -> &str {
+    if name.is_empty() {
+        "unknown"
+    } else {
+        name
     }
-    Ok((raw, filter))
 }
 
-fn print_led_list(raw: bool, filter: Option<LedListFilter>) -> blight::Result<()> {
+fn print_led_list(opts: LedListOptions) -> blight::Result<()> {
     let mut names = led::led_names()?;
     let mut col_len = 0;
     names.sort_by(|a, b| {
@@ -439,100 +1795,297 @@ fn print_led_list(raw: bool, filter: Option<LedListFilter>) -> blight::Result<()
         a.raw_name().cmp(b.raw_name())
     });
 
-    let pretty_print_led = |led: &led::LedName| {
+    let pretty_print_led = |led: &led::LedName, device: Option<&led::DeviceInfo>| {
+        let device = device
+            .map(|d| format!(", device: {}", d.display_name().cyan()))
+            .unwrap_or_default();
         println!(
-            "{raw:col_len$}\t[name: {parsed}, color: {color}, function: {fun}]",
-            raw = led.raw_name().green(), fun = format!("{:?}", led.function()).yellow(), color = format!("{:?}", led.color()).magenta(), parsed = led.parsed_name().unwrap_or("Unknown").blue()
-          );
+            "{raw:col_len$}\t[name: {parsed}, color: {color}, function: {fun}{device}]",
+            raw = led.raw_name().green(),
+            fun = or_unknown(led.function().as_str()).yellow(),
+            color = or_unknown(led.color().as_str()).magenta(),
+            parsed = led.parsed_name().unwrap_or("Unknown").blue()
+        );
     };
-    if let Some(filter) = filter {
-        // NOTE: the function and color filtering is done in a very inefficient way to avoid complexity and additional dependencies.
-        // The current implementation is considered good enough as the performance in this context is unlikely to be an issue.
+    if let Some(filter) = opts.filter {
         match filter {
-            LedListFilter::Function(function) => 
-                names.retain(|n| format!("{:?}", n.function()).to_lowercase().starts_with(&function))
-            ,
-            LedListFilter::Color(color) => 
-                names.retain(|n| format!("{:?}", n.color()).to_lowercase().starts_with(&color))
-            ,
-            LedListFilter::FunctionColor(function, color) => 
-                names.retain(|n| {
-                let dbg_str = format!("{:?},{:?}", n.function(), n.color());
-                let (fun, clr) = dbg_str.split_once(',').expect("comma doesn't exist in the format string");
-                fun.to_lowercase().starts_with(&function) && clr.to_lowercase().starts_with(&color)
+            LedListFilter::Function(function) => {
+                names.retain(|n| n.function().as_str().starts_with(&function))
+            }
+            LedListFilter::Color(color) => names.retain(|n| n.color().as_str().starts_with(&color)),
+            LedListFilter::FunctionColor(function, color) => names.retain(|n| {
+                n.function().as_str().starts_with(&function)
+                    && n.color().as_str().starts_with(&color)
             }),
             LedListFilter::Index(idx) => {
                 // Replace list of names with a vec containing single element of the requested index or an empty vec if index is out of bounds
-                names = idx.checked_sub(1).and_then(|idx| (idx < names.len()).then_some(vec![names.swap_remove(idx)])).unwrap_or_default();
-            },
-            
+                names = idx
+                    .checked_sub(1)
+                    .and_then(|idx| (idx < names.len()).then_some(vec![names.swap_remove(idx)]))
+                    .unwrap_or_default();
+            }
         }
     }
 
-    if !raw {
-        println!("{}", "Detected LED Devices".bold());
+    if opts.raw && opts.device.is_none() && opts.format == Format::Human {
+        for led in &names {
+            println!("{}", led.raw_name());
+        }
+        return Ok(());
     }
-    for (i, led) in names.iter().enumerate() {
-        if raw {
-            println!("{}", led.raw_name());            
-        } else {
-            print!("({n}) ", n = i + 1);
-            pretty_print_led(led);
+    let mut entries: Vec<_> = led::try_leds_from_names(names)
+        .into_iter()
+        .map(|(name, led)| {
+            let device = led.as_ref().ok().and_then(|led| led.device_info().ok());
+            (name, led, device)
+        })
+        .collect();
+    if let Some(pattern) = &opts.device {
+        entries.retain(|(_, _, device)| {
+            device
+                .as_ref()
+                .is_some_and(|d| d.display_name().to_lowercase().contains(pattern.as_str()))
+        });
+    }
+    match opts.format {
+        Format::Human => (),
+        Format::Json => {
+            let leds = entries.iter().map(|(name, led, device)| match led {
+                Ok(led) => output::led_json(led, device.as_ref(), None),
+                Err(err) => output::error_json(name.raw_name(), err),
+            });
+            output::print_json("leds", output::Json::Array(leds.collect()));
+            return Ok(());
+        }
+        Format::Porcelain => {
+            output::print_porcelain(entries.iter().map(|(name, led, device)| match led {
+                Ok(led) => output::led_record(led, device.as_ref(), None),
+                Err(err) => output::error_record(name.raw_name(), err),
+            }));
+            return Ok(());
+        }
+    }
+    if opts.raw {
+        for (name, ..) in &entries {
+            println!("{}", name.raw_name());
+        }
+        return Ok(());
+    }
+
+    println!("{}", "Detected LED Devices".bold());
+    if opts.group {
+        // Group LEDs by the physical device they belong to, keeping the order in which the devices were first seen
+        let mut groups: Vec<(Option<&led::DeviceInfo>, Vec<usize>)> = vec![];
+        for (i, (_, _, device)) in entries.iter().enumerate() {
+            let device = device.as_ref();
+            match groups
+                .iter_mut()
+                .find(|(d, _)| d.map(led::DeviceInfo::path) == device.map(led::DeviceInfo::path))
+            {
+                Some((_, members)) => members.push(i),
+                None => groups.push((device, vec![i])),
+            }
+        }
+        // LEDs without a known device are listed last
+        groups.sort_by_key(|(device, _)| device.is_none());
+        let mut n = 0;
+        for (device, members) in groups {
+            match device {
+                Some(device) => println!("{}", describe_device(device).bold()),
+                None => println!("{}", "Unknown device".bold()),
+            }
+            for i in members {
+                n += 1;
+                let (name, led, _) = &entries[i];
+                print!("  ({n}) ");
+                pretty_print_led(name, None);
+                if let Err(err) = led {
+                    println!("  {:col_len$}\t{} {err}", "", "Error:".red().bold());
+                }
+            }
+        }
+        return Ok(());
+    }
+    for (i, (name, led, device)) in entries.iter().enumerate() {
+        print!("({n}) ", n = i + 1);
+        pretty_print_led(name, device.as_ref());
+        if let Err(err) = led {
+            println!("{:col_len$}\t{} {err}", "", "Error:".red().bold());
         }
     }
     Ok(())
 }
 
-pub fn print_led_info(name: &str) -> blight::Result<()> {
-    fn print_info(
-        led: &impl blight::Light,
-        parsed_name: Option<&str>,
-        color: Color,
-        func: Function,
-        dimmable: bool,
-    ) {
-        let state = if u32::try_from(led.current()).unwrap() == 0 { "off" } else { "on" }.green();
+/// Device name followed by its bus and vendor:product IDs (if available)
+fn describe_device(device: &led::DeviceInfo) -> String {
+    let mut desc = device.display_name().into_owned();
+    let ids = match (device.bus(), device.vendor(), device.product()) {
+        (Some(bus), Some(vendor), Some(product)) => format!("{bus} {vendor:04x}:{product:04x}"),
+        (Some(bus), ..) => bus.to_string(),
+        (None, ..) => device.subsystem().unwrap_or_default().to_owned(),
+    };
+    if !ids.is_empty() {
+        desc.push_str(&format!(" ({ids})"));
+    }
+    desc
+}
+
+fn print_led_info(target: Option<&LedTarget>, format: Format) -> blight::Result<()> {
+    fn print_info(led: &led::LedType) {
+        let name = led.led_name();
+        let state = if led.current() == 0 { "off" } else { "on" }.green();
         let write = if let Err(err) = check_write_perm(led.name(), led::LEDDIR) {
             err.to_string().red()
         } else {
             "Ok".green()
         };
         println!(
-            "{title}\nName: {name}\nWrite permission: {write}\nState: {state}\nCurrent brightness: {current}\nMax brightness: {max}\nDimmable: {dim}\nParsed name: {parsed}\nColor: {color}\nFunction: {func}",
+            "{title}\nName: {name}\nWrite permission: {write}\nState: {state}\nCurrent brightness: {current}\nMax brightness: {max}\nDimmable: {dim}\nParsed name: {parsed}\nColor: {color}\nFunction: {func}\nDevice: {device}",
             title = "LED Device Info".bold(),
             name = led.name().green(),
             current = led.current().to_string().green(),
-            max =led.max().to_string().green(), 
-            dim = dimmable.to_string().green(),
-            parsed =parsed_name.unwrap_or("None").green(), 
-            color =format!("{color:?}").green(), 
-            func =format!("{func:?}").green() 
+            max = led.max().to_string().green(),
+            dim = led.is_dimmable().to_string().green(),
+            parsed = name.parsed_name().unwrap_or("None").green(),
+            color = or_unknown(name.color().as_str()).green(),
+            func = or_unknown(name.function().as_str()).green(),
+            device = led.device_info().map_or("Unknown".into(), |d| describe_device(&d)).green(),
+        );
+    }
+    fn print_broken(name: &led::LedName, err: &blight::Error) {
+        println!(
+            "{title}\nName: {name}\n{error} {err}",
+            title = "LED Device Info".bold(),
+            name = name.raw_name().red(),
+            error = "Error:".red().bold(),
         );
     }
-    match led::Led::new(name.into())? {
-        led::LedType::Dimmable(led) => {
-            print_info(&led, led.parsed_name(), led.color(), led.function(), true)
+
+    let entries = match target {
+        Some(LedTarget::Name(name)) => {
+            let led = led::Led::new(name.into())?;
+            vec![(led.led_name().clone(), Ok(led))]
+        }
+        Some(LedTarget::Group(filter)) => led_entries(filter)?,
+        None => led_entries(&led::LedFilter::new())?,
+    };
+    if entries.is_empty() {
+        return Err(blight::ErrorKind::NotFound.into());
+    }
+    let writable = |led: &led::LedType| Some(check_write_perm(led.name(), led::LEDDIR).is_ok());
+    match format {
+        Format::Human => {
+            for (i, (name, led)) in entries.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                match led {
+                    Ok(led) => print_info(led),
+                    Err(err) => print_broken(name, err),
+                }
+            }
+        }
+        Format::Json => {
+            let leds = entries.iter().map(|(name, led)| match led {
+                Ok(led) => output::led_json(led, led.device_info().ok().as_ref(), writable(led)),
+                Err(err) => output::error_json(name.raw_name(), err),
+            });
+            output::print_json("leds", output::Json::Array(leds.collect()));
         }
-        led::LedType::NonDimmable(led) => {
-            print_info(&led, led.parsed_name(), led.color(), led.function(), false)
+        Format::Porcelain => {
+            output::print_porcelain(entries.iter().map(|(name, led)| match led {
+                Ok(led) => output::led_record(led, led.device_info().ok().as_ref(), writable(led)),
+                Err(err) => output::error_record(name.raw_name(), err),
+            }));
         }
     }
     Ok(())
 }
 
+/// All LEDs matching the filter sorted by name, including the ones that failed to initialize
+fn led_entries(
+    filter: &led::LedFilter,
+) -> blight::Result<Vec<(led::LedName<'static>, blight::Result<led::LedType>)>> {
+    let mut names = led::led_names()?;
+    names.retain(|n| filter.matches_name(n));
+    names.sort_by(|a, b| a.raw_name().cmp(b.raw_name()));
+    let mut entries = led::try_leds_from_names(names);
+    entries.retain(|(_, led)| led.as_ref().is_ok_and(|led| filter.matches(led)) || led.is_err());
+    Ok(entries)
+}
+
 pub fn print_led_help() {
-    let flags = "Flags: raw [--raw, -r], on [--on], off [--off]
-Opts: function [--function <name>, -f <name>], color [--color <name>, -c <name>], index [--index <n>, -i <n>]
+    let flags = "Flags: raw [--raw, -r], group [--group, -g], on [--on], off [--off], dimmable [--dimmable], check [--check],
+       json [--json], porcelain [--porcelain]
+Opts: function [--function <name>, -f <name>], color [--color <name>, -c <name>], index [--index <n>, -i <n>],
+      name [--name <pattern>, -n <pattern>], device [--device <name>, -d <name>]
     Raw shows only the LED device sysfs name without any formatting.
+    Group groups the LED list by the physical device the LEDs belong to.
+    Device filters LED list by the name of the physical device (e.g. 'AT Translated').
     On and off specify if an LED should be either toggled on or off.
     Function filters LED list function (e.g. 'kbd', 'numlock', 'scrolllock').
     Color filters LED list by color (e.g. 'red', 'white').
-    Index selects a specific LED by its position in the list.";
+    Index selects a specific LED by its position in the list.
+    Selectors (function, color, name, dimmable) can be used in place of <led> with 'set', 'preset', 'cycle' and 'toggle'
+    to change all matching LEDs at once. Function and color must be exact kernel names when used as selectors.
+    Name matches the device part of the LED name against a glob pattern (e.g. 'input*').
+    Effects (breathe, blink, pulse) accept the same effect opts as the backlight effects (see 'blight help')
+    and only work with dimmable LEDs.
+    Check only validates a sequence file without playing it.
+    Json and porcelain print machine-readable output for 'list' and 'info' (see 'blight help').
+
+Sequence syntax: steps separated by ';' or new lines, '#' starts a comment
+    on [<time>], off [<time>]    set max brightness or turn off, then wait
+    set <value> [<time>]         set a raw value (e.g. 128), percentage (e.g. 50%) or fraction (e.g. 0.5), then wait
+    fade [<from>->]<to> <time>   gradually change brightness (e.g. fade 0->100% 1s)
+    wait <time>                  keep the current brightness
+    repeat <n>|forever           repeat all steps since the start (or the previous repeat)
+    @<led>[,<led>...] <step>     limit a step to specific LEDs ('*' and '?' wildcards are supported)
+    Sequences without '@' apply to all LEDs given after the file.
+
+Bind opts: command [--command <cmd>], interval [--interval <time>], range [--range <min>:<max>],
+           invert [--invert], once [--once]
+    Command is run with 'sh -c' every interval (5s by default). The LED is turned on if it succeeds, and off otherwise.
+    Range maps the number printed by the command to the LED brightness instead (min = off, max = full brightness).
+    Invert flips the result (e.g. to turn an LED on when a command fails).
+    Once runs the command and updates the LED a single time, instead of until interrupted.";
     let commands: String = [
-        ("list [flags: raw] [opts: function, color, index]", "-> list all LED devices"),
-        ("info <led>", "-> show LED device info"),
-        ("set <led> <val>", "-> set LED brightness (0-255)"),
-        ("toggle <led> [flags: on, off]", "-> toggle LED state"),
+        (
+            "list [flags: raw, group, json, porcelain] [opts: function, color, index, device]",
+            "-> list all LED devices",
+        ),
+        (
+            "info [<led>|<selectors>] [flags: json, porcelain]",
+            "-> show LED device info (all LEDs if none are specified or set in the config)",
+        ),
+        (
+            "set <led>|<selectors> <level>|- [opts: coalesce]",
+            "-> set LED brightness (same levels as 'blight set', '-' reads stdin)",
+        ),
+        (
+            "toggle <led>|<selectors> [flags: on, off]",
+            "-> toggle LED state",
+        ),
+        (
+            "preset <led>|<selectors> <preset>",
+            "-> set LED brightness to a preset (see 'blight help')",
+        ),
+        (
+            "cycle <led>|<selectors> [<preset>...]",
+            "-> switch LEDs to the next preset (all presets by default)",
+        ),
+        (
+            "breathe|blink|pulse <led>|<selectors> [effect opts]",
+            "-> run a brightness effect",
+        ),
+        (
+            "play <file>|- [<led>...|<selectors>] [flags: check]",
+            "-> play an LED sequence",
+        ),
+        (
+            "bind <led>|<selectors> --command <cmd> [bind opts]",
+            "-> drive an LED with a command",
+        ),
         ("help", "-> display help"),
     ]
     .into_iter()
@@ -544,17 +2097,30 @@ Examples:
     blight led list (list all LED devices)
     blight led list --function kbd (list keyboard LEDs)
     blight led list --raw (show raw sysfs names)
+    blight led list --group (list LEDs grouped by the keyboard or device they belong to)
     blight led info input3::capslock (show LED info)
+    blight led list --function kbd_backlight --json (list keyboard backlights as JSON)
     blight led set input3::capslock 255 (set LED max brightness)
+    blight led set platform::kbd_backlight +10% (increase LED brightness by 10%)
     blight led toggle input3::capslock --off (turn LED off)
-    blight led toggle $(blight led list -i 1 -r) (toggle 1st LED from the list on/off)";
+    blight led toggle $(blight led list -i 1 -r) (toggle 1st LED from the list on/off)
+    blight led toggle --function capslock (toggle all capslock LEDs together)
+    blight led set --function kbd_backlight 0 (turn off all keyboard backlight zones)
+    blight led cycle --function kbd_backlight off dim bright (step through keyboard backlight presets)
+    blight led breathe --function kbd_backlight --period 4s (slowly breathe the keyboard backlight)
+    blight led blink platform::kbd_backlight --times 5 (blink the keyboard backlight 5 times)
+    echo 'on 200ms; off 100ms; repeat 3' | blight led play - --function capslock (blink capslock LEDs 3 times)
+    blight led play status.seq --check (validate a sequence file)
+    blight led bind input3::scrolllock --command 'ping -c1 -W1 example.com' --invert --interval 30s (turn LED on when offline)
+    blight led bind platform::kbd_backlight --command 'cut -d\" \" -f1 /proc/loadavg' --range 0:4 (show CPU load)";
 
     println!(
-        "{t}\n\n{f}\n\n{ct}\n{commands}\n{e}",
+        "{t}\n\n{f}\n\n{ct}\n{commands}\n{e}\n\n{h}",
         t = "LED Commands".blue().bold(),
         f = flags.magenta(),
         ct = "Commands".bold(),
-        e = examples.bright_yellow()
+        e = examples.bright_yellow(),
+        h = "Use `blight led <command> --help' to display the options of a single command".yellow()
     );
 }
 
@@ -564,6 +2130,7 @@ pub fn print_led_shelp() {
         ("info <led>", "-> show LED info"),
         ("set <led> <value>", "-> set LED brightness"),
         ("toggle <led>", "-> toggle LED on/off"),
+        ("breathe <led>", "-> run a breathing effect"),
     ]
     .into_iter()
     .map(|(c, e)| format!("{} {e}\n", c.green().bold()))
@@ -576,34 +2143,125 @@ pub fn print_led_shelp() {
     );
 }
 
-pub fn print_devices() {
+pub fn print_devices(format: Format) -> blight::Result<()> {
+    let devices = blight::try_devices()?;
+    match format {
+        Format::Human => (),
+        Format::Json => {
+            let devices = devices.iter().map(|(name, device)| match device {
+                Ok(device) => output::device_json(device, None),
+                Err(err) => output::error_json(name, err),
+            });
+            output::print_json("devices", output::Json::Array(devices.collect()));
+            return Ok(());
+        }
+        Format::Porcelain => {
+            output::print_porcelain(devices.iter().map(|(name, device)| match device {
+                Ok(device) => output::device_record(device, None),
+                Err(err) => output::error_record(name, err),
+            }));
+            return Ok(());
+        }
+    }
     println!("{}", "Detected Devices".bold());
-    fs::read_dir(BLDIR)
-        .expect("Failed to read Backlight Directory")
-        .for_each(|d| println!("{}", d.unwrap().file_name().to_string_lossy().green()));
+    for (name, device) in devices {
+        match device {
+            Ok(_) => println!("{}", name.green()),
+            Err(err) => println!("{} {} {err}", name.red(), "Error:".red().bold()),
+        }
+    }
+    Ok(())
 }
 
 pub fn print_help() {
     let title = "blight: A backlight utility for Linux that plays well with hybrid GPUs";
     let quote = "\"And man said, \'let there b-light\' and there was light.\" - Some Book 1:3";
-    let flags = "Flags: sweep [--sweep, -s] Opts: device [--device <name>, -d <name>]
-    Sweep lets you increase brightness gradually, resulting in a smooth change.
-    Device lets you specify a backlight device target other than the default one.";
+    let flags = "Flags: sweep [--sweep, -s], no-sweep [--no-sweep], json [--json], porcelain [--porcelain]
+Opts: device [--device <name>, -d <name>], format [--format <template>], interval [--interval <time>, -i <time>],
+      coalesce [--coalesce <ms>]
+    Sweep lets you increase brightness gradually, resulting in a smooth change. No-sweep overrides the config.
+    Device lets you specify a backlight device target other than the default one.
+    Flags can be given before or after values, use '--' to pass values starting with '-' (e.g. blight set -- -5%).
+    Json prints a single JSON object, porcelain prints stable tab separated lines meant for scripts.
+    Both formats start with a schema version, which only changes when existing fields are changed or removed.
+    Format prints a custom template instead, using the placeholders {name}, {raw}, {max}, {percent}, {percent_precise}
+    and {bar}. Bars take an optional width and style: {bar:20}, {bar:unicode}, {bar:20:unicode}. Use {{ and }} for braces.
+    Interval sets how often 'watch' and 'bar' check the brightness (250ms by default).
+    Coalesce applies at most one level per interval when reading from stdin, skipping stale ones.
+Bar: i3bar (and swaybar) clicks toggle the backlight and scrolling changes it by --step percent (5 by default).
+    Waybar: {\"exec\": \"blight bar --protocol waybar\", \"return-type\": \"json\", \"on-scroll-up\": \"blight inc 5\", ...}
+    Polybar: type = custom/ipc, hook-0 = blight get --format '{percent}%', initial = 1, scroll-up = blight inc 5, ...
+    and 'blight bar --protocol polybar --module <name>' running in the background to trigger hook-0 on every change.
+Levels: 128 (raw value), 40% (percentage), 0.35 (fraction), +10% or -5% (relative percentage), +10 or -5 (relative raw value)
+Effect opts: period [--period <time>, -p <time>], times [--times <n>, -t <n>], forever [--forever],
+             min [--min <percent>], max [--max <percent>]
+    Period sets the duration of a single cycle of the effect (e.g. 500ms, 2s).
+    Times sets how many cycles to run, forever runs until interrupted with Ctrl-C.
+    Min and max set the brightness range of the effect (0-100%).
+    The original brightness is restored once an effect ends or is interrupted.
+Config: 'key = value' lines in ~/.config/blight/config (see 'blight config path'), [device.<name>] sections override
+    them for a single device. Keys: device, function, color and name (LEDs used by 'led' commands without a name or
+    selector), step (default for inc/dec), sweep (true|false), sweep_delay (e.g. 25ms) or sweep_duration (time to
+    sweep the whole range, e.g. 500ms), curve (linear|perceptual), min and max (raw value or percentage 'set',
+    'inc', 'dec' and 'bar' stay within), history (true|false, records backlight changes made with set, inc, dec,
+    preset, cycle, restore and i3bar clicks for 'undo', a 'set -' stream once it ends; exec, effects and LEDs aren't
+    recorded).
+    Every key can also be set with a BLIGHT_<KEY> environment variable, e.g. BLIGHT_STEP=10. Flags override both.
+Presets: named absolute levels kept in ~/.config/blight/presets, shared by backlights and LEDs.
+    Cycle switches to the preset after the one matching the current brightness (within 1%), or to the next higher one.
+Exit codes: 0 success, 1 other errors, 2 invalid arguments, 3 device/LED/save not found, 4 permission denied,
+            5 brightness file locked by another instance, 6 failed to read/write device, save or config files";
     let commands: String = [
-        ("inc <val> [flags: sweep] [opts: device]", "-> increase brightness"),
-        ("dec <val> [flags: sweep] [opts: device]", "-> decrease brightness"),
-        ("set <val> [opts: device]", "-> set custom brightness value"),
+        ("inc [<val>] [flags: sweep, no-sweep] [opts: device]", "-> increase brightness"),
+        ("dec [<val>] [flags: sweep, no-sweep] [opts: device]", "-> decrease brightness"),
+        (
+            "set <level>|- [flags: sweep, no-sweep] [opts: device, coalesce]",
+            "-> set brightness (raw value, percentage or relative change), '-' reads levels from stdin",
+        ),
+        (
+            "save [--as <slot>] [opts: device] [led selectors]",
+            "-> save the brightness of all backlights (or one device) and the selected LEDs",
+        ),
+        ("restore [<slot>] [flags: sweep, no-sweep]", "-> restore the brightness saved in a slot"),
+        ("undo [<n>] [flags: sweep, no-sweep] [opts: device]", "-> undo the last (or last n) brightness changes"),
+        ("history [opts: device]", "-> show recent brightness changes"),
+        ("saves [list|delete <slot>]", "-> list or delete save slots\n"),
+        (
+            "config [get <key>|set <key> <value>|unset <key>|edit|path]",
+            "-> show or change the config file (shows all settings by default)",
+        ),
         (
-            "save [opts: device]",
-            "-> save current brightness value to restore later",
+            "preset [<name>|list|set <name> <level>|delete <name>] [flags: sweep, no-sweep] [opts: device]",
+            "-> apply, list or change brightness presets",
+        ),
+        (
+            "cycle [<preset>...] [flags: sweep, no-sweep] [opts: device]",
+            "-> switch to the next preset (all presets by default)",
+        ),
+        (
+            "exec --set <level> [flags: sweep, no-sweep] [opts: device] -- <command> [<arg>...]",
+            "-> change the brightness while a command runs, then restore it\n",
+        ),
+        (
+            "breathe|pulse|blink [effect opts] [opts: device]",
+            "-> run a brightness effect (breathe runs until interrupted)",
         ),
-        ("restore", "-> restore saved brightness value\n"),
         (
             "setup",
             "-> installs udev rules and adds user to video group (run with sudo)",
         ),
-        ("status [flags: dev]", "-> backlight device status"),
-        ("list", "-> list all backlight devices"),
+        ("status [flags: json, porcelain] [opts: device, format]", "-> backlight device status"),
+        ("get [--percent|--raw|--max] [opts: device, format]", "-> print a single brightness value (raw by default)"),
+        (
+            "watch [flags: json, porcelain] [opts: device, format, interval]",
+            "-> print the brightness whenever it changes",
+        ),
+        (
+            "bar --protocol waybar|i3bar|polybar [--step <percent>] [--module <name>] [opts: device, format, interval]",
+            "-> stream the brightness to a status bar",
+        ),
+        ("list [flags: json, porcelain]", "-> list all backlight devices"),
+        ("completions bash|zsh|fish", "-> print a shell completion script"),
         ("led", "-> list led related commands"),
         ("help", "-> display help"),
     ]
@@ -615,17 +2273,37 @@ pub fn print_help() {
 Examples:
     sudo blight setup
     blight status (show backlight device status info)
+    blight get --percent (print the current brightness percentage)
+    blight status --porcelain (print the device status in a stable format for scripts)
+    blight watch --format '☀ {percent}% [{bar:10}]' (keep a status bar module up to date)
+    blight bar --protocol i3bar --step 10 (status line for i3bar/swaybar, use as status_command)
     blight inc 5 --sweep (increase brightness smoothly by 5%)
     blight set 10 (sets the brightness value to 10)
+    blight set 40% (sets the brightness to 40%)
+    blight set -5 (decreases the brightness value by 5)
+    sensor-script | blight set - --coalesce 100 (apply each level the script prints, at most every 100ms)
+    blight save --as movie --function kbd_backlight (save all backlights and the keyboard backlight as 'movie')
+    blight restore movie (restore the brightness saved as 'movie')
+    blight undo 2 (go back to the brightness before the last 2 changes)
     blight inc 2 -s -d nvidia_0 (increases nvidia_0's brightness smoothly by 2%)
-    blight led list --function kbd (list keyboard related LEDs)";
+    blight config set step 10 (make 'blight inc' and 'blight dec' change the brightness by 10%)
+    blight config set device.nvidia_0.min 5% (never dim nvidia_0 below 5% with set, inc and dec)
+    blight preset set night 10% (save 10% as the 'night' preset)
+    blight preset night --sweep (smoothly switch to the 'night' preset)
+    blight cycle night day (switch between the 'night' and 'day' presets)
+    blight exec --set 100% --sweep -- mpv film.mkv (full brightness while the film plays)
+    blight pulse (pulse the screen once, as a visual bell)
+    blight breathe --period 4s --min 20 --times 3 (slowly dim and brighten the screen 3 times)
+    blight led list --function kbd (list keyboard related LEDs)
+    blight completions bash > ~/.local/share/bash-completion/completions/blight (enable tab completion)";
 
     println!(
-        "{t}\n\n{quote}\n\n{f}\n\n{ct}\n{commands}\n{e}",
+        "{t}\n\n{quote}\n\n{f}\n\n{ct}\n{commands}\n{e}\n\n{h}",
         t = title.blue().bold(),
         f = flags.magenta(),
         ct = "Commands".bold(),
-        e = exampels.bright_yellow()
+        e = exampels.bright_yellow(),
+        h = "Use `blight <command> --help' to display the options of a single command".yellow()
     );
 }
 
@@ -633,7 +2311,7 @@ pub fn print_shelp() {
     let cc: String = [
         ("inc <val>", "-> increase brightness by given value"),
         ("dec <val>", "-> decrease brightness by given value"),
-        ("set <val>", "-> set custom brightness value"),
+        ("set <level>", "-> set brightness (e.g. 128, 40%, +10%)"),
         ("status", "-> show backlight device info"),
         ("setup", "-> gain write permission to brightness file"),
     ]
@@ -649,39 +2327,6 @@ pub fn print_shelp() {
     );
 }
 
-pub fn save(device_name: Option<Cow<str>>) -> Result<(), DynError> {
-    let device = Device::new(device_name)?;
-    let mut savedir = PathBuf::from(env::var("HOME").unwrap() + SAVEDIR);
-
-    if !savedir.exists() && fs::create_dir_all(&savedir).is_err() {
-        return Err(BlightError::CreateSaveDir(savedir).into());
-    }
-
-    savedir.push("blight.save");
-
-    fs::write(&savedir, format!("{} {}", device.name(), device.current()))
-        .map_err(|_| BlightError::WriteToSaveFile(savedir))?;
-
-    Ok(())
-}
-
-pub fn restore() -> Result<(), DynError> {
-    let save = PathBuf::from((env::var("HOME").unwrap() + SAVEDIR) + "/blight.save");
-
-    let restore = if save.is_file() {
-        fs::read_to_string(save).map_err(BlightError::ReadFromSave)?
-    } else {
-        Err(BlightError::NoSaveFound)?
-    };
-
-    let (device_name, val) = restore.split_once(' ').unwrap();
-    let mut device = Device::new(Some(device_name.into()))?;
-
-    let value: u32 = val.parse().map_err(|_| BlightError::SaveParseErr)?;
-    device.write_value(value)?;
-    Ok(())
-}
-
 pub struct PanicReporter;
 
 impl PanicReporter {
@@ -709,6 +2354,87 @@ impl PanicReporter {
     }
 }
 
+/// Open a backlight device with file locking and the clamp policy from the config
+fn open_configured(
+    name: Option<Cow<str>>,
+    settings: &config::Settings,
+) -> Result<(Device, config::DeviceSettings), DynError> {
+    let mut device = new_locked(name)?;
+    let device_settings = settings.for_device(Some(device.name()));
+    device.set_clamp_policy(device_settings.clamp_policy(device.max()));
+    Ok((device, device_settings))
+}
+
+/// Write a value with the configured sweep, unless the device is already at it
+fn write_configured(
+    device: &mut Device,
+    device_settings: &config::DeviceSettings,
+    value: u32,
+    sweep: Option<Change>,
+) -> Result<(), blight::Error> {
+    if value == device.current() {
+        return Ok(());
+    }
+    match device_settings.change(sweep) {
+        Change::Sweep => device.sweep_write(value, device_settings.sweep_delay),
+        Change::Regular => device.write_value(value),
+    }
+}
+
+/// Run a command until it exits, passing on signals received in the meantime
+fn run_command(command: &[String]) -> Result<(), BlightError> {
+    let (program, args) = command.split_first().expect("exec always has a command");
+    let interrupted = signal::catch_interrupts();
+    let mut child = std::process::Command::new(program)
+        .args(args)
+        .spawn()
+        .map_err(|err| BlightError::RunCommand(program.clone(), err))?;
+    let status = loop {
+        if interrupted.swap(false, std::sync::atomic::Ordering::Relaxed) {
+            signal::forward(&child);
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+            Err(err) => return Err(BlightError::RunCommand(program.clone(), err)),
+        }
+    };
+    if status.success() {
+        Ok(())
+    } else {
+        Err(BlightError::CommandFailed {
+            command: program.clone(),
+            status,
+        })
+    }
+}
+
+/// Write a value like [`write_configured`], and record the change for 'blight undo' unless history is turned off
+fn change_configured(
+    device: &mut Device,
+    device_settings: &config::DeviceSettings,
+    settings: &config::Settings,
+    value: u32,
+    sweep: Option<Change>,
+) -> Result<(), blight::Error> {
+    let old = device.current();
+    write_configured(device, device_settings, value, sweep)?;
+    if settings.history() {
+        history::record_change(device, old);
+    }
+    Ok(())
+}
+
+/// A single LED or a selection of LEDs as a group, so presets apply the same way to both
+fn led_targets(led: &LedTarget) -> Result<blight::led::LedGroup, blight::Error> {
+    match led {
+        LedTarget::Name(name) => Ok(blight::led::LedGroup::from_leds(vec![
+            blight::led::Led::new(name.as_str().into())?,
+        ])),
+        LedTarget::Group(filter) => blight::led::LedGroup::new(filter),
+    }
+}
+
 fn new_locked(name: Option<Cow<str>>) -> Result<Device, DynError> {
     let device = match Device::new_locked(name.clone(), false) {
         Err(err) if *err.kind() == blight::ErrorKind::LockError { blocked: true } => {
@@ -0,0 +1,858 @@
+//! Declarative command table and a small strict argument parser
+//!
+//! Each command is described by a [`Spec`] listing the flags it accepts. Arguments are split into flags and positional
+//! values in a single pass, so flags can be given in any position. Unknown flags and flags missing their value are
+//! errors, with a suggestion when a known flag (or command) looks similar.
+//!
+//! Arguments starting with `-` followed by a digit or a dot (e.g. `-5`, `-5%`) are positional values, which keeps
+//! relative brightness levels working. Everything after `--` is positional as well.
+
+use colored::Colorize;
+use std::fmt::Display;
+
+/// A flag accepted by a command, written as `--long` or `-s` (short)
+#[derive(Debug)]
+pub struct Flag {
+    pub long: &'static str,
+    pub short: Option<char>,
+    /// Name of the value in help texts, `None` for boolean flags
+    pub value: Option<&'static str>,
+    pub help: &'static str,
+}
+
+impl Flag {
+    const fn switch(long: &'static str, short: Option<char>, help: &'static str) -> Self {
+        Self {
+            long,
+            short,
+            value: None,
+            help,
+        }
+    }
+
+    const fn option(
+        long: &'static str,
+        short: Option<char>,
+        value: &'static str,
+        help: &'static str,
+    ) -> Self {
+        Self {
+            long,
+            short,
+            value: Some(value),
+            help,
+        }
+    }
+}
+
+/// A command with its positional arguments and flags
+#[derive(Debug)]
+pub struct Spec {
+    /// Full name of the command, e.g. `led set`
+    pub name: &'static str,
+    /// Positional arguments for the usage line, e.g. `<led>|<selectors> <level>`
+    pub args: &'static str,
+    pub about: &'static str,
+    pub flags: &'static [Flag],
+    /// Max number of positional arguments, `None` for no limit
+    pub max_positional: Option<usize>,
+}
+
+impl Spec {
+    const fn new(name: &'static str, args: &'static str, about: &'static str) -> Self {
+        Self {
+            name,
+            args,
+            about,
+            flags: &[],
+            max_positional: Some(0),
+        }
+    }
+
+    const fn flags(mut self, flags: &'static [Flag]) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    const fn positional(mut self, max: Option<usize>) -> Self {
+        self.max_positional = max;
+        self
+    }
+
+    /// Short name used in the command tables, e.g. `set` for `led set`
+    pub fn short_name(&self) -> &'static str {
+        self.name.rsplit(' ').next().unwrap_or(self.name)
+    }
+}
+
+const DEVICE: Flag = Flag::option(
+    "device",
+    Some('d'),
+    "name",
+    "backlight device to use instead of the detected one",
+);
+const SWEEP: Flag = Flag::switch("sweep", Some('s'), "change the brightness gradually");
+const NO_SWEEP: Flag = Flag::switch(
+    "no-sweep",
+    None,
+    "change the brightness at once, even if sweep is configured",
+);
+const JSON: Flag = Flag::switch("json", None, "print a versioned JSON object");
+const PORCELAIN: Flag = Flag::switch(
+    "porcelain",
+    None,
+    "print stable tab separated lines for scripts",
+);
+const FORMAT: Flag = Flag::option(
+    "format",
+    None,
+    "template",
+    "print a custom template, e.g. '{percent}% [{bar:10}]'",
+);
+const INTERVAL: Flag = Flag::option(
+    "interval",
+    Some('i'),
+    "time",
+    "how often to check the brightness (250ms by default)",
+);
+
+const PERIOD: Flag = Flag::option(
+    "period",
+    Some('p'),
+    "time",
+    "duration of a single cycle, e.g. 500ms or 2s",
+);
+const TIMES: Flag = Flag::option("times", Some('t'), "n", "number of cycles to run");
+const FOREVER: Flag = Flag::switch("forever", None, "run until interrupted with Ctrl-C");
+const MIN: Flag = Flag::option("min", None, "percent", "lowest brightness of the effect");
+const MAX: Flag = Flag::option("max", None, "percent", "highest brightness of the effect");
+
+const FUNCTION: Flag = Flag::option(
+    "function",
+    Some('f'),
+    "name",
+    "select LEDs by function, e.g. capslock",
+);
+const COLOR: Flag = Flag::option(
+    "color",
+    Some('c'),
+    "name",
+    "select LEDs by color, e.g. white",
+);
+const NAME: Flag = Flag::option(
+    "name",
+    Some('n'),
+    "pattern",
+    "select LEDs by device name glob, e.g. 'input*'",
+);
+const DIMMABLE: Flag = Flag::switch("dimmable", None, "select dimmable LEDs only");
+
+const COALESCE: Flag = Flag::option(
+    "coalesce",
+    None,
+    "ms",
+    "with '-', apply at most one value per interval, skipping stale ones",
+);
+
+const BRIGHTNESS: &[Flag] = &[SWEEP, NO_SWEEP, DEVICE];
+const EXEC: &[Flag] = &[
+    Flag::option(
+        "set",
+        None,
+        "level",
+        "brightness while the command runs, e.g. 100%",
+    ),
+    SWEEP,
+    NO_SWEEP,
+    DEVICE,
+];
+const EFFECT: &[Flag] = &[PERIOD, TIMES, FOREVER, MIN, MAX, DEVICE];
+const SELECTORS: &[Flag] = &[FUNCTION, COLOR, NAME, DIMMABLE];
+const LED_EFFECT: &[Flag] = &[
+    PERIOD, TIMES, FOREVER, MIN, MAX, FUNCTION, COLOR, NAME, DIMMABLE,
+];
+
+pub const COMMANDS: &[Spec] = &[
+    Spec::new(
+        "inc",
+        "[<value>]",
+        "Increase brightness by a percentage (the configured step by default)",
+    )
+    .flags(BRIGHTNESS)
+    .positional(Some(1)),
+    Spec::new(
+        "dec",
+        "[<value>]",
+        "Decrease brightness by a percentage (the configured step by default)",
+    )
+    .flags(BRIGHTNESS)
+    .positional(Some(1)),
+    Spec::new(
+        "set",
+        "<level>|-",
+        "Set brightness to a raw value, percentage, fraction or relative change",
+    )
+    .flags(&[SWEEP, NO_SWEEP, DEVICE, COALESCE])
+    .positional(Some(1)),
+    Spec::new(
+        "save",
+        "",
+        "Save the brightness of all backlights (and selected LEDs) to restore later",
+    )
+    .flags(&[
+        Flag::option(
+            "as",
+            None,
+            "slot",
+            "name of the slot to save to ('default' by default)",
+        ),
+        DEVICE,
+        FUNCTION,
+        COLOR,
+        NAME,
+        DIMMABLE,
+    ]),
+    Spec::new(
+        "restore",
+        "[<slot>]",
+        "Restore the brightness saved in a slot",
+    )
+    .flags(&[SWEEP, NO_SWEEP])
+    .positional(Some(1)),
+    Spec::new("saves", "[list|delete <slot>]", "List or delete save slots").positional(Some(2)),
+    Spec::new(
+        "preset",
+        "[<name>|list|set <name> <level>|delete <name>]",
+        "Apply, list or change brightness presets",
+    )
+    .flags(BRIGHTNESS)
+    .positional(Some(3)),
+    Spec::new(
+        "cycle",
+        "[<preset>...]",
+        "Switch to the next preset (all presets by default)",
+    )
+    .flags(BRIGHTNESS)
+    .positional(None),
+    Spec::new(
+        "undo",
+        "[<n>]",
+        "Undo the last (or last n) brightness changes of a device",
+    )
+    .flags(BRIGHTNESS)
+    .positional(Some(1)),
+    Spec::new(
+        "history",
+        "",
+        "Show recent brightness changes (of all devices by default)",
+    )
+    .flags(&[DEVICE]),
+    Spec::new(
+        "exec",
+        "--set <level> -- <command> [<arg>...]",
+        "Change the brightness while a command runs, then restore it",
+    )
+    .flags(EXEC)
+    .positional(None),
+    Spec::new("breathe", "", "Slowly dim and brighten the backlight").flags(EFFECT),
+    Spec::new(
+        "pulse",
+        "",
+        "Briefly dim the backlight, e.g. as a visual bell",
+    )
+    .flags(EFFECT),
+    Spec::new("blink", "", "Turn the backlight off and on").flags(EFFECT),
+    Spec::new("status", "", "Show backlight device status")
+        .flags(&[JSON, PORCELAIN, FORMAT, DEVICE]),
+    Spec::new(
+        "get",
+        "",
+        "Print a single brightness value (raw by default)",
+    )
+    .flags(&[
+        Flag::switch("raw", None, "print the raw brightness value"),
+        Flag::switch("percent", None, "print the brightness percentage"),
+        Flag::switch("max", None, "print the max brightness value"),
+        FORMAT,
+        DEVICE,
+    ]),
+    Spec::new("watch", "", "Print the brightness whenever it changes")
+        .flags(&[JSON, PORCELAIN, FORMAT, INTERVAL, DEVICE]),
+    Spec::new("bar", "", "Stream the brightness to a status bar").flags(&[
+        Flag::option(
+            "protocol",
+            Some('p'),
+            "waybar|i3bar|polybar",
+            "status bar protocol to use",
+        ),
+        Flag::option(
+            "step",
+            None,
+            "percent",
+            "brightness change for each scroll step (5 by default)",
+        ),
+        Flag::option(
+            "module",
+            None,
+            "name",
+            "polybar module whose hook is triggered on changes ('blight' by default)",
+        ),
+        FORMAT,
+        INTERVAL,
+        DEVICE,
+    ]),
+    Spec::new("list", "", "List all backlight devices").flags(&[JSON, PORCELAIN]),
+    Spec::new(
+        "setup",
+        "",
+        "Install udev rules and add the user to the video group (run with sudo)",
+    ),
+    Spec::new(
+        "config",
+        "[get <key>|set <key> <value>|unset <key>|edit|path]",
+        "Show or change the config file",
+    )
+    .positional(Some(3)),
+    Spec::new(
+        "completions",
+        "bash|zsh|fish",
+        "Print a shell completion script",
+    )
+    .positional(Some(1)),
+    Spec::new("led", "<command>", "LED commands, see 'blight led help'").positional(None),
+    Spec::new(
+        "help",
+        "[<command>]",
+        "Show help for all commands or a single one",
+    )
+    .positional(Some(2)),
+];
+
+pub const LED_COMMANDS: &[Spec] = &[
+    Spec::new("led list", "", "List LED devices").flags(&[
+        Flag::switch("raw", Some('r'), "print only the sysfs names"),
+        Flag::switch(
+            "group",
+            Some('g'),
+            "group LEDs by the device they belong to",
+        ),
+        Flag::option(
+            "device",
+            Some('d'),
+            "name",
+            "filter by the name of the physical device",
+        ),
+        Flag::option(
+            "function",
+            Some('f'),
+            "name",
+            "filter by function, e.g. kbd_backlight",
+        ),
+        Flag::option("color", Some('c'), "name", "filter by color, e.g. white"),
+        Flag::option(
+            "index",
+            Some('i'),
+            "n",
+            "select an LED by its position in the list",
+        ),
+        JSON,
+        PORCELAIN,
+    ]),
+    Spec::new(
+        "led info",
+        "[<led>|<selectors>]",
+        "Show LED info (all LEDs if none are specified)",
+    )
+    .flags(&[FUNCTION, COLOR, NAME, DIMMABLE, JSON, PORCELAIN])
+    .positional(Some(1)),
+    Spec::new(
+        "led set",
+        "<led>|<selectors> <level>|-",
+        "Set LED brightness ('-' reads levels from stdin)",
+    )
+    .flags(&[FUNCTION, COLOR, NAME, DIMMABLE, COALESCE])
+    .positional(Some(2)),
+    Spec::new(
+        "led preset",
+        "<led>|<selectors> <preset>",
+        "Set LED brightness to a preset",
+    )
+    .flags(SELECTORS)
+    .positional(Some(2)),
+    Spec::new(
+        "led cycle",
+        "<led>|<selectors> [<preset>...]",
+        "Switch LEDs to the next preset (all presets by default)",
+    )
+    .flags(SELECTORS)
+    .positional(None),
+    Spec::new("led toggle", "<led>|<selectors>", "Toggle LEDs on/off")
+        .flags(&[
+            Flag::switch("on", None, "turn the LEDs on"),
+            Flag::switch("off", None, "turn the LEDs off"),
+            FUNCTION,
+            COLOR,
+            NAME,
+            DIMMABLE,
+        ])
+        .positional(Some(1)),
+    Spec::new(
+        "led breathe",
+        "<led>|<selectors>",
+        "Slowly dim and brighten dimmable LEDs",
+    )
+    .flags(LED_EFFECT)
+    .positional(Some(1)),
+    Spec::new(
+        "led blink",
+        "<led>|<selectors>",
+        "Turn dimmable LEDs off and on",
+    )
+    .flags(LED_EFFECT)
+    .positional(Some(1)),
+    Spec::new(
+        "led pulse",
+        "<led>|<selectors>",
+        "Briefly dim dimmable LEDs",
+    )
+    .flags(LED_EFFECT)
+    .positional(Some(1)),
+    Spec::new(
+        "led play",
+        "<file>|- [<led>...|<selectors>]",
+        "Play an LED sequence from a file or stdin",
+    )
+    .flags(&[
+        Flag::switch("check", None, "only validate the sequence"),
+        FUNCTION,
+        COLOR,
+        NAME,
+        DIMMABLE,
+    ])
+    .positional(None),
+    Spec::new(
+        "led bind",
+        "<led>|<selectors>",
+        "Drive LEDs with the result of a shell command",
+    )
+    .flags(&[
+        Flag::option("command", None, "cmd", "command to run with 'sh -c'"),
+        Flag::option(
+            "interval",
+            Some('i'),
+            "time",
+            "time between runs of the command (5s by default)",
+        ),
+        Flag::option(
+            "range",
+            None,
+            "min:max",
+            "map the number printed by the command to the brightness",
+        ),
+        Flag::switch("invert", None, "invert the result"),
+        Flag::switch(
+            "once",
+            None,
+            "update the LEDs once instead of until interrupted",
+        ),
+        FUNCTION,
+        COLOR,
+        NAME,
+        DIMMABLE,
+    ])
+    .positional(Some(1)),
+    Spec::new("led help", "", "Show help for all LED commands"),
+];
+
+pub const HELP: Flag = Flag::switch("help", Some('h'), "print help for the command");
+
+#[derive(Debug)]
+pub enum ArgError {
+    UnknownCommand {
+        parent: Option<&'static str>,
+        name: String,
+        suggestion: Option<String>,
+    },
+    UnknownFlag {
+        command: &'static str,
+        flag: String,
+        suggestion: Option<String>,
+    },
+    MissingValue {
+        command: &'static str,
+        flag: &'static str,
+    },
+    UnexpectedArgument {
+        command: &'static str,
+        arg: String,
+    },
+}
+
+impl ArgError {
+    /// A similar command or flag to the one that was entered, if any
+    pub fn suggestion(&self) -> Option<&str> {
+        match self {
+            ArgError::UnknownCommand { suggestion, .. }
+            | ArgError::UnknownFlag { suggestion, .. } => suggestion.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The command the arguments were given to, or the parent of an unknown subcommand (e.g. `led`)
+    pub fn command(&self) -> Option<&'static str> {
+        match self {
+            ArgError::UnknownCommand { parent, .. } => *parent,
+            ArgError::UnknownFlag { command, .. }
+            | ArgError::MissingValue { command, .. }
+            | ArgError::UnexpectedArgument { command, .. } => Some(command),
+        }
+    }
+}
+
+impl Display for ArgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArgError::UnknownCommand {
+                parent: None, name, ..
+            } => write!(f, "unrecognised command '{name}'"),
+            ArgError::UnknownCommand {
+                parent: Some(parent),
+                name,
+                ..
+            } => {
+                write!(f, "unrecognised command '{name}' for '{parent}'")
+            }
+            ArgError::UnknownFlag { command, flag, .. } => {
+                write!(f, "unknown option '{flag}' for '{command}'")
+            }
+            ArgError::MissingValue { command, flag } => {
+                write!(f, "option '--{flag}' of '{command}' requires a value")
+            }
+            ArgError::UnexpectedArgument { command, arg } => {
+                write!(f, "unexpected argument '{arg}' for '{command}'")
+            }
+        }
+    }
+}
+
+/// Flags and positional arguments given to a command
+#[derive(Debug, Default)]
+pub struct Matches {
+    /// Flags in the order they were given, identified by their long name
+    flags: Vec<(&'static str, Option<String>)>,
+    pub positional: Vec<String>,
+    pub help: bool,
+}
+
+impl Matches {
+    /// Whether a flag was given
+    pub fn flag(&self, long: &str) -> bool {
+        self.flags.iter().any(|(name, _)| *name == long)
+    }
+
+    /// Value of the last occurrence of an option
+    pub fn value(&self, long: &str) -> Option<&str> {
+        self.flags
+            .iter()
+            .rev()
+            .find(|(name, _)| *name == long)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    /// All flags in the order they were given
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, Option<&str>)> {
+        self.flags
+            .iter()
+            .map(|(name, value)| (*name, value.as_deref()))
+    }
+}
+
+/// Find a command by name, suggesting a similar one if it doesn't exist
+pub fn find(table: &'static [Spec], name: &str) -> Result<&'static Spec, ArgError> {
+    table
+        .iter()
+        .find(|spec| spec.short_name() == name)
+        .ok_or_else(|| ArgError::UnknownCommand {
+            parent: table
+                .first()
+                .and_then(|spec| spec.name.split_once(' '))
+                .map(|(parent, _)| parent),
+            name: name.to_owned(),
+            suggestion: closest(name, table.iter().map(Spec::short_name)).map(str::to_owned),
+        })
+}
+
+/// Whether an argument is a flag, as opposed to a positional value such as `-` (stdin) or `-5` (negative number)
+pub fn is_flag(arg: &str) -> bool {
+    let mut chars = arg.chars();
+    chars.next() == Some('-')
+        && chars
+            .next()
+            .is_some_and(|c| !c.is_ascii_digit() && c != '.')
+}
+
+/// Split the arguments of a command into flags and positional values
+pub fn parse(
+    spec: &'static Spec,
+    args: impl IntoIterator<Item = String>,
+) -> Result<Matches, ArgError> {
+    let mut matches = Matches::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            matches.positional.extend(args.by_ref());
+            break;
+        }
+        if !is_flag(&arg) {
+            matches.positional.push(arg);
+            continue;
+        }
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if arg.starts_with("--") => (name, Some(value.to_owned())),
+            _ => (arg.as_str(), None),
+        };
+        let flag = match name.strip_prefix("--") {
+            Some(long) => spec.flags.iter().chain([&HELP]).find(|f| f.long == long),
+            None => {
+                let mut short = name[1..].chars();
+                let c = short.next().filter(|_| short.next().is_none());
+                spec.flags
+                    .iter()
+                    .chain([&HELP])
+                    .find(|f| c.is_some() && f.short == c)
+            }
+        };
+        let Some(flag) = flag else {
+            let suggestion = closest(
+                name.trim_start_matches('-'),
+                spec.flags.iter().map(|f| f.long),
+            );
+            return Err(ArgError::UnknownFlag {
+                command: spec.name,
+                flag: name.to_owned(),
+                suggestion: suggestion.map(|s| format!("--{s}")),
+            });
+        };
+        if flag.long == HELP.long {
+            matches.help = true;
+            continue;
+        }
+        let value = match (flag.value, inline_value) {
+            (None, None) => None,
+            (None, Some(_)) => {
+                return Err(ArgError::UnexpectedArgument {
+                    command: spec.name,
+                    arg,
+                });
+            }
+            (Some(_), Some(value)) => Some(value),
+            (Some(_), None) => match args.next() {
+                Some(value) if !is_flag(&value) => Some(value),
+                _ => {
+                    return Err(ArgError::MissingValue {
+                        command: spec.name,
+                        flag: flag.long,
+                    })
+                }
+            },
+        };
+        matches.flags.push((flag.long, value));
+    }
+    if let Some(max) = spec.max_positional {
+        if let Some(arg) = matches.positional.get(max) {
+            return Err(ArgError::UnexpectedArgument {
+                command: spec.name,
+                arg: arg.clone(),
+            });
+        }
+    }
+    Ok(matches)
+}
+
+/// The candidate closest to the input, if it's similar enough to be a likely typo
+pub fn closest<'a>(input: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .map(|candidate| (distance(input, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= 2 && *distance < candidate.len())
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two strings
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(current)
+            };
+            prev = current;
+        }
+    }
+    row[b.len()]
+}
+
+/// Print the usage and flags of a single command
+pub fn print_help(spec: &Spec) {
+    let usage = format!("blight {} {}", spec.name, spec.args);
+    let flags = if spec.flags.is_empty() {
+        ""
+    } else {
+        " [flags]"
+    };
+    println!(
+        "{} {}{flags}\n{}\n",
+        "Usage:".bold(),
+        usage.trim_end().green().bold(),
+        spec.about
+    );
+    println!("{}", "Flags:".bold());
+    let flags: Vec<_> = spec
+        .flags
+        .iter()
+        .chain([&HELP])
+        .map(|flag| {
+            let short = flag.short.map(|c| format!("-{c}, ")).unwrap_or_default();
+            let value = flag.value.map(|v| format!(" <{v}>")).unwrap_or_default();
+            (format!("{short}--{}{value}", flag.long), flag.help)
+        })
+        .collect();
+    let width = flags
+        .iter()
+        .map(|(flag, _)| flag.len())
+        .max()
+        .unwrap_or_default();
+    for (flag, help) in flags {
+        println!("  {}  {help}", format!("{flag:<width$}").magenta());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(command: &str, args: &[&str]) -> Result<Matches, ArgError> {
+        let spec = find(COMMANDS, command).unwrap();
+        parse(spec, args.iter().map(|arg| (*arg).to_owned()))
+    }
+
+    #[test]
+    fn flags_in_any_position() {
+        let m = parse_args("set", &["-d", "amdgpu_bl0", "40%", "--sweep"]).unwrap();
+        assert_eq!(m.positional, ["40%"]);
+        assert_eq!(m.value("device"), Some("amdgpu_bl0"));
+        assert!(m.flag("sweep"));
+        assert!(!m.flag("no-sweep"));
+        assert!(parse_args("set", &["-h"]).unwrap().help);
+    }
+
+    #[test]
+    fn double_dash() {
+        let m = parse_args("set", &["--", "-5%"]).unwrap();
+        assert_eq!(m.positional, ["-5%"]);
+        let m = parse_args("exec", &["--set", "100%", "--", "mpv", "--fs", "-"]).unwrap();
+        assert_eq!(m.value("set"), Some("100%"));
+        assert_eq!(m.positional, ["mpv", "--fs", "-"]);
+    }
+
+    #[test]
+    fn inline_values() {
+        let m = parse_args("set", &["--device=amdgpu_bl0", "50"]).unwrap();
+        assert_eq!(m.value("device"), Some("amdgpu_bl0"));
+        // The last occurrence wins
+        let m = parse_args("set", &["--device=a=b", "-d", "c", "50"]).unwrap();
+        assert_eq!(m.value("device"), Some("c"));
+        let m = parse_args("status", &["--format={percent}%"]).unwrap();
+        assert_eq!(m.value("format"), Some("{percent}%"));
+        assert!(matches!(
+            parse_args("set", &["--sweep=yes", "50"]),
+            Err(ArgError::UnexpectedArgument { arg, .. }) if arg == "--sweep=yes"
+        ));
+    }
+
+    #[test]
+    fn negative_positionals() {
+        for level in ["-5", "-.5", "-5%", "-0.35", "-"] {
+            assert!(!is_flag(level), "{level}");
+            let m = parse_args("set", &[level]).unwrap();
+            assert_eq!(m.positional, [level]);
+        }
+        for flag in ["-d", "--device", "-x", "--"] {
+            assert!(is_flag(flag), "{flag}");
+        }
+    }
+
+    #[test]
+    fn missing_value() {
+        for args in [
+            &["50", "-d"][..],
+            &["-d", "--sweep", "50"],
+            &["-d", "-s", "50"],
+        ] {
+            assert!(matches!(
+                parse_args("set", args),
+                Err(ArgError::MissingValue {
+                    command: "set",
+                    flag: "device"
+                })
+            ));
+        }
+        // A negative level is a value, not a flag
+        let m = parse_args("exec", &["--set", "-10%", "--", "true"]).unwrap();
+        assert_eq!(m.value("set"), Some("-10%"));
+    }
+
+    #[test]
+    fn too_many_positionals() {
+        assert!(matches!(
+            parse_args("set", &["50", "60"]),
+            Err(ArgError::UnexpectedArgument { command: "set", arg }) if arg == "60"
+        ));
+        assert!(matches!(
+            parse_args("status", &["extra"]),
+            Err(ArgError::UnexpectedArgument { arg, .. }) if arg == "extra"
+        ));
+        assert_eq!(
+            parse_args("cycle", &["a", "b", "c", "d"])
+                .unwrap()
+                .positional
+                .len(),
+            4
+        );
+    }
+
+    #[test]
+    fn suggestions() {
+        let err = parse_args("inc", &["5", "--sweeep"]).unwrap_err();
+        assert!(matches!(&err, ArgError::UnknownFlag { flag, .. } if flag == "--sweeep"));
+        assert_eq!(err.suggestion(), Some("--sweep"));
+        assert_eq!(err.command(), Some("inc"));
+        assert_eq!(
+            parse_args("set", &["-z", "5"]).unwrap_err().suggestion(),
+            None
+        );
+
+        let err = find(COMMANDS, "stauts").unwrap_err();
+        assert_eq!(err.suggestion(), Some("status"));
+        assert_eq!(err.command(), None);
+        let err = find(LED_COMMANDS, "tgogle").unwrap_err();
+        assert_eq!(err.suggestion(), Some("toggle"));
+        assert_eq!(err.command(), Some("led"));
+        assert_eq!(find(COMMANDS, "brightness").unwrap_err().suggestion(), None);
+
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("same", "same"), 0);
+        let candidates = ["set", "get", "inc"];
+        assert_eq!(closest("sey", candidates.into_iter()), Some("set"));
+        // Too different from every candidate, or shorter than the edits needed
+        assert_eq!(closest("xyzzy", candidates.into_iter()), None);
+        assert_eq!(closest("ab", ["x"].into_iter()), None);
+    }
+}
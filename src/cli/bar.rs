@@ -0,0 +1,227 @@
+//! Stream the backlight brightness to status bars in their native formats
+//!
+//! - waybar: one JSON object per line for a custom module with `"return-type": "json"`
+//! - i3bar: the i3bar JSON protocol, including click events read from stdin (also used by swaybar)
+//! - polybar: IPC hooks of a `custom/ipc` module, which are triggered with `polybar-msg` on every change
+//!
+//! Waybar and polybar don't send events on stdin, so they run blight commands for scrolling instead.
+//! Polybar also reads the text from the command of the hook (e.g. `blight get --format`), so nothing is printed.
+
+use std::{
+    io::{self, BufRead, Write},
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::Duration,
+};
+
+use blight::{watch::Watcher, Device, Direction, Light};
+
+use super::{history, output::Json, template::Template, BlightError, DynError};
+
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Waybar,
+    I3bar,
+    Polybar,
+}
+
+impl Protocol {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "waybar" => Some(Self::Waybar),
+            "i3bar" | "swaybar" => Some(Self::I3bar),
+            "polybar" => Some(Self::Polybar),
+            _ => None,
+        }
+    }
+}
+
+/// Brightness changes requested by the bar
+#[derive(Debug, Clone, Copy)]
+enum Action {
+    Inc,
+    Dec,
+    Toggle,
+}
+
+#[derive(Debug)]
+pub struct Bar {
+    pub protocol: Protocol,
+    pub interval: Option<Duration>,
+    /// Brightness change in percent for each scroll step
+    pub step: u32,
+    pub text: Template,
+    /// Name of the polybar module whose hook is triggered
+    pub module: String,
+}
+
+impl Bar {
+    /// Print the brightness whenever it changes, until the bar closes the output or reading the brightness fails
+    ///
+    /// Changes made by click events are recorded for `blight undo` if `history` is set.
+    pub fn run(&self, device: Device, history: bool) -> Result<(), DynError> {
+        let interval = self.interval.unwrap_or(DEFAULT_INTERVAL);
+        let mut watcher = Watcher::new(device);
+        let events = (self.protocol == Protocol::I3bar).then(|| {
+            let (tx, rx) = mpsc::channel();
+            thread::spawn(move || read_click_events(&tx));
+            rx
+        });
+        let mut out = io::stdout();
+        if self.protocol == Protocol::I3bar
+            && writeln!(out, "{{\"version\":1,\"click_events\":true}}\n[").is_err()
+        {
+            return Ok(());
+        }
+        // Brightness to go back to when toggling the backlight on again
+        let mut restore = None;
+        let mut first = true;
+        loop {
+            if watcher.poll()?.is_some() {
+                if self.protocol == Protocol::Polybar {
+                    self.run_hook()?;
+                } else if writeln!(out, "{}", self.render(watcher.light(), first)).is_err() {
+                    // The bar went away
+                    return Ok(());
+                }
+                first = false;
+            }
+            let action = wait_for_action(events.as_ref(), interval);
+            let old = watcher.light().current();
+            match action {
+                Some(Action::Toggle) => {
+                    let device = watcher.light_mut();
+                    let value = match device.current() {
+                        0 => restore.take().unwrap_or(device.max()),
+                        current => {
+                            restore = Some(current);
+                            0
+                        }
+                    };
+                    // Turning the backlight off is deliberate, so the configured min doesn't apply
+                    device.write_value_unclamped(value)?;
+                }
+                Some(action) => {
+                    let device = watcher.light_mut();
+                    let dir = if matches!(action, Action::Inc) {
+                        Direction::Inc
+                    } else {
+                        Direction::Dec
+                    };
+                    let value = device.calculate_change(self.step, dir);
+                    if value != device.current() {
+                        device.write_value(value)?;
+                    }
+                }
+                None => (),
+            }
+            if history && action.is_some() {
+                history::record_change(watcher.light(), old);
+            }
+        }
+    }
+
+    fn render(&self, device: &Device, first: bool) -> String {
+        let text = self
+            .text
+            .render(device.name(), device.current(), device.max());
+        let percent = device.current_percent().round();
+        match self.protocol {
+            Protocol::Waybar => {
+                // Waybar expects a whole number
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let percentage = percent as u64;
+                let class = level_class(percent);
+                let tooltip = format!(
+                    "{}: {percent}% ({}/{})",
+                    device.name(),
+                    device.current(),
+                    device.max()
+                );
+                Json::Object(vec![
+                    ("text", Json::Str(text)),
+                    ("percentage", Json::Int(percentage)),
+                    ("class", class.into()),
+                    ("alt", class.into()),
+                    ("tooltip", Json::Str(tooltip)),
+                ])
+                .to_string()
+            }
+            Protocol::I3bar => {
+                let block = Json::Object(vec![
+                    ("name", "blight".into()),
+                    ("instance", device.name().into()),
+                    ("full_text", Json::Str(text)),
+                    ("short_text", Json::Str(format!("{percent}%"))),
+                ]);
+                // Status lines are elements of an infinite array
+                format!("{}[{block}]", if first { "" } else { "," })
+            }
+            Protocol::Polybar => unreachable!("polybar reads the brightness from its hook"),
+        }
+    }
+
+    /// Make polybar run the first hook of the module again, which shows the new brightness
+    fn run_hook(&self) -> Result<(), BlightError> {
+        // Polybar not running is fine, it runs the hook itself when it starts (with `initial = 1`)
+        Command::new("polybar-msg")
+            .args(["action", &format!("#{}.hook.0", self.module)])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_err(|err| BlightError::RunCommand("polybar-msg".into(), err))?;
+        Ok(())
+    }
+}
+
+/// CSS class for waybar, also usable as `alt` to pick an icon
+fn level_class(percent: f64) -> &'static str {
+    match percent {
+        p if p <= 0.0 => "off",
+        p if p < 34.0 => "low",
+        p if p < 67.0 => "medium",
+        _ => "high",
+    }
+}
+
+/// Wait for the next action from the bar, or until the interval passes
+fn wait_for_action(events: Option<&Receiver<Action>>, interval: Duration) -> Option<Action> {
+    match events.map(|rx| rx.recv_timeout(interval)) {
+        Some(Ok(action)) => Some(action),
+        Some(Err(RecvTimeoutError::Timeout)) => None,
+        // No events, or stdin was closed
+        None | Some(Err(RecvTimeoutError::Disconnected)) => {
+            thread::sleep(interval);
+            None
+        }
+    }
+}
+
+/// Read i3bar click events from stdin: left click toggles, scrolling up and down changes the brightness
+fn read_click_events(tx: &Sender<Action>) {
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        let action = match json_number(&line, "button") {
+            Some(1) => Action::Toggle,
+            Some(4) => Action::Inc,
+            Some(5) => Action::Dec,
+            _ => continue,
+        };
+        if tx.send(action).is_err() {
+            break;
+        }
+    }
+}
+
+/// Extract a number field from a flat JSON object, which is all that's needed for i3bar click events
+fn json_number(json: &str, key: &str) -> Option<u32> {
+    let (_, rest) = json.split_once(&format!("\"{key}\""))?;
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    rest[..end].parse().ok()
+}
@@ -0,0 +1,315 @@
+//! Shell completions for bash, zsh and fish
+//!
+//! The scripts are thin wrappers that call `blight __complete <words>` with the words typed so far. Candidates are
+//! computed from the command table in [`args`], so commands and flags never go out of sync with the parser, and values
+//! such as backlight and LED names come from the system at the time of completion.
+//!
+//! Candidates are printed one per line as `value<TAB>description`, with the description left out when there is none.
+
+use blight::led;
+
+use super::{
+    args::{self, Spec},
+    config, presets, saves,
+};
+
+const BASH: &str = r#"# bash completion for blight
+_blight() {
+    local line="${COMP_LINE:0:COMP_POINT}"
+    local -a words
+    read -ra words <<< "$line"
+    [[ "$line" == *[[:space:]] ]] && words+=("")
+    local cur="${words[${#words[@]}-1]}"
+
+    local IFS=$'\n'
+    COMPREPLY=($(blight __complete "${words[@]:1}" 2>/dev/null | cut -f1))
+
+    # LED names contain colons, which bash treats as word separators
+    if [[ "$cur" == *:* && "$COMP_WORDBREAKS" == *:* ]]; then
+        local prefix="${cur%"${cur##*:}"}"
+        COMPREPLY=("${COMPREPLY[@]#"$prefix"}")
+    fi
+}
+complete -o default -F _blight blight
+"#;
+
+const ZSH: &str = r#"#compdef blight
+# zsh completion for blight
+_blight() {
+    local -a candidates described
+    local line value
+    candidates=("${(@f)$(blight __complete "${(Q)words[@]:1:$CURRENT-1}" 2>/dev/null)}")
+    for line in $candidates; do
+        [[ -z "$line" ]] && continue
+        value="${line%%$'\t'*}"
+        if [[ "$line" == *$'\t'* ]]; then
+            described+=("${value//:/\\:}:${line#*$'\t'}")
+        else
+            described+=("${value//:/\\:}")
+        fi
+    done
+    if (( ${#described} )); then
+        _describe -t values 'blight' described
+    else
+        _files
+    fi
+}
+
+if [[ "$funcstack[1]" == "_blight" ]]; then
+    _blight "$@"
+else
+    compdef _blight blight
+fi
+"#;
+
+const FISH: &str = r"# fish completion for blight
+function __blight_complete
+    set -l tokens (commandline -opc)
+    blight __complete $tokens[2..-1] (commandline -ct) 2>/dev/null
+end
+
+complete -c blight -f -a '(__blight_complete)'
+# Sequence files for 'blight led play'
+complete -c blight -n '__fish_seen_subcommand_from play' -F
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bash" => Some(Self::Bash),
+            "zsh" => Some(Self::Zsh),
+            "fish" => Some(Self::Fish),
+            _ => None,
+        }
+    }
+
+    /// Completion script to be sourced by the shell
+    pub fn script(self) -> &'static str {
+        match self {
+            Shell::Bash => BASH,
+            Shell::Zsh => ZSH,
+            Shell::Fish => FISH,
+        }
+    }
+}
+
+/// Print the candidates for the last word, given all words after `blight` (the last one may be empty)
+pub fn print_candidates(words: &[String]) {
+    let Some((current, previous)) = words.split_last() else {
+        return;
+    };
+    for (value, description) in candidates(previous, current) {
+        if !value.starts_with(current.as_str()) {
+            continue;
+        }
+        if description.is_empty() {
+            println!("{value}");
+        } else {
+            println!("{value}\t{description}");
+        }
+    }
+}
+
+fn candidates(previous: &[String], current: &str) -> Vec<(String, String)> {
+    let Some((name, mut rest)) = previous.split_first() else {
+        return commands(args::COMMANDS);
+    };
+    let Ok(mut spec) = args::find(args::COMMANDS, name) else {
+        return vec![];
+    };
+    if spec.name == "led" {
+        let Some((name, led_rest)) = rest.split_first() else {
+            return commands(args::LED_COMMANDS);
+        };
+        let Ok(led_spec) = args::find(args::LED_COMMANDS, name) else {
+            return vec![];
+        };
+        (spec, rest) = (led_spec, led_rest);
+    }
+
+    let mut positional = 0;
+    let mut selectors = false;
+    let mut words = rest.iter().map(String::as_str);
+    while let Some(word) = words.next() {
+        if word == "--" {
+            positional += words.len();
+            break;
+        }
+        if !args::is_flag(word) {
+            positional += 1;
+            continue;
+        }
+        let Some(flag) = find_flag(spec, word) else {
+            continue;
+        };
+        selectors |= matches!(flag.long, "function" | "color" | "name" | "dimmable");
+        // The value of the flag is the word being completed
+        if flag.value.is_some() && words.next().is_none() {
+            return values(spec, flag.long);
+        }
+    }
+
+    if current.starts_with('-') {
+        flags(spec)
+    } else if spec.name == "help" {
+        match rest {
+            [] => commands(args::COMMANDS),
+            [led] if led == "led" => commands(args::LED_COMMANDS),
+            _ => vec![],
+        }
+    } else {
+        positional_values(spec, positional, selectors)
+    }
+}
+
+/// A flag given as `--long` or `-s`, flags with an inline value (`--long=value`) don't take the next word
+fn find_flag(spec: &'static Spec, word: &str) -> Option<&'static args::Flag> {
+    if word.contains('=') {
+        return None;
+    }
+    match word.strip_prefix("--") {
+        Some(long) => spec.flags.iter().find(|f| f.long == long),
+        None => {
+            let mut short = word[1..].chars();
+            let c = short.next().filter(|_| short.next().is_none());
+            spec.flags.iter().find(|f| c.is_some() && f.short == c)
+        }
+    }
+}
+
+fn commands(table: &[Spec]) -> Vec<(String, String)> {
+    table
+        .iter()
+        .map(|spec| (spec.short_name().to_owned(), spec.about.to_owned()))
+        .collect()
+}
+
+fn flags(spec: &Spec) -> Vec<(String, String)> {
+    spec.flags
+        .iter()
+        .chain([&args::HELP])
+        .map(|flag| (format!("--{}", flag.long), flag.help.to_owned()))
+        .collect()
+}
+
+fn values(spec: &Spec, flag: &str) -> Vec<(String, String)> {
+    match (spec.name, flag) {
+        // Physical device names of LEDs aren't known without reading every LED
+        ("led list", "device") => vec![],
+        (_, "device") => blight::device_names()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|name| (name, "backlight".to_owned()))
+            .collect(),
+        (_, "function") => led_attributes(|name| name.function().as_str().to_owned()),
+        (_, "color") => led_attributes(|name| name.color().as_str().to_owned()),
+        ("save", "as") => plain(saves::slots().unwrap_or_default()),
+        ("bar", "protocol") => plain(["waybar", "i3bar", "swaybar", "polybar"]),
+        _ => vec![],
+    }
+}
+
+fn positional_values(spec: &Spec, index: usize, selectors: bool) -> Vec<(String, String)> {
+    match (spec.name, index) {
+        ("completions", 0) => plain(["bash", "zsh", "fish"]),
+        ("saves", 0) => plain(["list", "delete"]),
+        ("config", 0) => plain(["get", "set", "unset", "edit", "path"]),
+        ("config", 1) => config_keys(),
+        ("restore", 0) | ("saves", 1) => plain(saves::slots().unwrap_or_default()),
+        ("preset", 0) => {
+            let mut candidates = preset_names();
+            candidates.extend(plain(["list", "set", "delete"]));
+            candidates
+        }
+        ("preset", 1) | ("cycle", _) => preset_names(),
+        // With selectors the presets come first, otherwise after the LED name
+        ("led preset", 0) | ("led cycle", _) if selectors => preset_names(),
+        ("led preset", 1) | ("led cycle", 1..) if !selectors => preset_names(),
+        // LED names can't be combined with selectors
+        (_, _) if selectors => vec![],
+        (
+            "led info" | "led set" | "led toggle" | "led breathe" | "led blink" | "led pulse"
+            | "led bind" | "led preset" | "led cycle",
+            0,
+        ) => led_names(),
+        // The first argument is the sequence file, completed by the shell
+        ("led play", 1..) => led_names(),
+        _ => vec![],
+    }
+}
+
+/// Preset names, described by their level
+fn preset_names() -> Vec<(String, String)> {
+    presets::load()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, level)| (name, level.to_string()))
+        .collect()
+}
+
+/// Global config keys, and the keys that can be set for each backlight device
+fn config_keys() -> Vec<(String, String)> {
+    let devices = blight::device_names().unwrap_or_default();
+    let per_device = config::KEYS
+        .iter()
+        .filter(|key| key.per_device)
+        .flat_map(|key| {
+            devices
+                .iter()
+                .map(move |device| format!("device.{device}.{}", key.name))
+        });
+    plain(
+        config::KEYS
+            .iter()
+            .map(|key| key.name.to_owned())
+            .chain(per_device),
+    )
+}
+
+/// Candidates without a description
+fn plain<S: Into<String>>(values: impl IntoIterator<Item = S>) -> Vec<(String, String)> {
+    values
+        .into_iter()
+        .map(|value| (value.into(), String::new()))
+        .collect()
+}
+
+/// LED names with their function and color as the description
+fn led_names() -> Vec<(String, String)> {
+    led::led_names()
+        .unwrap_or_default()
+        .iter()
+        .map(|name| {
+            let description = [
+                ("function", name.function().as_str()),
+                ("color", name.color().as_str()),
+            ]
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(key, value)| format!("{key}: {value}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+            (name.raw_name().to_owned(), description)
+        })
+        .collect()
+}
+
+/// Unique functions or colors of all LEDs
+fn led_attributes(attribute: impl Fn(&led::LedName) -> String) -> Vec<(String, String)> {
+    let mut values: Vec<String> = led::led_names()
+        .unwrap_or_default()
+        .iter()
+        .map(attribute)
+        .filter(|value| !value.is_empty())
+        .collect();
+    values.sort();
+    values.dedup();
+    plain(values)
+}
@@ -0,0 +1,957 @@
+//! User configuration file with defaults for the CLI
+//!
+//! The file is read from `$XDG_CONFIG_HOME/blight/config` (`~/.config/blight/config` by default), or from the path
+//! in `$BLIGHT_CONFIG`. It consists of `key = value` lines, `#` comments and `[device.<name>]` sections, which
+//! override the global settings for a single backlight device:
+//!
+//! ```text
+//! device = amdgpu_bl0
+//! step = 5
+//! sweep = true
+//!
+//! [device.nvidia_0]
+//! curve = perceptual
+//! min = 5%
+//! ```
+//!
+//! Every key can also be set with a `BLIGHT_<KEY>` environment variable (e.g. `BLIGHT_STEP=10`), which takes
+//! precedence over the file, including its device sections. Flags given on the command line take precedence over both.
+//!
+//! The `function`, `color` and `name` keys select the LEDs of `blight led` commands given neither an LED name nor a
+//! selector, e.g. `blight led set 50%` with `function = kbd_backlight`.
+
+use std::{
+    collections::BTreeMap,
+    env,
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use blight::{led::LedFilter, Bound, Change, ClampPolicy, Delay, Device, Direction, Level, Light};
+
+use super::{args, paths, BlightError};
+
+const CONFIG_VAR: &str = "BLIGHT_CONFIG";
+const DEVICE_SECTION: &str = "device.";
+
+/// Contents of a new config file created by `blight config edit`
+const TEMPLATE: &str = "\
+# blight config, every key can also be set with a BLIGHT_<KEY> environment variable
+#
+# Backlight device to use when none is given with --device
+# device = amdgpu_bl0
+# LEDs to use for 'blight led' commands when no LED name or selector is given
+# function = kbd_backlight
+# color = white
+# name = platform
+# Default percentage for 'blight inc' and 'blight dec'
+# step = 5
+# Change the brightness gradually, and the delay between each step or the time to sweep the whole range
+# sweep = false
+# sweep_delay = 25ms
+# sweep_duration = 500ms
+# How percentages map to brightness values: linear or perceptual
+# curve = linear
+# Lowest and highest brightness 'set', 'inc', 'dec' and 'bar' go to, as a raw value or percentage
+# min = 5%
+# max = 100%
+# Record brightness changes for 'blight history' and 'blight undo'
+# history = true
+#
+# Sections override the settings above for a single device
+# [device.nvidia_0]
+# curve = perceptual
+";
+
+/// A configuration key and the values it accepts
+#[derive(Debug)]
+pub struct Key {
+    pub name: &'static str,
+    /// Description of valid values for error messages
+    expected: &'static str,
+    /// Value used when the key isn't set anywhere
+    default: Option<&'static str>,
+    /// Whether the key can be overridden per device
+    pub per_device: bool,
+}
+
+pub const KEYS: &[Key] = &[
+    Key {
+        name: "device",
+        expected: "a backlight device name",
+        default: None,
+        per_device: false,
+    },
+    Key {
+        name: "function",
+        expected: "an LED function, e.g. kbd_backlight",
+        default: None,
+        per_device: false,
+    },
+    Key {
+        name: "color",
+        expected: "an LED color, e.g. white",
+        default: None,
+        per_device: false,
+    },
+    Key {
+        name: "name",
+        expected: "an LED device name, '*' and '?' match any characters",
+        default: None,
+        per_device: false,
+    },
+    Key {
+        name: "step",
+        expected: "a percentage between 1 and 100",
+        default: None,
+        per_device: true,
+    },
+    Key {
+        name: "sweep",
+        expected: "true or false",
+        default: Some("false"),
+        per_device: true,
+    },
+    Key {
+        name: "sweep_delay",
+        expected: "a duration, e.g. 25ms",
+        default: Some("25ms"),
+        per_device: true,
+    },
+    Key {
+        name: "sweep_duration",
+        expected: "a duration, e.g. 500ms",
+        default: None,
+        per_device: true,
+    },
+    Key {
+        name: "curve",
+        expected: "linear or perceptual",
+        default: Some("linear"),
+        per_device: true,
+    },
+    Key {
+        name: "min",
+        expected: "a raw value or percentage, e.g. 5%",
+        default: None,
+        per_device: true,
+    },
+    Key {
+        name: "max",
+        expected: "a raw value or percentage, e.g. 90%",
+        default: None,
+        per_device: true,
+    },
+    Key {
+        name: "history",
+        expected: "true or false",
+        default: Some("true"),
+        per_device: false,
+    },
+];
+
+impl Key {
+    fn find(name: &str) -> Option<&'static Key> {
+        KEYS.iter().find(|key| key.name == name)
+    }
+
+    /// Environment variable overriding the key, e.g. `BLIGHT_SWEEP_DELAY`
+    fn var(&self) -> String {
+        format!("BLIGHT_{}", self.name.to_uppercase())
+    }
+
+    /// Check a value, returning it without surrounding quotes
+    fn validate(&self, value: &str) -> Option<String> {
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        let valid = match self.name {
+            "device" => !value.is_empty() && !value.contains('/'),
+            "step" => parse_step(value).is_some(),
+            "sweep" | "history" => parse_bool(value).is_some(),
+            "function" | "color" | "name" => {
+                !value.is_empty() && !value.contains(char::is_whitespace)
+            }
+            "sweep_delay" | "sweep_duration" => value.parse::<Delay>().is_ok(),
+            "curve" => Curve::from_name(value).is_some(),
+            "min" | "max" => parse_bound(value).is_some(),
+            _ => false,
+        };
+        valid.then(|| value.to_owned())
+    }
+}
+
+fn parse_step(value: &str) -> Option<u32> {
+    value
+        .trim_end_matches('%')
+        .parse()
+        .ok()
+        .filter(|step| (1..=100).contains(step))
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Bounds are absolute levels, relative changes don't make sense as a limit
+fn parse_bound(value: &str) -> Option<Level> {
+    value
+        .parse::<Level>()
+        .ok()
+        .filter(|level| !level.is_relative())
+}
+
+/// How percentages map to raw brightness values
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Curve {
+    /// Percentages are a fraction of the max brightness
+    #[default]
+    Linear,
+    /// Percentages follow the perceived brightness, giving finer control at the low end
+    Perceptual,
+}
+
+impl Curve {
+    /// Exponent of the perceptual curve, a common approximation of how brightness is perceived
+    const EXPONENT: f64 = 2.0;
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "linear" => Some(Self::Linear),
+            "perceptual" => Some(Self::Perceptual),
+            _ => None,
+        }
+    }
+
+    fn to_raw(self, percent: f64, max: u32) -> u32 {
+        match self {
+            Curve::Linear => Level::Percent(percent).resolve(0, max),
+            Curve::Perceptual => {
+                let fraction = (percent.clamp(0.0, 100.0) / 100.0).powf(Self::EXPONENT);
+                #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+                let value = (f64::from(max) * fraction).round() as u32;
+                value
+            }
+        }
+    }
+
+    fn to_percent(self, value: u32, max: u32) -> f64 {
+        let fraction = f64::from(value) / f64::from(max.max(1));
+        match self {
+            Curve::Linear => fraction * 100.0,
+            Curve::Perceptual => fraction.powf(Self::EXPONENT.recip()) * 100.0,
+        }
+    }
+
+    /// Resolve a level, with percentages following the curve
+    fn resolve(self, level: Level, current: u32, max: u32) -> u32 {
+        match (self, level) {
+            (Curve::Linear, _) | (_, Level::Raw(_) | Level::RelativeRaw(_)) => {
+                level.resolve(current, max)
+            }
+            (Curve::Perceptual, Level::Percent(percent)) => self.to_raw(percent, max),
+            (Curve::Perceptual, Level::RelativePercent(delta)) => {
+                let value = self.to_raw(self.to_percent(current, max) + delta, max);
+                // Small changes at the low end can round to the current value, always move by at least 1
+                match value.cmp(&current) {
+                    std::cmp::Ordering::Equal if delta > 0.0 => (current + 1).min(max),
+                    std::cmp::Ordering::Equal if delta < 0.0 => current.saturating_sub(1),
+                    _ => value,
+                }
+            }
+        }
+    }
+}
+
+/// Validated values of a single scope (global, device section or environment), by key
+type Values = BTreeMap<&'static str, String>;
+
+/// Settings from the config file and the environment
+#[derive(Debug, Default)]
+pub struct Settings {
+    global: Values,
+    devices: BTreeMap<String, Values>,
+    env: Values,
+}
+
+/// Settings that apply to a single device, with defaults filled in
+#[derive(Debug)]
+pub struct DeviceSettings {
+    pub step: Option<u32>,
+    pub sweep: bool,
+    pub sweep_delay: Delay,
+    pub curve: Curve,
+    pub min: Option<Level>,
+    pub max: Option<Level>,
+}
+
+impl Settings {
+    /// Read the config file if it exists, and the environment variables
+    pub fn load() -> Result<Self, BlightError> {
+        let mut settings = match path() {
+            Ok(path) => match fs::read_to_string(&path) {
+                Ok(contents) => parse(&contents, &path)?,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Settings::default(),
+                Err(err) => return Err(BlightError::ReadConfig(path, err)),
+            },
+            // Without a config directory there can't be a config file, the environment still applies
+            Err(_) => Settings::default(),
+        };
+        settings.read_env(|var| env::var(var).ok())?;
+        Ok(settings)
+    }
+
+    /// Read the `BLIGHT_<KEY>` variables, looked up with `var`
+    fn read_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        for key in KEYS {
+            let name = key.var();
+            let Some(value) = var(&name).filter(|value| !value.is_empty()) else {
+                continue;
+            };
+            let value = key
+                .validate(&value)
+                .ok_or_else(|| ConfigError::invalid_value(key, &value, Origin::Env(name)))?;
+            self.env.insert(key.name, value);
+        }
+        Ok(())
+    }
+
+    /// Scopes that apply to a device (or globally), highest precedence first
+    fn scopes(&self, device: Option<&str>) -> impl Iterator<Item = &Values> {
+        let section = device.and_then(|device| self.devices.get(device));
+        [Some(&self.env), section, Some(&self.global)]
+            .into_iter()
+            .flatten()
+    }
+
+    /// Effective value of a key for a device, or globally
+    fn value(&self, device: Option<&str>, key: &str) -> Option<&str> {
+        self.scopes(device)
+            .find_map(|values| values.get(key))
+            .map(String::as_str)
+            .or_else(|| Key::find(key).and_then(|key| key.default))
+    }
+
+    /// Delay between the steps of a sweep, from `sweep_duration` or `sweep_delay`
+    ///
+    /// Both set the same thing, so the one set in the scope with the highest precedence wins.
+    fn sweep_delay(&self, device: Option<&str>) -> Delay {
+        self.scopes(device)
+            .find_map(|values| {
+                let duration = values.get("sweep_duration").and_then(|d| d.parse().ok());
+                // Sweeps move by 1% of the max brightness per step
+                let delay = duration.map(|duration: Delay| Delay::from(*duration / 100));
+                delay.or_else(|| values.get("sweep_delay").and_then(|d| d.parse().ok()))
+            })
+            .unwrap_or_default()
+    }
+
+    /// LEDs used by `blight led` commands when neither a name nor a selector is given
+    pub fn led_filter(&self) -> Option<LedFilter> {
+        let function = self.value(None, "function");
+        let color = self.value(None, "color");
+        let name = self.value(None, "name");
+        if function.is_none() && color.is_none() && name.is_none() {
+            return None;
+        }
+        let mut filter = LedFilter::new();
+        if let Some(Ok(function)) = function.map(|function| function.to_lowercase().parse()) {
+            filter = filter.function(function);
+        }
+        if let Some(Ok(color)) = color.map(|color| color.to_lowercase().parse()) {
+            filter = filter.color(color);
+        }
+        if let Some(name) = name {
+            filter = filter.device(name);
+        }
+        Some(filter)
+    }
+
+    /// Backlight device to use when none is given with `--device`
+    pub fn device(&self) -> Option<&str> {
+        self.value(None, "device")
+    }
+
+    /// Whether brightness changes are recorded for `blight history` and `blight undo`
+    pub fn history(&self) -> bool {
+        self.value(None, "history")
+            .and_then(parse_bool)
+            .unwrap_or(true)
+    }
+
+    /// Settings for a device, `None` for the global settings
+    pub fn for_device(&self, device: Option<&str>) -> DeviceSettings {
+        let value = |key| self.value(device, key);
+        DeviceSettings {
+            step: value("step").and_then(parse_step),
+            sweep: value("sweep").and_then(parse_bool).unwrap_or_default(),
+            sweep_delay: self.sweep_delay(device),
+            curve: value("curve")
+                .and_then(Curve::from_name)
+                .unwrap_or_default(),
+            min: value("min").and_then(parse_bound),
+            max: value("max").and_then(parse_bound),
+        }
+    }
+
+    /// Effective value of a key given as `<key>` or `device.<name>.<key>`
+    pub fn get(&self, key: &str) -> Result<Option<&str>, BlightError> {
+        let (device, key) = split_key(key)?;
+        Ok(self.value(device, key.name))
+    }
+
+    /// Print the effective global settings and the device sections, in the format of the config file
+    pub fn print(&self) {
+        for key in KEYS {
+            if let Some(value) = self.value(None, key.name) {
+                println!("{} = {value}", key.name);
+            }
+        }
+        for device in self.devices.keys() {
+            println!("\n[{DEVICE_SECTION}{device}]");
+            for key in KEYS.iter().filter(|key| key.per_device) {
+                if let Some(value) = self.value(Some(device), key.name) {
+                    println!("{} = {value}", key.name);
+                }
+            }
+        }
+    }
+}
+
+impl DeviceSettings {
+    /// Sweep unless overridden with `--sweep` or `--no-sweep`
+    pub fn change(&self, flag: Option<Change>) -> Change {
+        match (flag, self.sweep) {
+            (Some(change), _) => change,
+            (None, true) => Change::Sweep,
+            (None, false) => Change::Regular,
+        }
+    }
+
+    /// Clamp policy with the configured min and max, percentages following the curve
+    pub fn clamp_policy(&self, max: u32) -> ClampPolicy {
+        let bound = |level: Level| match (self.curve, level) {
+            (Curve::Linear, Level::Percent(percent)) => Bound::Percent(percent),
+            (Curve::Perceptual, Level::Percent(percent)) => {
+                Bound::Raw(self.curve.to_raw(percent, max))
+            }
+            (_, level) => Bound::Raw(level.resolve(0, max)),
+        };
+        let mut policy = ClampPolicy::new();
+        if let Some(min) = self.min {
+            policy = policy.min(bound(min));
+        }
+        if let Some(max) = self.max {
+            policy = policy.max(bound(max));
+        }
+        policy
+    }
+
+    /// Raw value for a brightness level, following the curve and the clamp policy of the device
+    pub fn resolve(&self, level: Level, device: &Device) -> u32 {
+        self.resolve_from(level, device.current(), device)
+    }
+
+    /// Same as [`DeviceSettings::resolve`], with relative levels starting from `current`
+    pub fn resolve_from(&self, level: Level, current: u32, device: &Device) -> u32 {
+        let value = self.curve.resolve(level, current, device.max());
+        // Values above the max are left as they are, so writing them is still an error
+        if value > device.max() {
+            return value;
+        }
+        device.clamp_policy().apply(value, device.max())
+    }
+
+    /// Raw value after changing the brightness by `step` percent
+    pub fn adjust(&self, device: &Device, step: u32, dir: Direction) -> u32 {
+        match (self.curve, dir) {
+            (Curve::Linear, _) => device.calculate_change(step, dir),
+            (Curve::Perceptual, Direction::Inc) => {
+                self.resolve(Level::RelativePercent(f64::from(step)), device)
+            }
+            (Curve::Perceptual, Direction::Dec) => {
+                self.resolve(Level::RelativePercent(-f64::from(step)), device)
+            }
+        }
+    }
+}
+
+/// Location of the config file
+pub fn path() -> Result<PathBuf, BlightError> {
+    match env::var_os(CONFIG_VAR).filter(|path| !path.is_empty()) {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Ok(paths::CONFIG.dir()?.join("config")),
+    }
+}
+
+/// Split a key given as `<key>` or `device.<name>.<key>` into the device and key
+fn split_key(key: &str) -> Result<(Option<&str>, &'static Key), ConfigError> {
+    let (device, name) = match key
+        .strip_prefix(DEVICE_SECTION)
+        .and_then(|rest| rest.rsplit_once('.'))
+    {
+        Some((device, name)) if !device.is_empty() => (Some(device), name),
+        _ => (None, key),
+    };
+    let Some(found) = Key::find(name) else {
+        return Err(ConfigError::unknown_key(name, Origin::Arg));
+    };
+    if device.is_some() && !found.per_device {
+        return Err(ConfigError {
+            origin: Origin::Arg,
+            kind: ErrorKind::GlobalOnly(found.name),
+        });
+    }
+    Ok((device, found))
+}
+
+/// Section name of a `[device.<name>]` header line, `Err` for other headers and `None` for other lines
+fn section(line: &str) -> Option<Result<&str, ()>> {
+    let header = line.strip_prefix('[')?.strip_suffix(']')?.trim();
+    Some(
+        header
+            .strip_prefix(DEVICE_SECTION)
+            .filter(|name| !name.is_empty())
+            .ok_or(()),
+    )
+}
+
+fn parse(contents: &str, path: &Path) -> Result<Settings, ConfigError> {
+    let mut settings = Settings::default();
+    let mut device: Option<String> = None;
+    for (i, line) in contents.lines().enumerate() {
+        let origin = || Origin::File {
+            path: path.to_owned(),
+            line: i + 1,
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match section(line) {
+            Some(Ok(name)) => {
+                device = Some(name.to_owned());
+                continue;
+            }
+            Some(Err(())) => {
+                return Err(ConfigError {
+                    origin: origin(),
+                    kind: ErrorKind::BadSection(line.to_owned()),
+                });
+            }
+            None => (),
+        }
+        let Some((name, value)) = line.split_once('=') else {
+            return Err(ConfigError {
+                origin: origin(),
+                kind: ErrorKind::BadLine(line.to_owned()),
+            });
+        };
+        let name = name.trim();
+        let key = Key::find(name).ok_or_else(|| ConfigError::unknown_key(name, origin()))?;
+        let value = key
+            .validate(value)
+            .ok_or_else(|| ConfigError::invalid_value(key, value.trim(), origin()))?;
+        match &device {
+            None => settings.global.insert(key.name, value),
+            Some(_) if !key.per_device => {
+                return Err(ConfigError {
+                    origin: origin(),
+                    kind: ErrorKind::GlobalOnly(key.name),
+                });
+            }
+            Some(device) => settings
+                .devices
+                .entry(device.clone())
+                .or_default()
+                .insert(key.name, value),
+        };
+    }
+    Ok(settings)
+}
+
+/// Set a key given as `<key>` or `device.<name>.<key>` in the config file, or remove it if `value` is `None`
+///
+/// Comments and the order of the other lines in the file are kept.
+pub fn write_key(key: &str, value: Option<&str>) -> Result<(), BlightError> {
+    let (device, key) = split_key(key)?;
+    let value = match value {
+        Some(value) => Some(
+            key.validate(value)
+                .ok_or_else(|| ConfigError::invalid_value(key, value, Origin::Arg))?,
+        ),
+        None => None,
+    };
+    let path = path()?;
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(BlightError::ReadConfig(path, err)),
+    };
+    // Make sure the file is valid before changing it, so errors point to the line numbers the user sees
+    parse(&contents, &path)?;
+    create_parent(&path)?;
+    let contents = set_line(&contents, device, key, value.as_deref());
+    fs::write(&path, contents).map_err(|_| BlightError::WriteConfig(path))
+}
+
+/// Contents of a valid config file with a key set in the global scope or a device section, or removed
+///
+/// The key replaces the first line that sets it in the scope, dropping any later ones, or is added after the last
+/// setting of the scope.
+/// Other lines are left untouched.
+fn set_line(contents: &str, device: Option<&str>, key: &Key, value: Option<&str>) -> String {
+    let mut lines: Vec<String> = contents.lines().map(str::to_owned).collect();
+    let mut current: Option<&str> = None;
+    let mut existing = Vec::new();
+    let mut insert_at = None;
+    let mut first_section = None;
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if let Some(name) = section(trimmed) {
+            current = name.ok();
+            first_section.get_or_insert(i);
+            if current.is_some() && current == device {
+                insert_at = Some(i + 1);
+            }
+            continue;
+        }
+        if current != device || trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if trimmed
+            .split_once('=')
+            .is_some_and(|(name, _)| name.trim() == key.name)
+        {
+            existing.push(i);
+        }
+        insert_at = Some(i + 1);
+    }
+
+    let new_line = value.map(|value| format!("{} = {value}", key.name));
+    match (new_line, existing.split_first()) {
+        (Some(line), Some((&first, rest))) => {
+            lines[first] = line;
+            for &i in rest.iter().rev() {
+                lines.remove(i);
+            }
+        }
+        (Some(line), None) => match (insert_at, device) {
+            (Some(i), _) => lines.insert(i, line),
+            (None, None) => lines.insert(first_section.unwrap_or(lines.len()), line),
+            (None, Some(device)) => {
+                if lines.last().is_some_and(|last| !last.trim().is_empty()) {
+                    lines.push(String::new());
+                }
+                lines.push(format!("[{DEVICE_SECTION}{device}]"));
+                lines.push(line);
+            }
+        },
+        (None, _) => {
+            for &i in existing.iter().rev() {
+                lines.remove(i);
+            }
+        }
+    }
+
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    contents
+}
+
+/// Open the config file in `$VISUAL` or `$EDITOR` (`vi` if neither is set), and check it once the editor exits
+pub fn edit() -> Result<(), BlightError> {
+    let path = path()?;
+    if !path.exists() {
+        create_parent(&path)?;
+        fs::write(&path, TEMPLATE).map_err(|_| BlightError::WriteConfig(path.clone()))?;
+    }
+    let editor = ["VISUAL", "EDITOR"]
+        .into_iter()
+        .find_map(|var| env::var(var).ok().filter(|editor| !editor.is_empty()))
+        .unwrap_or_else(|| "vi".to_owned());
+    // The editor may include arguments, e.g. 'code --wait'
+    let status = process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg("sh")
+        .arg(&path)
+        .status();
+    match status {
+        Ok(status) if status.success() => Settings::load().map(|_| ()),
+        _ => Err(BlightError::RunEditor(editor)),
+    }
+}
+
+fn create_parent(path: &Path) -> Result<(), BlightError> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() && !dir.exists() => {
+            fs::create_dir_all(dir).map_err(|_| BlightError::CreateConfigDir(dir.to_owned()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Where an invalid setting came from
+#[derive(Debug)]
+pub enum Origin {
+    File {
+        path: PathBuf,
+        line: usize,
+    },
+    Env(String),
+    /// Arguments of `blight config`
+    Arg,
+}
+
+#[derive(Debug)]
+pub enum ErrorKind {
+    UnknownKey {
+        key: String,
+        suggestion: Option<&'static str>,
+    },
+    InvalidValue {
+        key: &'static str,
+        value: String,
+        expected: &'static str,
+    },
+    GlobalOnly(&'static str),
+    BadSection(String),
+    BadLine(String),
+}
+
+/// An unknown key or invalid value, along with where it came from
+#[derive(Debug)]
+pub struct ConfigError {
+    pub origin: Origin,
+    pub kind: ErrorKind,
+}
+
+impl ConfigError {
+    fn unknown_key(key: &str, origin: Origin) -> Self {
+        let suggestion = args::closest(key, KEYS.iter().map(|key| key.name));
+        Self {
+            origin,
+            kind: ErrorKind::UnknownKey {
+                key: key.to_owned(),
+                suggestion,
+            },
+        }
+    }
+
+    fn invalid_value(key: &Key, value: &str, origin: Origin) -> Self {
+        let kind = ErrorKind::InvalidValue {
+            key: key.name,
+            value: value.to_owned(),
+            expected: key.expected,
+        };
+        Self { origin, kind }
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ErrorKind::UnknownKey { key, .. } => write!(f, "unknown config key '{key}'")?,
+            ErrorKind::InvalidValue {
+                key,
+                value,
+                expected,
+            } => {
+                write!(
+                    f,
+                    "invalid value '{value}' for '{key}' (expected {expected})"
+                )?;
+            }
+            ErrorKind::GlobalOnly(key) => write!(f, "'{key}' can't be set for a single device")?,
+            ErrorKind::BadSection(header) => {
+                write!(
+                    f,
+                    "invalid section '{header}' (expected '[{DEVICE_SECTION}<name>]')"
+                )?;
+            }
+            ErrorKind::BadLine(line) => write!(f, "expected 'key = value', found '{line}'")?,
+        }
+        match &self.origin {
+            Origin::File { path, line } => write!(f, " at line {line} of {}", path.display()),
+            Origin::Env(var) => write!(f, " in ${var}"),
+            Origin::Arg => Ok(()),
+        }
+    }
+}
+
+impl From<ConfigError> for BlightError {
+    fn from(err: ConfigError) -> Self {
+        BlightError::BadConfig(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(contents: &str) -> Result<Settings, ConfigError> {
+        parse(contents, Path::new("config"))
+    }
+
+    #[test]
+    fn unknown_key_suggestion() {
+        let err = parse_str("step = 5\nsweep_dealy = 10ms\n").unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ErrorKind::UnknownKey { ref key, suggestion: Some("sweep_delay") } if key == "sweep_dealy"
+        ));
+        assert!(matches!(err.origin, Origin::File { line: 2, .. }));
+        let err = split_key("device.nvidia_0.curv").unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ErrorKind::UnknownKey {
+                suggestion: Some("curve"),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn bad_sections() {
+        for header in ["[device.]", "[devices.nvidia_0]", "[nvidia_0]"] {
+            let err = parse_str(&format!("step = 5\n{header}\ncurve = linear\n")).unwrap_err();
+            assert!(matches!(err.kind, ErrorKind::BadSection(ref line) if line == header));
+            assert!(matches!(err.origin, Origin::File { line: 2, .. }));
+        }
+        let settings = parse_str("[ device.nvidia_0 ]\ncurve = perceptual\n").unwrap();
+        assert_eq!(
+            settings.value(Some("nvidia_0"), "curve"),
+            Some("perceptual")
+        );
+    }
+
+    #[test]
+    fn global_only_keys() {
+        for key in ["device = amdgpu_bl0", "history = false"] {
+            let err = parse_str(&format!("[device.nvidia_0]\n{key}\n")).unwrap_err();
+            assert!(matches!(err.kind, ErrorKind::GlobalOnly(_)));
+        }
+        assert!(matches!(
+            split_key("device.nvidia_0.history").unwrap_err().kind,
+            ErrorKind::GlobalOnly("history")
+        ));
+        let (device, key) = split_key("device.nvidia_0.step").unwrap();
+        assert_eq!((device, key.name), (Some("nvidia_0"), "step"));
+    }
+
+    #[test]
+    fn precedence() {
+        let mut settings =
+            parse_str("step = 5\nsweep = true\n[device.nvidia_0]\nstep = 10\ncurve = perceptual\n")
+                .unwrap();
+        assert_eq!(settings.value(None, "step"), Some("5"));
+        assert_eq!(settings.value(Some("nvidia_0"), "step"), Some("10"));
+        assert_eq!(settings.value(Some("nvidia_0"), "sweep"), Some("true"));
+        assert_eq!(settings.value(Some("other"), "curve"), Some("linear"));
+
+        settings
+            .read_env(|var| (var == "BLIGHT_STEP").then(|| "20".to_owned()))
+            .unwrap();
+        assert_eq!(settings.value(None, "step"), Some("20"));
+        assert_eq!(settings.value(Some("nvidia_0"), "step"), Some("20"));
+        assert_eq!(settings.for_device(Some("nvidia_0")).step, Some(20));
+        assert_eq!(
+            settings.for_device(Some("nvidia_0")).curve,
+            Curve::Perceptual
+        );
+
+        let err = Settings::default()
+            .read_env(|var| (var == "BLIGHT_CURVE").then(|| "steep".to_owned()))
+            .unwrap_err();
+        assert!(matches!(err.origin, Origin::Env(ref var) if var == "BLIGHT_CURVE"));
+    }
+
+    #[test]
+    fn led_filter() {
+        assert_eq!(Settings::default().led_filter(), None);
+        let settings = parse_str("function = kbd_backlight\nname = input*\n").unwrap();
+        assert_eq!(
+            settings.led_filter(),
+            Some(
+                LedFilter::new()
+                    .function("kbd_backlight".parse().unwrap())
+                    .device("input*")
+            )
+        );
+    }
+
+    #[test]
+    fn sweep_duration() {
+        let mut settings = parse_str("sweep_duration = 500ms\n").unwrap();
+        assert_eq!(
+            *settings.for_device(None).sweep_delay,
+            std::time::Duration::from_millis(5)
+        );
+        settings
+            .read_env(|var| (var == "BLIGHT_SWEEP_DELAY").then(|| "20ms".to_owned()))
+            .unwrap();
+        assert_eq!(
+            *settings.for_device(None).sweep_delay,
+            std::time::Duration::from_millis(20)
+        );
+    }
+
+    #[test]
+    fn set_line_keeps_comments() {
+        let contents = "\
+# Global settings
+step = 5 # coarse
+sweep = true
+
+# Laptop panel
+[device.nvidia_0]
+# Low end
+curve = linear
+";
+        let key = |name| Key::find(name).unwrap();
+
+        // Insert at the end of the scope
+        assert_eq!(
+            set_line(contents, None, key("curve"), Some("perceptual")),
+            contents.replace("sweep = true\n", "sweep = true\ncurve = perceptual\n")
+        );
+        assert_eq!(
+            set_line(contents, Some("nvidia_0"), key("min"), Some("5%")),
+            format!("{contents}min = 5%\n")
+        );
+        assert_eq!(
+            set_line(contents, Some("amdgpu_bl0"), key("step"), Some("10")),
+            format!("{contents}\n[device.amdgpu_bl0]\nstep = 10\n")
+        );
+
+        // Replace in place
+        assert_eq!(
+            set_line(contents, None, key("step"), Some("10")),
+            contents.replace("step = 5 # coarse", "step = 10")
+        );
+        assert_eq!(
+            set_line(contents, Some("nvidia_0"), key("curve"), Some("perceptual")),
+            contents.replace("curve = linear", "curve = perceptual")
+        );
+
+        // Remove
+        assert_eq!(
+            set_line(contents, None, key("sweep"), None),
+            contents.replace("sweep = true\n", "")
+        );
+        assert_eq!(
+            set_line(contents, Some("nvidia_0"), key("curve"), None),
+            contents.replace("curve = linear\n", "")
+        );
+        assert_eq!(set_line(contents, None, key("min"), None), contents);
+    }
+}
@@ -0,0 +1,335 @@
+//! Recent brightness changes of each backlight device, for `blight history` and `blight undo`
+//!
+//! Changes are kept in the `history` file of the state directory (see [`paths`]) as tab separated lines with the time
+//! (seconds since the Unix epoch), the device name, the raw values before and after the change, and the command that
+//! made it. Only the last [`LIMIT`] changes of each device are kept. Recording can be turned off with the `history`
+//! config key.
+//!
+//! Changes of backlights made by `set`, `inc`, `dec`, `preset`, `cycle`, `restore` and i3bar clicks are recorded, a
+//! whole `set -` stream as a single change once it ends. `exec` and effects aren't, as they put the brightness back
+//! when they end, and neither are LEDs. The file is locked while it's read or changed, so concurrent commands don't
+//! lose each other's changes.
+
+use std::{
+    collections::HashMap,
+    env,
+    fmt::Display,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, Write},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use blight::{Device, Light, Snapshot};
+use colored::Colorize;
+
+use super::{paths, BlightError};
+
+/// Number of changes kept for each device
+const LIMIT: usize = 50;
+
+/// A single brightness change
+pub struct Entry {
+    time: u64,
+    device: String,
+    old: u32,
+    new: u32,
+    command: String,
+}
+
+impl Entry {
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(5, '\t');
+        Some(Self {
+            time: fields.next()?.parse().ok()?,
+            device: fields.next()?.to_owned(),
+            old: fields.next()?.parse().ok()?,
+            new: fields.next()?.parse().ok()?,
+            command: fields.next()?.to_owned(),
+        })
+    }
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}\t{}\t{}\t{}\t{}",
+            self.time, self.device, self.old, self.new, self.command
+        )
+    }
+}
+
+fn path() -> Result<PathBuf, BlightError> {
+    Ok(paths::STATE.dir()?.join("history"))
+}
+
+/// Lines that can't be parsed are left out, the file is only written by blight and losing an entry is harmless
+fn parse(contents: &str) -> Vec<Entry> {
+    contents.lines().filter_map(Entry::parse).collect()
+}
+
+/// All recorded changes, oldest first
+fn load() -> Result<Vec<Entry>, BlightError> {
+    let path = path()?;
+    let mut file = match File::open(&path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(BlightError::ReadHistory(path, err)),
+    };
+    let mut contents = String::new();
+    file.lock_shared()
+        .and_then(|()| file.read_to_string(&mut contents))
+        .map_err(|err| BlightError::ReadHistory(path, err))?;
+    Ok(parse(&contents))
+}
+
+/// Change the recorded changes, with the file locked so concurrent commands don't lose each other's changes
+fn update(change: impl FnOnce(&mut Vec<Entry>)) -> Result<(), BlightError> {
+    let path = path()?;
+    if let Some(dir) = path.parent() {
+        if !dir.exists() && fs::create_dir_all(dir).is_err() {
+            return Err(BlightError::WriteHistory(path));
+        }
+    }
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .map_err(|err| BlightError::ReadHistory(path.clone(), err))?;
+    let mut contents = String::new();
+    file.lock()
+        .and_then(|()| file.read_to_string(&mut contents))
+        .map_err(|err| BlightError::ReadHistory(path.clone(), err))?;
+    let mut entries = parse(&contents);
+    change(&mut entries);
+    let contents: String = entries.iter().map(ToString::to_string).collect();
+    file.set_len(0)
+        .and_then(|()| file.rewind())
+        .and_then(|()| file.write_all(contents.as_bytes()))
+        .map_err(|_| BlightError::WriteHistory(path))
+}
+
+/// Record a change made by the running command
+pub fn record(device: &str, old: u32, new: u32) -> Result<(), BlightError> {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    // Tabs and new lines would break the format of the file
+    let command = env::args()
+        .skip(1)
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(['\t', '\n'], " ");
+    let entry = Entry {
+        time,
+        device: device.to_owned(),
+        old,
+        new,
+        command: format!("blight {command}"),
+    };
+    update(|entries| {
+        entries.push(entry);
+        trim(entries);
+    })
+}
+
+/// Record the change of a device made by the running command, unless its brightness is the same as before
+///
+/// History is best effort, failing to record it (e.g. in a read-only home) shouldn't fail the change itself.
+pub fn record_change(device: &Device, old: u32) {
+    if device.current() != old {
+        let _ = record(device.name(), old, device.current());
+    }
+}
+
+/// Record every backlight that changed since the snapshot was captured, e.g. by restoring a save
+pub fn record_since(snapshot: &Snapshot) {
+    for diff in snapshot.diff() {
+        if let Some(current) = diff.current() {
+            let _ = record(diff.name(), diff.saved(), current);
+        }
+    }
+}
+
+/// Keep the newest [`LIMIT`] entries of each device
+fn trim(entries: &mut Vec<Entry>) {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut kept = vec![false; entries.len()];
+    for (i, entry) in entries.iter().enumerate().rev() {
+        let count = counts.entry(entry.device.clone()).or_default();
+        *count += 1;
+        kept[i] = *count <= LIMIT;
+    }
+    let mut kept = kept.into_iter();
+    entries.retain(|_| kept.next().unwrap_or_default());
+}
+
+/// Brightness a device had before its last `n` recorded changes, `n` being at least 1
+pub fn before(device: &str, n: usize) -> Result<u32, BlightError> {
+    before_in(&load()?, device, n)
+}
+
+fn before_in(entries: &[Entry], device: &str, n: usize) -> Result<u32, BlightError> {
+    let changes: Vec<&Entry> = entries
+        .iter()
+        .filter(|entry| entry.device == device)
+        .collect();
+    if changes.is_empty() {
+        return Err(BlightError::NoHistory(device.to_owned()));
+    }
+    changes
+        .len()
+        .checked_sub(n)
+        .map(|i| changes[i].old)
+        .ok_or_else(|| BlightError::NotEnoughHistory {
+            device: device.to_owned(),
+            available: changes.len(),
+        })
+}
+
+/// Remove the last `n` recorded changes of a device, once they've been undone
+pub fn forget(device: &str, n: usize) -> Result<(), BlightError> {
+    update(|entries| forget_in(entries, device, n))
+}
+
+fn forget_in(entries: &mut Vec<Entry>, device: &str, n: usize) {
+    let mut remaining = n;
+    for i in (0..entries.len()).rev() {
+        if remaining == 0 {
+            break;
+        }
+        if entries[i].device == device {
+            entries.remove(i);
+            remaining -= 1;
+        }
+    }
+}
+
+/// Print the recorded changes, newest first
+pub fn print(device: Option<&str>) -> Result<(), BlightError> {
+    let entries: Vec<Entry> = load()?
+        .into_iter()
+        .filter(|entry| device.is_none_or(|device| entry.device == device))
+        .collect();
+    if entries.is_empty() {
+        println!("No changes recorded");
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let width = entries
+        .iter()
+        .map(|entry| entry.device.len())
+        .max()
+        .unwrap_or_default();
+    for entry in entries.iter().rev() {
+        println!(
+            "{:>8} {} {:>6} -> {:<6} {}",
+            ago(now.saturating_sub(entry.time)),
+            format!("{:<width$}", entry.device).green().bold(),
+            entry.old,
+            entry.new,
+            entry.command.dimmed(),
+        );
+    }
+    Ok(())
+}
+
+/// Short description of how long ago something happened, e.g. `5m ago`
+fn ago(secs: u64) -> String {
+    match secs {
+        0..60 => "just now".to_owned(),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86_400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(device: &str, old: u32, new: u32) -> Entry {
+        Entry {
+            time: 0,
+            device: device.to_owned(),
+            old,
+            new,
+            command: "blight set 50".to_owned(),
+        }
+    }
+
+    fn values(entries: &[Entry], device: &str) -> Vec<(u32, u32)> {
+        entries
+            .iter()
+            .filter(|entry| entry.device == device)
+            .map(|entry| (entry.old, entry.new))
+            .collect()
+    }
+
+    #[test]
+    fn parse_lines() {
+        let entries = [entry("amdgpu_bl0", 10, 20), entry("nvidia_0", 5, 0)];
+        let contents: String = entries.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            contents,
+            "0\tamdgpu_bl0\t10\t20\tblight set 50\n0\tnvidia_0\t5\t0\tblight set 50\n"
+        );
+        let parsed = parse(&format!("garbage\n{contents}1\tshort\n"));
+        assert_eq!(values(&parsed, "amdgpu_bl0"), [(10, 20)]);
+        assert_eq!(values(&parsed, "nvidia_0"), [(5, 0)]);
+        assert_eq!(parsed.len(), 2);
+    }
+
+    #[test]
+    fn trim_per_device() {
+        let mut entries: Vec<Entry> = (0..60)
+            .flat_map(|i| [entry("a", i, i + 1), entry("b", i, i + 1)])
+            .chain([entry("c", 0, 1)])
+            .collect();
+        trim(&mut entries);
+        assert_eq!(entries.len(), 2 * LIMIT + 1);
+        for device in ["a", "b"] {
+            let kept = values(&entries, device);
+            assert_eq!(kept.len(), LIMIT);
+            // The oldest changes are dropped
+            assert_eq!(kept.first(), Some(&(10, 11)));
+            assert_eq!(kept.last(), Some(&(59, 60)));
+        }
+        assert_eq!(values(&entries, "c"), [(0, 1)]);
+        // Order across devices is kept
+        assert_eq!(entries.last().map(|entry| entry.device.as_str()), Some("c"));
+    }
+
+    #[test]
+    fn undo() {
+        let mut entries = vec![
+            entry("a", 10, 20),
+            entry("b", 1, 2),
+            entry("a", 20, 30),
+            entry("a", 30, 40),
+        ];
+        assert_eq!(before_in(&entries, "a", 1).unwrap(), 30);
+        assert_eq!(before_in(&entries, "a", 3).unwrap(), 10);
+        assert!(matches!(
+            before_in(&entries, "a", 4),
+            Err(BlightError::NotEnoughHistory { available: 3, .. })
+        ));
+        assert!(matches!(
+            before_in(&entries, "c", 1),
+            Err(BlightError::NoHistory(_))
+        ));
+
+        forget_in(&mut entries, "a", 2);
+        assert_eq!(values(&entries, "a"), [(10, 20)]);
+        assert_eq!(values(&entries, "b"), [(1, 2)]);
+        assert_eq!(before_in(&entries, "a", 1).unwrap(), 10);
+        // Forgetting more than recorded leaves the other devices alone
+        forget_in(&mut entries, "a", 5);
+        assert_eq!(values(&entries, "a"), []);
+        assert_eq!(entries.len(), 1);
+    }
+}
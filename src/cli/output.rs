@@ -0,0 +1,309 @@
+//! Machine-readable output formats for the read commands
+//!
+//! Both formats are versioned with [`SCHEMA_VERSION`], which must be bumped whenever a field is removed or changed.
+//! Adding new fields (JSON) or new trailing columns (porcelain) is not considered a breaking change.
+//!
+//! JSON output is a single object with a `version` field, followed by the command specific data.
+//!
+//! Porcelain output is line based, with tab separated fields and a record type as the first field of each line.
+//! The first line is always `version<TAB>1`. Missing values are left empty.
+//! - `device <name> <current> <max> <percent>` (`status` appends a writable field: 1 or 0)
+//! - `led <name> <devicename> <color> <function> <dimmable> <current> <max> <percent> <device>` (`led info` appends a writable field)
+//! - `error <name> <message>` for devices that failed to initialize
+
+use std::fmt::{Display, Write};
+
+use blight::{led, Device, Light};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Human,
+    Json,
+    Porcelain,
+}
+
+/// A minimal JSON value, as blight doesn't depend on a serialization library
+pub enum Json {
+    Null,
+    Bool(bool),
+    Int(u64),
+    Float(f64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{b}"),
+            Json::Int(n) => write!(f, "{n}"),
+            Json::Float(n) if n.is_finite() => write!(f, "{}", (n * 100.0).round() / 100.0),
+            Json::Float(_) => write!(f, "null"),
+            Json::Str(s) => {
+                f.write_char('"')?;
+                for c in s.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\t' => f.write_str("\\t")?,
+                        c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                        c => f.write_char(c)?,
+                    }
+                }
+                f.write_char('"')
+            }
+            Json::Array(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(fields) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "\"{key}\":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Json::Str(value.to_owned())
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+/// Wrap the data in a versioned JSON document
+pub fn json_document(key: &'static str, data: Json) -> Json {
+    Json::Object(vec![
+        ("version", Json::Int(SCHEMA_VERSION.into())),
+        (key, data),
+    ])
+}
+
+/// Wrap the data in a versioned JSON document and print it
+pub fn print_json(key: &'static str, data: Json) {
+    println!("{}", json_document(key, data));
+}
+
+/// Print the porcelain header, followed by the given records
+pub fn print_porcelain(records: impl IntoIterator<Item = Vec<String>>) {
+    print!("{}", porcelain(records));
+}
+
+fn porcelain(records: impl IntoIterator<Item = Vec<String>>) -> String {
+    let mut out = format!("version\t{SCHEMA_VERSION}\n");
+    for record in records {
+        out.push_str(&record.join("\t"));
+        out.push('\n');
+    }
+    out
+}
+
+/// Porcelain fields can't contain tabs or newlines
+fn field(value: impl Display) -> String {
+    value.to_string().replace(['\t', '\n'], " ")
+}
+
+fn percent(current: u32, max: u32) -> f64 {
+    if max == 0 {
+        return 0.0;
+    }
+    f64::from(current) / f64::from(max) * 100.0
+}
+
+pub fn device_json(device: &Device, writable: Option<bool>) -> Json {
+    let mut fields = vec![
+        ("name", device.name().into()),
+        ("current", Json::Int(device.current().into())),
+        ("max", Json::Int(device.max().into())),
+        ("percent", Json::Float(device.current_percent())),
+    ];
+    if let Some(writable) = writable {
+        fields.push(("writable", Json::Bool(writable)));
+    }
+    Json::Object(fields)
+}
+
+pub fn device_record(device: &Device, writable: Option<bool>) -> Vec<String> {
+    device_fields(device.name(), device.current(), device.max(), writable)
+}
+
+fn device_fields(name: &str, current: u32, max: u32, writable: Option<bool>) -> Vec<String> {
+    let mut record = vec![
+        "device".into(),
+        field(name),
+        current.to_string(),
+        max.to_string(),
+        format!("{:.2}", percent(current, max)),
+    ];
+    if let Some(writable) = writable {
+        record.push(u8::from(writable).to_string());
+    }
+    record
+}
+
+pub fn error_json(name: &str, err: &blight::Error) -> Json {
+    Json::Object(vec![
+        ("name", name.into()),
+        ("error", Json::Str(err.to_string())),
+    ])
+}
+
+pub fn error_record(name: &str, err: &blight::Error) -> Vec<String> {
+    vec!["error".into(), field(name), field(err)]
+}
+
+fn non_empty(s: &str) -> Option<&str> {
+    (!s.is_empty()).then_some(s)
+}
+
+pub fn led_json(
+    led: &led::LedType,
+    device: Option<&led::DeviceInfo>,
+    writable: Option<bool>,
+) -> Json {
+    let name = led.led_name();
+    let device = device.map(|d| {
+        let id = |id: Option<u16>| -> Json { id.map(|id| Json::Str(format!("{id:04x}"))).into() };
+        Json::Object(vec![
+            ("name", Json::Str(d.display_name().into_owned())),
+            ("path", Json::Str(d.path().display().to_string())),
+            ("subsystem", d.subsystem().into()),
+            ("bus", d.bus().map(|b| Json::Str(b.to_string())).into()),
+            ("vendor", id(d.vendor())),
+            ("product", id(d.product())),
+        ])
+    });
+    let mut fields = vec![
+        ("name", led.name().into()),
+        ("devicename", name.devicename().into()),
+        ("color", non_empty(name.color().as_str()).into()),
+        ("function", non_empty(name.function().as_str()).into()),
+        ("dimmable", Json::Bool(led.is_dimmable())),
+        ("current", Json::Int(led.current().into())),
+        ("max", Json::Int(led.max().into())),
+        ("percent", Json::Float(percent(led.current(), led.max()))),
+        ("device", device.into()),
+    ];
+    if let Some(writable) = writable {
+        fields.push(("writable", Json::Bool(writable)));
+    }
+    Json::Object(fields)
+}
+
+pub fn led_record(
+    led: &led::LedType,
+    device: Option<&led::DeviceInfo>,
+    writable: Option<bool>,
+) -> Vec<String> {
+    led_fields(
+        led.led_name(),
+        led.is_dimmable(),
+        (led.current(), led.max()),
+        device.map(led::DeviceInfo::display_name).as_deref(),
+        writable,
+    )
+}
+
+fn led_fields(
+    name: &led::LedName,
+    dimmable: bool,
+    (current, max): (u32, u32),
+    device: Option<&str>,
+    writable: Option<bool>,
+) -> Vec<String> {
+    let mut record = vec![
+        "led".into(),
+        field(name.raw_name()),
+        field(name.devicename().unwrap_or_default()),
+        field(name.color()),
+        field(name.function()),
+        u8::from(dimmable).to_string(),
+        current.to_string(),
+        max.to_string(),
+        format!("{:.2}", percent(current, max)),
+        device.map(field).unwrap_or_default(),
+    ];
+    if let Some(writable) = writable {
+        record.push(u8::from(writable).to_string());
+    }
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blight::ErrorKind;
+
+    #[test]
+    fn json_escapes() {
+        let s = Json::from("quote \" backslash \\ newline \n tab \t cr \r bell \u{7} del \u{7f}");
+        assert_eq!(
+            s.to_string(),
+            r#""quote \" backslash \\ newline \n tab \t cr \u000d bell \u0007 del \u007f""#
+        );
+        assert_eq!(Json::from("é ✓ 😀").to_string(), "\"é ✓ 😀\"");
+    }
+
+    #[test]
+    fn json_values() {
+        let data = Json::Object(vec![
+            ("null", Json::Null),
+            ("bool", Json::Bool(true)),
+            ("int", Json::Int(255)),
+            ("float", Json::Float(47.058_823)),
+            ("nan", Json::Float(f64::NAN)),
+            (
+                "array",
+                Json::Array(vec![Json::from("a"), Json::from(None::<&str>)]),
+            ),
+        ]);
+        assert_eq!(
+            json_document("device", data).to_string(),
+            r#"{"version":1,"device":{"null":null,"bool":true,"int":255,"float":47.06,"nan":null,"array":["a",null]}}"#
+        );
+    }
+
+    #[test]
+    fn porcelain_records() {
+        let name = led::LedName::parse("input3::capslock".into());
+        let records = vec![
+            device_fields("amdgpu_bl0", 120, 255, None),
+            device_fields("acpi_video0", 0, 0, Some(true)),
+            led_fields(&name, false, (1, 1), Some("AT keyboard"), None),
+            led_fields(&name, false, (0, 1), None, Some(false)),
+            error_record("bad\tname", &ErrorKind::NotFound.into()),
+        ];
+        assert_eq!(
+            porcelain(records),
+            "version\t1\n\
+             device\tamdgpu_bl0\t120\t255\t47.06\n\
+             device\tacpi_video0\t0\t0\t0.00\t1\n\
+             led\tinput3::capslock\tinput3\t\tcapslock\t0\t1\t1\t100.00\tAT keyboard\n\
+             led\tinput3::capslock\tinput3\t\tcapslock\t0\t0\t1\t0.00\t\t0\n\
+             error\tbad name\tno known backlight or LED device detected\n"
+        );
+    }
+}
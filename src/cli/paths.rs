@@ -0,0 +1,67 @@
+//! Locations of the files blight keeps between runs
+//!
+//! Directories follow the XDG base directory spec, e.g. saves are stored in `$XDG_STATE_HOME/blight`, which defaults
+//! to `~/.local/state/blight`, and the config file in `$XDG_CONFIG_HOME/blight`. When neither the XDG variable nor
+//! `HOME` is set, as is the case for system services running as root at boot, the system-wide `/var/lib/blight` and
+//! `/etc/blight` are used instead.
+
+use std::{env, fs, os::unix::fs::MetadataExt, path::PathBuf};
+
+use super::BlightError;
+
+/// A kind of directory and where to look for it
+pub struct Base {
+    /// Environment variable holding the base directory
+    var: &'static str,
+    /// Base directory relative to `HOME` when the variable isn't set
+    home: &'static str,
+    /// Directory used by root when neither the variable nor `HOME` is set
+    system: &'static str,
+    /// Directory used by older versions, kept in use as long as the new one doesn't exist
+    legacy: Option<&'static str>,
+}
+
+pub const STATE: Base = Base {
+    var: "XDG_STATE_HOME",
+    home: ".local/state",
+    system: "/var/lib/blight",
+    legacy: Some(".local/share/blight"),
+};
+
+pub const CONFIG: Base = Base {
+    var: "XDG_CONFIG_HOME",
+    home: ".config",
+    system: "/etc/blight",
+    legacy: None,
+};
+
+impl Base {
+    /// Resolve the blight directory, without creating it
+    pub fn dir(&self) -> Result<PathBuf, BlightError> {
+        let home = env_path("HOME");
+        let dir = match (env_path(self.var), &home) {
+            (Some(base), _) => base.join("blight"),
+            (None, Some(home)) => home.join(self.home).join("blight"),
+            (None, None) if is_root() => return Ok(PathBuf::from(self.system)),
+            (None, None) => return Err(BlightError::NoDirectory { var: self.var }),
+        };
+        let legacy = home
+            .zip(self.legacy)
+            .map(|(home, legacy)| home.join(legacy));
+        match legacy {
+            Some(legacy) if !dir.exists() && legacy.is_dir() => Ok(legacy),
+            _ => Ok(dir),
+        }
+    }
+}
+
+/// Value of an environment variable holding a directory, relative paths are ignored as required by the XDG spec
+fn env_path(var: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+}
+
+fn is_root() -> bool {
+    fs::metadata("/proc/self").is_ok_and(|meta| meta.uid() == 0)
+}
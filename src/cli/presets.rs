@@ -0,0 +1,229 @@
+//! Named brightness presets for `blight preset` and `blight cycle`
+//!
+//! Presets are absolute brightness levels stored as `name = level` lines in the `presets` file of the config directory
+//! (see [`paths`]), e.g. `night = 10%`. The same presets apply to backlights and LEDs, levels are resolved for each
+//! light separately.
+
+use std::{fs, io, path::PathBuf};
+
+use blight::Level;
+use colored::Colorize;
+
+use super::{args, paths, BlightError};
+
+/// Words used as subcommands of `blight preset`, which can't be preset names
+const RESERVED: &[&str] = &["list", "set", "delete"];
+
+/// Check that a preset name can be used on the command line and in the presets file
+pub fn validate_name(name: &str) -> Result<String, BlightError> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && !RESERVED.contains(&name)
+        && name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+    if valid {
+        Ok(name.to_owned())
+    } else {
+        Err(BlightError::BadPresetName(name.to_owned()))
+    }
+}
+
+/// Parse the level of a preset, which has to be absolute
+pub fn parse_level(level: &str) -> Result<Level, BlightError> {
+    let parsed: Level = level.parse().map_err(BlightError::InvalidLevel)?;
+    if parsed.is_relative() {
+        return Err(BlightError::RelativePreset(level.to_owned()));
+    }
+    Ok(parsed)
+}
+
+fn path() -> Result<PathBuf, BlightError> {
+    Ok(paths::CONFIG.dir()?.join("presets"))
+}
+
+/// All presets in the order they were added
+pub fn load() -> Result<Vec<(String, Level)>, BlightError> {
+    let path = path()?;
+    match fs::read_to_string(&path) {
+        Ok(contents) => parse(&contents).map_err(|line| BlightError::BadPresetsFile { path, line }),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(err) => Err(BlightError::ReadPresets(path, err)),
+    }
+}
+
+/// Presets in the contents of the presets file, or the number of the first invalid line
+fn parse(contents: &str) -> Result<Vec<(String, Level)>, usize> {
+    let mut presets = vec![];
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let preset = line.split_once('=').and_then(|(name, level)| {
+            let name = validate_name(name.trim()).ok()?;
+            Some((name, parse_level(level.trim()).ok()?))
+        });
+        match preset {
+            Some(preset) => presets.push(preset),
+            None => return Err(i + 1),
+        }
+    }
+    Ok(presets)
+}
+
+/// Level of a single preset
+pub fn get(name: &str) -> Result<Level, BlightError> {
+    let presets = load()?;
+    match presets.iter().find(|(preset, _)| preset == name) {
+        Some((_, level)) => Ok(*level),
+        None => Err(not_found(name, &presets)),
+    }
+}
+
+/// The given presets in order, or all of them if none are given
+pub fn select(names: &[String]) -> Result<Vec<(String, Level)>, BlightError> {
+    let presets = load()?;
+    if names.is_empty() && presets.is_empty() {
+        return Err(BlightError::NoPresets);
+    }
+    if names.is_empty() {
+        return Ok(presets);
+    }
+    names
+        .iter()
+        .map(|name| {
+            presets
+                .iter()
+                .find(|(preset, _)| preset == name)
+                .cloned()
+                .ok_or_else(|| not_found(name, &presets))
+        })
+        .collect()
+}
+
+fn not_found(name: &str, presets: &[(String, Level)]) -> BlightError {
+    let suggestion = args::closest(name, presets.iter().map(|(preset, _)| preset.as_str()));
+    BlightError::NoPresetFound {
+        name: name.to_owned(),
+        suggestion: suggestion.map(str::to_owned),
+    }
+}
+
+/// Add a preset or change its level
+pub fn set(name: &str, level: Level) -> Result<(), BlightError> {
+    let mut presets = load()?;
+    match presets.iter_mut().find(|(preset, _)| preset == name) {
+        Some((_, old)) => *old = level,
+        None => presets.push((name.to_owned(), level)),
+    }
+    write(&presets)
+}
+
+pub fn delete(name: &str) -> Result<(), BlightError> {
+    let mut presets = load()?;
+    let len = presets.len();
+    presets.retain(|(preset, _)| preset != name);
+    if presets.len() == len {
+        return Err(not_found(name, &presets));
+    }
+    write(&presets)
+}
+
+fn write(presets: &[(String, Level)]) -> Result<(), BlightError> {
+    let path = path()?;
+    if let Some(dir) = path.parent() {
+        if !dir.exists() && fs::create_dir_all(dir).is_err() {
+            return Err(BlightError::CreateConfigDir(dir.to_owned()));
+        }
+    }
+    let contents: String = presets
+        .iter()
+        .map(|(name, level)| format!("{name} = {level}\n"))
+        .collect();
+    fs::write(&path, contents).map_err(|_| BlightError::WritePresets(path))
+}
+
+pub fn print() -> Result<(), BlightError> {
+    let presets = load()?;
+    if presets.is_empty() {
+        println!("No presets found");
+    }
+    let width = presets
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or_default();
+    for (name, level) in presets {
+        println!("{} {level}", format!("{name:<width$}").green().bold());
+    }
+    Ok(())
+}
+
+/// Index of the value to switch to when cycling
+///
+/// If the current value matches one of the values (within 1% of the max brightness), the one after it is next,
+/// wrapping around at the end. Otherwise the next one is the lowest value above the current one, or the first value
+/// if there is none.
+pub fn next(values: &[u32], current: u32, max: u32) -> usize {
+    let tolerance = max / 100;
+    if let Some(i) = values
+        .iter()
+        .position(|value| value.abs_diff(current) <= tolerance)
+    {
+        return (i + 1) % values.len();
+    }
+    values
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| **value > current)
+        .min_by_key(|(_, value)| **value)
+        .map_or(0, |(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_presets() {
+        let presets = parse("# comment\n\nnight = 10%\n  day=200  \n").unwrap();
+        assert_eq!(
+            presets,
+            [
+                ("night".to_owned(), Level::Percent(10.0)),
+                ("day".to_owned(), Level::Raw(200))
+            ]
+        );
+        assert_eq!(parse(""), Ok(vec![]));
+        // Relative levels, reserved or invalid names and lines without a level
+        assert_eq!(parse("night = 10%\ndim = -5%\n"), Err(2));
+        assert_eq!(parse("list = 10%"), Err(1));
+        assert_eq!(parse("1st = 10%"), Err(1));
+        assert_eq!(parse("night\n"), Err(1));
+        assert_eq!(parse("night = bright"), Err(1));
+    }
+
+    #[test]
+    fn next_preset() {
+        let values = [10, 50, 100];
+        // Exact match and within 1% of the max brightness
+        assert_eq!(next(&values, 50, 200), 2);
+        assert_eq!(next(&values, 52, 200), 2);
+        assert_eq!(next(&values, 48, 200), 2);
+        // Between presets the lowest one above is next
+        assert_eq!(next(&values, 47, 200), 1);
+        assert_eq!(next(&values, 48, 100), 1);
+        assert_eq!(next(&values, 20, 100), 1);
+        assert_eq!(next(&values, 0, 100), 0);
+        // Wraps around after the last one, or when above all of them
+        assert_eq!(next(&values, 100, 100), 0);
+        assert_eq!(next(&values, 150, 200), 0);
+        // Presets out of order
+        assert_eq!(next(&[100, 10, 50], 20, 100), 2);
+        // No tolerance without a max brightness
+        assert_eq!(next(&[0, 0], 0, 0), 1);
+        assert_eq!(next(&[0], 0, 0), 0);
+    }
+}
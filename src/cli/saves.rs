@@ -0,0 +1,162 @@
+//! Save slots for `blight save`, `blight restore` and `blight saves`
+//!
+//! Each slot stores a [`Snapshot`] in its own file in the state directory (see [`paths`]). The default slot uses
+//! `blight.save`, which is where older versions saved their single device, and named slots are stored as
+//! `slots/<name>.save`.
+//! See [`blight::snapshot`] for the file format.
+
+use std::{fs, io, path::PathBuf};
+
+use blight::{led, snapshot::SnapshotFilter, Change, Snapshot};
+use colored::Colorize;
+
+use super::{history, paths, BlightError, DynError};
+
+pub const DEFAULT_SLOT: &str = "default";
+
+/// Check that a slot name can be used as a file name
+pub fn validate_slot(slot: &str) -> Result<String, BlightError> {
+    let valid = !slot.is_empty()
+        && slot.len() <= 64
+        && !slot.starts_with('.')
+        && slot
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(slot.to_owned())
+    } else {
+        Err(BlightError::BadSlotName(slot.to_owned()))
+    }
+}
+
+fn slot_path(slot: &str) -> Result<PathBuf, BlightError> {
+    let dir = paths::STATE.dir()?;
+    if slot == DEFAULT_SLOT {
+        Ok(dir.join("blight.save"))
+    } else {
+        Ok(dir.join("slots").join(format!("{slot}.save")))
+    }
+}
+
+fn read_slot(slot: &str) -> Result<Snapshot, BlightError> {
+    let path = slot_path(slot)?;
+    if !path.is_file() {
+        return Err(BlightError::NoSaveFound(slot.to_owned()));
+    }
+    let contents = fs::read_to_string(path).map_err(BlightError::ReadFromSave)?;
+    contents.parse().map_err(BlightError::SaveParseErr)
+}
+
+/// Names of all saved slots, with the default slot first
+pub fn slots() -> Result<Vec<String>, BlightError> {
+    let dir = paths::STATE.dir()?;
+    let mut named: Vec<String> = fs::read_dir(dir.join("slots"))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name();
+            name.to_str()?.strip_suffix(".save").map(str::to_owned)
+        })
+        .collect();
+    named.sort();
+    let default = dir
+        .join("blight.save")
+        .is_file()
+        .then(|| DEFAULT_SLOT.to_owned());
+    Ok(default.into_iter().chain(named).collect())
+}
+
+/// Save every backlight, or only `device`, and the LEDs matching the filter
+///
+/// Lights that fail are skipped with a note on stderr, unless none could be saved.
+pub fn save(
+    slot: &str,
+    device: Option<&str>,
+    leds: Option<&led::LedFilter>,
+) -> Result<(), DynError> {
+    let mut filter = SnapshotFilter::new();
+    if let Some(device) = device {
+        filter = filter.device(device);
+    }
+    if let Some(leds) = leds {
+        filter = filter.leds(leds.clone());
+    }
+    let (snapshot, failed) = Snapshot::try_capture(&filter)?;
+    if snapshot.lights().is_empty() {
+        if let Some((_, err)) = failed.into_iter().next() {
+            return Err(err.into());
+        }
+    } else {
+        for (name, err) in &failed {
+            eprintln!("{} {name}: {err}", "Skipped".yellow().bold());
+        }
+    }
+    let path = slot_path(slot)?;
+    if let Some(dir) = path.parent() {
+        if !dir.exists() && fs::create_dir_all(dir).is_err() {
+            return Err(BlightError::CreateSaveDir(dir.to_owned()).into());
+        }
+    }
+    fs::write(&path, snapshot.to_string()).map_err(|_| BlightError::WriteToSaveFile(path))?;
+    Ok(())
+}
+
+/// Restore a slot, recording the backlights it changed for `blight undo` if `history` is set
+pub fn restore(slot: &str, change: Change, history: bool) -> Result<(), DynError> {
+    let snapshot = read_slot(slot)?;
+    // Brightness of the backlights before restoring, best effort like the rest of the history
+    let before = history
+        .then(|| Snapshot::capture(&SnapshotFilter::new()).ok())
+        .flatten();
+    // Lights that were restored are recorded even if others failed
+    let result = snapshot.apply(change);
+    if let Some(before) = before {
+        history::record_since(&before);
+    }
+    Ok(result?)
+}
+
+pub fn delete(slot: &str) -> Result<(), DynError> {
+    let path = slot_path(slot)?;
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            Err(BlightError::NoSaveFound(slot.to_owned()).into())
+        }
+        Err(_) => Err(BlightError::DeleteSave(path).into()),
+    }
+}
+
+pub fn print_slots() -> Result<(), BlightError> {
+    let slots = slots()?;
+    if slots.is_empty() {
+        println!("No saves found");
+    }
+    for slot in slots {
+        println!("{}", slot.green().bold());
+        match read_slot(&slot) {
+            Ok(snapshot) => {
+                for light in snapshot.lights() {
+                    let max = light.max().map(|max| format!("/{max}")).unwrap_or_default();
+                    let percent = light
+                        .percent()
+                        .map(|percent| format!(" ({}%)", percent.round()))
+                        .unwrap_or_default();
+                    let identity = [light.identity().connector(), light.identity().driver()]
+                        .into_iter()
+                        .flatten()
+                        .fold(String::new(), |acc, attr| acc + " " + attr);
+                    println!(
+                        "  {:<9} {} {}{max}{percent}{}",
+                        light.kind().as_str(),
+                        light.name(),
+                        light.value(),
+                        identity.dimmed()
+                    );
+                }
+            }
+            Err(err) => println!("  {}", err.to_string().red()),
+        }
+    }
+    Ok(())
+}
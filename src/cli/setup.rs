@@ -3,6 +3,7 @@
 //! The write permission and ownership of the brightness file is assigned to the video group through the udev rules.
 //! The user is then added to the video group if they're not in the group already.
 
+use super::BlightError;
 use colored::*;
 use std::{
     error::Error,
@@ -24,14 +25,19 @@ const UDEVFILE: &str = "/lib/udev/rules.d/90-blight.rules";
 
 /// The function runs the setup. The udev file 90-blight.rules is placed in /lib/udev/.udev.rules.d/.
 /// The user is added to the 'video' group if they're not already in it.
-pub fn run() {
+///
+/// The progress of each step is printed as it runs, the returned error only reports whether any of them failed.
+pub fn run() -> Result<(), BlightError> {
+    let (mut failed, mut permission_denied) = (false, false);
     println!("{}", "Running Setup".bold());
     print!("UDEV Rules: ");
     match setup_rules() {
         Ok(RulesStatus::WriteOk) => println!("{}", "Ok".green()),
         Ok(RulesStatus::AlreadyExists) => println!("{}", "Ok (already in place)".green()),
         Err(err) => {
+            failed = true;
             if err.kind() == ErrorKind::PermissionDenied {
+                permission_denied = true;
                 println!("{}", "Failed. Run `blight setup` with sudo.".red())
             } else {
                 println!("{} {}", "Error:".red(), err);
@@ -41,8 +47,15 @@ pub fn run() {
     print!("Video Group: ");
     match setup_group() {
         GroupResult::Exists => println!("{}", "Ok (already in group)".green()),
-        GroupResult::Err(err) => println!("{} {}", "Error:".red(), err),
-        GroupResult::UnknownErr => println!("{}", "Failed. Run `blight setup` with sudo.".red(),),
+        GroupResult::Err(err) => {
+            failed = true;
+            println!("{} {}", "Error:".red(), err)
+        }
+        GroupResult::UnknownErr => {
+            failed = true;
+            permission_denied = true;
+            println!("{}", "Failed. Run `blight setup` with sudo.".red(),)
+        }
         GroupResult::Ok => println!("{}", "Ok".green()),
     }
 
@@ -51,6 +64,10 @@ pub fn run() {
         "Recommended: Reboot your system once the setup completes successfully.".yellow(),
         "You can run `blight status` to check if you have gained write permissions.".yellow()
     );
+    if failed {
+        return Err(BlightError::SetupFailed { permission_denied });
+    }
+    Ok(())
 }
 
 enum RulesStatus {
@@ -0,0 +1,49 @@
+//! Minimal SIGINT/SIGTERM/SIGHUP handling, so that long-running commands can clean up before exiting
+
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+
+const SIGHUP: i32 = 1;
+const SIGINT: i32 = 2;
+const SIGTERM: i32 = 15;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+/// The last signal received, 0 if none was
+static RECEIVED: AtomicI32 = AtomicI32::new(0);
+
+extern "C" {
+    fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+    fn kill(pid: i32, sig: i32) -> i32;
+}
+
+extern "C" fn on_signal(signum: i32) {
+    RECEIVED.store(signum, Ordering::Relaxed);
+    INTERRUPTED.store(true, Ordering::Relaxed);
+}
+
+/// Install handlers for SIGHUP, SIGINT and SIGTERM, returning a flag that is set once any of them is received
+///
+/// Instead of terminating the process, the signals only set the flag, so callers must poll it.
+pub fn catch_interrupts() -> &'static AtomicBool {
+    for signum in [SIGHUP, SIGINT, SIGTERM] {
+        // Safety: the handler only performs an atomic store, which is async-signal-safe
+        unsafe {
+            signal(signum, on_signal);
+        }
+    }
+    &INTERRUPTED
+}
+
+/// Pass the last received signal on to a child process, so it exits and the caller can clean up after it
+///
+/// SIGINT is left out, as Ctrl-C in a terminal already sends it to the child along with the rest of the process group.
+pub fn forward(child: &std::process::Child) {
+    let signum = RECEIVED.swap(0, Ordering::Relaxed);
+    if matches!(signum, SIGHUP | SIGTERM) {
+        if let Ok(pid) = i32::try_from(child.id()) {
+            // Safety: kill only sends a signal, the child may have exited already which is harmless
+            unsafe {
+                kill(pid, signum);
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Brightness levels read line by line from stdin, for `blight set -` and `blight led set <led> -`
+//!
+//! Lines are read on a separate thread, so new values keep arriving while a write (or a sweep) is in progress. Empty
+//! lines are ignored, and invalid ones are skipped with a note on stderr instead of ending the stream. The stream also
+//! ends early once `stop` is set, e.g. by Ctrl-C.
+
+use std::{
+    io::{self, BufRead},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use blight::Level;
+use colored::Colorize;
+
+use super::{BlightError, DynError};
+
+/// How often `stop` is checked while waiting for a line
+const POLL: Duration = Duration::from_millis(25);
+
+/// A level and the line it was read from
+pub struct Line {
+    pub number: usize,
+    pub level: Level,
+}
+
+/// Parse a coalesce window, in milliseconds or with a unit (e.g. `100` or `1s`)
+pub fn parse_coalesce(arg: &str) -> Option<Duration> {
+    arg.parse::<u64>()
+        .map(Duration::from_millis)
+        .ok()
+        .or_else(|| arg.parse::<blight::Delay>().ok().map(|delay| *delay))
+}
+
+/// Read levels from stdin until it's closed or `stop` is set, passing them on to `apply` in batches
+///
+/// Without a coalesce window, each level is applied on its own as soon as it arrives. With one, at most one batch is
+/// applied per window: levels arriving within the window after a write are collected and applied together once it has
+/// passed, so `apply` can go straight to the resulting value instead of writing every stale one.
+pub fn run(
+    coalesce: Option<Duration>,
+    stop: &AtomicBool,
+    mut apply: impl FnMut(&[Line]) -> Result<(), DynError>,
+) -> Result<(), DynError> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for (i, line) in io::stdin().lock().lines().enumerate() {
+            let failed = line.is_err();
+            if tx.send((i + 1, line)).is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    let mut last_write: Option<Instant> = None;
+    while let Some(first) = next_line(&rx, None, stop) {
+        let mut raw = vec![first];
+        if let Some(window) = coalesce {
+            let deadline = last_write.map_or_else(Instant::now, |last| last + window);
+            while let Some(line) = next_line(&rx, Some(deadline), stop) {
+                raw.push(line);
+            }
+            raw.extend(rx.try_iter());
+        }
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let mut lines = Vec::with_capacity(raw.len());
+        for (number, line) in raw {
+            let line = line.map_err(BlightError::ReadStream)?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match line.parse() {
+                Ok(level) => lines.push(Line { number, level }),
+                Err(err) => skip(number, &err),
+            }
+        }
+        if !lines.is_empty() {
+            apply(&lines)?;
+            last_write = Some(Instant::now());
+        }
+    }
+    Ok(())
+}
+
+/// The value an LED ends up at after `level`, any level above 0 turns a non-dimmable LED on (like `LedGroup::set_level`)
+pub fn led_value(level: Level, current: u32, max: u32, dimmable: bool) -> u32 {
+    if dimmable {
+        return level.resolve(current, max).min(max);
+    }
+    let on = match level {
+        Level::Raw(value) => value != 0,
+        Level::Percent(percent) => percent > 0.0,
+        Level::RelativeRaw(delta) => i64::from(current) + delta > 0,
+        Level::RelativePercent(delta) => {
+            f64::from(current) / f64::from(max.max(1)) * 100.0 + delta > 0.0
+        }
+    };
+    u32::from(on)
+}
+
+/// Wait for the next line until `deadline` (if any), returning `None` once it has passed, stdin is closed or `stop` is set
+fn next_line<T>(rx: &Receiver<T>, deadline: Option<Instant>, stop: &AtomicBool) -> Option<T> {
+    while !stop.load(Ordering::Relaxed) {
+        let timeout = match deadline {
+            Some(deadline) => deadline.checked_duration_since(Instant::now())?.min(POLL),
+            None => POLL,
+        };
+        match rx.recv_timeout(timeout) {
+            Ok(line) => return Some(line),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return None,
+        }
+    }
+    None
+}
+
+/// Report a line that couldn't be applied, without ending the stream
+pub fn skip(number: usize, reason: &dyn std::fmt::Display) {
+    eprintln!("{} line {number}: {reason}", "Skipped".yellow().bold());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn led_values() {
+        assert_eq!(led_value(Level::Percent(40.0), 0, 255, true), 102);
+        assert_eq!(led_value(Level::Raw(300), 0, 255, true), 255);
+        assert_eq!(led_value(Level::Percent(40.0), 0, 1, false), 1);
+        assert_eq!(led_value(Level::Percent(0.0), 1, 1, false), 0);
+        assert_eq!(led_value(Level::RelativePercent(-50.0), 1, 1, false), 1);
+        assert_eq!(led_value(Level::RelativeRaw(-1), 1, 1, false), 0);
+    }
+}
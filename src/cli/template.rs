@@ -0,0 +1,214 @@
+//! Output templates for `--format`, e.g. `☀ {percent}% [{bar:10}]`
+//!
+//! Placeholders are written in braces, literal braces are escaped by doubling them (`{{` and `}}`):
+//! - `{name}` - device name
+//! - `{raw}` - current raw brightness value
+//! - `{max}` - max raw brightness value
+//! - `{percent}` - brightness percentage rounded to a whole number
+//! - `{percent_precise}` - brightness percentage with two decimal places
+//! - `{bar}` - brightness bar, followed by an optional width and style: `{bar:20}`, `{bar:unicode}`, `{bar:20:unicode}`
+
+use std::fmt::Write;
+
+const DEFAULT_BAR_WIDTH: usize = 10;
+const MAX_BAR_WIDTH: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BarStyle {
+    Ascii,
+    Unicode,
+}
+
+impl BarStyle {
+    fn chars(self) -> (char, char) {
+        match self {
+            BarStyle::Ascii => ('#', '-'),
+            BarStyle::Unicode => ('█', '░'),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Name,
+    Raw,
+    Max,
+    Percent,
+    PercentPrecise,
+    Bar { width: usize, style: BarStyle },
+}
+
+/// A parsed output template
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template(Vec<Segment>);
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let Some(end) = rest.find('}') else {
+                        return Err("unclosed '{' (use '{{' for a literal brace)".into());
+                    };
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(parse_placeholder(&rest[..end])?);
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => return Err("unmatched '}' (use '}}' for a literal brace)".into()),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self(segments))
+    }
+
+    pub fn render(&self, name: &str, current: u32, max: u32) -> String {
+        let fraction = if max == 0 {
+            0.0
+        } else {
+            (f64::from(current) / f64::from(max)).clamp(0.0, 1.0)
+        };
+        let mut out = String::new();
+        for segment in &self.0 {
+            // Writing to a String can't fail
+            let _ = match segment {
+                Segment::Literal(s) => write!(out, "{s}"),
+                Segment::Name => write!(out, "{name}"),
+                Segment::Raw => write!(out, "{current}"),
+                Segment::Max => write!(out, "{max}"),
+                Segment::Percent => write!(out, "{}", (fraction * 100.0).round()),
+                Segment::PercentPrecise => write!(out, "{:.2}", fraction * 100.0),
+                Segment::Bar { width, style } => {
+                    let (full, empty) = style.chars();
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    let filled = ((fraction * *width as f64).round() as usize).min(*width);
+                    out.extend(std::iter::repeat_n(full, filled));
+                    out.extend(std::iter::repeat_n(empty, width - filled));
+                    Ok(())
+                }
+            };
+        }
+        out
+    }
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<Segment, String> {
+    let mut parts = placeholder.split(':');
+    let segment = match parts.next().unwrap_or_default() {
+        "name" => Segment::Name,
+        "raw" => Segment::Raw,
+        "max" => Segment::Max,
+        "percent" => Segment::Percent,
+        "percent_precise" => Segment::PercentPrecise,
+        "bar" => {
+            let (mut width, mut style) = (DEFAULT_BAR_WIDTH, BarStyle::Ascii);
+            for arg in parts.by_ref() {
+                match arg {
+                    "" => (),
+                    "ascii" => style = BarStyle::Ascii,
+                    "unicode" => style = BarStyle::Unicode,
+                    w if let Ok(w) = w.parse() && (1..=MAX_BAR_WIDTH).contains(&w) => width = w,
+                    _ => {
+                        return Err(format!(
+                            "invalid bar option '{arg}' (expected a width between 1 and {MAX_BAR_WIDTH}, 'ascii' or 'unicode')"
+                        ))
+                    }
+                }
+            }
+            Segment::Bar { width, style }
+        }
+        other => {
+            return Err(format!(
+                "unknown placeholder '{{{other}}}' (expected name, raw, max, percent, percent_precise or bar)"
+            ))
+        }
+    };
+    if parts.next().is_some() {
+        return Err(format!(
+            "placeholder '{{{placeholder}}}' doesn't take any options"
+        ));
+    }
+    Ok(segment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, current: u32, max: u32) -> String {
+        Template::parse(template)
+            .unwrap()
+            .render("amdgpu_bl0", current, max)
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(
+            render(
+                "{name}: {raw}/{max} {percent}% ({percent_precise}%)",
+                120,
+                255
+            ),
+            "amdgpu_bl0: 120/255 47% (47.06%)"
+        );
+        assert_eq!(render("{percent} {percent_precise}", 5, 0), "0 0.00");
+        assert_eq!(render("plain text", 1, 2), "plain text");
+    }
+
+    #[test]
+    fn bars() {
+        assert_eq!(render("[{bar}]", 50, 100), "[#####-----]");
+        assert_eq!(render("[{bar:4}]", 100, 100), "[####]");
+        assert_eq!(render("[{bar:unicode}]", 0, 100), "[░░░░░░░░░░]");
+        assert_eq!(render("[{bar:3:unicode}]", 2, 3), "[██░]");
+        assert_eq!(render("[{bar::ascii}]", 10, 100), "[#---------]");
+        // Values above max are shown as a full bar
+        assert_eq!(render("{bar:2}", 150, 100), "##");
+
+        assert!(Template::parse("{bar:0}").is_err());
+        assert!(Template::parse(&format!("{{bar:{}}}", MAX_BAR_WIDTH + 1)).is_err());
+        assert!(Template::parse("{bar:wide}").is_err());
+    }
+
+    #[test]
+    fn escaped_braces() {
+        assert_eq!(render("{{{percent}}}", 50, 100), "{50}");
+        assert_eq!(render("{{name}}", 50, 100), "{name}");
+    }
+
+    #[test]
+    fn invalid_templates() {
+        let err = |template| Template::parse(template).unwrap_err();
+        assert_eq!(
+            err("{brightness}"),
+            "unknown placeholder '{brightness}' (expected name, raw, max, percent, percent_precise or bar)"
+        );
+        assert_eq!(
+            err("{percent:2}"),
+            "placeholder '{percent:2}' doesn't take any options"
+        );
+        assert_eq!(
+            err("{percent"),
+            "unclosed '{' (use '{{' for a literal brace)"
+        );
+        assert_eq!(err("50}"), "unmatched '}' (use '}}' for a literal brace)");
+        assert!(Template::parse("{}").is_err());
+    }
+}
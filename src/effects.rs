@@ -0,0 +1,333 @@
+//! Time-based software brightness effects (breathe, blink, pulse and fade) for backlights and LEDs
+//!
+//! Kernel LED triggers only cover a handful of effects and are not available for backlight devices at all.
+//! The [`Effect`] type drives any [`Dimmable`] [`Light`] by writing brightness values at a fixed frame rate, which works
+//! the same way for a [`Device`][crate::Device], a dimmable [`Led`][crate::led::Led] or several of them in lockstep.
+//!
+//! The original brightness of each light is restored once an effect stops, whether it ran to completion,
+//! was stopped early, or failed midway (unless restoring is explicitly disabled with [`Effect::restore`]).
+//!
+//! # Usage
+//! ```no_run
+//! use std::time::Duration;
+//! use blight::{effects::{Effect, Waveform}, led, Device};
+//!
+//! fn main() -> blight::Result<()> {
+//!     // Pulse the screen once as a visual bell
+//!     let mut screen = Device::new(None)?;
+//!     Effect::new(Waveform::Pulse).run(&mut screen)?;
+//!
+//!     // Blink all dimmable keyboard backlights 3 times
+//!     let filter = led::LedFilter::new().function(led::Function::KbdBacklight).dimmable(true);
+//!     let mut kbds: Vec<_> = led::LedGroup::new(&filter)?
+//!         .into_iter()
+//!         .filter_map(|led| match led {
+//!             led::LedType::Dimmable(led) => Some(led),
+//!             led::LedType::NonDimmable(_) => None,
+//!         })
+//!         .collect();
+//!     Effect::new(Waveform::Blink).repeat(3).run_all(&mut kbds)?;
+//!
+//!     // Slowly "breathe" the keyboard backlight between 10% and 80%, 5 times
+//!     if let led::LedType::Dimmable(mut kbd) = led::Led::new("platform::kbd_backlight".into())? {
+//!         Effect::new(Waveform::Breathe)
+//!             .period(Duration::from_secs(4))
+//!             .amplitude(10.0, 80.0)
+//!             .repeat(5)
+//!             .run(&mut kbd)?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::{
+    f64::consts::PI,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{utils, Delay, Dimmable, Light};
+
+/// Shape of the brightness curve of an [`Effect`] over a single period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    /// Smoothly rise from min to max and fall back to min (sine wave)
+    Breathe,
+    /// Stay at max for the first half of the period and at min for the second half
+    Blink,
+    /// Quickly rise to max and slowly decay back to min
+    Pulse,
+    /// Linearly move from min to max over the period
+    Fade,
+}
+
+impl Waveform {
+    /// Brightness level between `0.0` (min) and `1.0` (max) at `progress` (`0.0..=1.0`) through a period
+    fn level(self, progress: f64) -> f64 {
+        const ATTACK: f64 = 0.15;
+        match self {
+            Waveform::Breathe => (1.0 - (2.0 * PI * progress).cos()) / 2.0,
+            Waveform::Blink => {
+                if progress < 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Waveform::Pulse if progress < ATTACK => progress / ATTACK,
+            Waveform::Pulse => (1.0 - (progress - ATTACK) / (1.0 - ATTACK)).powi(2),
+            Waveform::Fade => progress,
+        }
+    }
+}
+
+/// A time-based brightness effect that can be run on any [`Light`]
+///
+/// See [module][self] level docs for usage examples.
+#[derive(Debug, Clone)]
+pub struct Effect {
+    waveform: Waveform,
+    period: Duration,
+    min: f64,
+    max: f64,
+    repeat: Option<u32>,
+    frame: Delay,
+    restore: bool,
+}
+
+impl Effect {
+    /// Create a new effect with defaults suitable for the given waveform
+    ///
+    /// All effects span the full brightness range (0-100%) by default and update brightness every 25ms. Other defaults:
+    /// - [`Waveform::Breathe`]: period of 3s, repeats forever
+    /// - [`Waveform::Blink`]: period of 500ms, repeats 3 times
+    /// - [`Waveform::Pulse`]: period of 600ms, runs once
+    /// - [`Waveform::Fade`]: period of 1s, runs once
+    #[must_use]
+    pub fn new(waveform: Waveform) -> Self {
+        let (period, repeat) = match waveform {
+            Waveform::Breathe => (Duration::from_secs(3), None),
+            Waveform::Blink => (Duration::from_millis(500), Some(3)),
+            Waveform::Pulse => (Duration::from_millis(600), Some(1)),
+            Waveform::Fade => (Duration::from_secs(1), Some(1)),
+        };
+        Self {
+            waveform,
+            period,
+            min: 0.0,
+            max: 100.0,
+            repeat,
+            frame: Delay::default(),
+            restore: true,
+        }
+    }
+
+    /// Duration of a single cycle of the effect
+    #[must_use]
+    pub fn period(mut self, period: Duration) -> Self {
+        self.period = period;
+        self
+    }
+
+    /// Brightness range of the effect, as percentages of the max brightness of the light
+    ///
+    /// Values are clamped between 0 and 100. If `min` is larger than `max`, the waveform is inverted
+    /// (e.g. a [`Waveform::Fade`] from 100 to 0 fades the light out).
+    #[must_use]
+    pub fn amplitude(mut self, min: f64, max: f64) -> Self {
+        self.min = min.clamp(0.0, 100.0);
+        self.max = max.clamp(0.0, 100.0);
+        self
+    }
+
+    /// Run the effect for the given number of periods
+    #[must_use]
+    pub fn repeat(mut self, times: u32) -> Self {
+        self.repeat = Some(times);
+        self
+    }
+
+    /// Run the effect until it is stopped (see [`Effect::run_until`])
+    #[must_use]
+    pub fn forever(mut self) -> Self {
+        self.repeat = None;
+        self
+    }
+
+    /// Delay between each brightness update
+    ///
+    /// Shorter delays produce smoother effects at the cost of more writes to the brightness file.
+    #[must_use]
+    pub fn frame_delay(mut self, delay: Delay) -> Self {
+        self.frame = delay;
+        self
+    }
+
+    /// Whether the original brightness should be restored once the effect stops (`true` by default)
+    ///
+    /// When disabled, the light is left at the final level of the waveform, which is mainly useful for [`Waveform::Fade`].
+    #[must_use]
+    pub fn restore(mut self, restore: bool) -> Self {
+        self.restore = restore;
+        self
+    }
+
+    /// Run the effect on a single light, blocking until it completes
+    ///
+    /// Effects that repeat [forever][Effect::forever] never complete, use [`Effect::run_until`] to stop them.
+    ///
+    /// # Errors
+    /// - All possible errors returned by [`Light::write_value`]
+    pub fn run<L: Light + Dimmable>(&self, light: &mut L) -> crate::Result<()> {
+        self.run_all_until(std::slice::from_mut(light), &AtomicBool::new(false))
+    }
+
+    /// Run the effect on a single light until it completes or `stop` is set to `true`
+    ///
+    /// # Errors
+    /// - All possible errors returned by [`Light::write_value`]
+    pub fn run_until<L: Light + Dimmable>(
+        &self,
+        light: &mut L,
+        stop: &AtomicBool,
+    ) -> crate::Result<()> {
+        self.run_all_until(std::slice::from_mut(light), stop)
+    }
+
+    /// Run the effect on multiple lights in lockstep, blocking until it completes
+    ///
+    /// # Errors
+    /// - All possible errors returned by [`Light::write_value`]
+    pub fn run_all<L: Light + Dimmable>(&self, lights: &mut [L]) -> crate::Result<()> {
+        self.run_all_until(lights, &AtomicBool::new(false))
+    }
+
+    /// Run the effect on multiple lights in lockstep until it completes or `stop` is set to `true`
+    ///
+    /// # Errors
+    /// - All possible errors returned by [`Light::write_value`]
+    pub fn run_all_until<L: Light + Dimmable>(
+        &self,
+        lights: &mut [L],
+        stop: &AtomicBool,
+    ) -> crate::Result<()> {
+        let original: Vec<L::Value> = lights.iter().map(Light::current).collect();
+        let result = self.animate(lights, stop);
+        let last_level = if self.restore {
+            None
+        } else {
+            Some(self.waveform.level(1.0))
+        };
+        // Restore even if the effect failed midway, but report the original error
+        for (light, original) in lights.iter_mut().zip(original) {
+            let restored = match last_level {
+                Some(level) => self.write_level(light, level),
+                None if light.current() != original => light.write_value(original),
+                None => Ok(()),
+            };
+            if result.is_ok() {
+                restored?;
+            }
+        }
+        result
+    }
+
+    fn animate<L: Light>(&self, lights: &mut [L], stop: &AtomicBool) -> crate::Result<()> {
+        let total = self.repeat.map(|times| self.period * times);
+        let start = Instant::now();
+        while !stop.load(Ordering::Relaxed) {
+            let elapsed = start.elapsed();
+            if total.is_some_and(|total| elapsed >= total) || self.period.is_zero() {
+                break;
+            }
+            let progress = (elapsed.as_secs_f64() / self.period.as_secs_f64()).fract();
+            let level = self.waveform.level(progress);
+            for light in lights.iter_mut() {
+                self.write_level(light, level)?;
+            }
+            let remaining = total.map_or(*self.frame, |total| total.saturating_sub(elapsed));
+            thread::sleep((*self.frame).min(remaining));
+        }
+        Ok(())
+    }
+
+    /// Write the given waveform level to the light, only if it differs from the current value
+    fn write_level<L: Light>(&self, light: &mut L, level: f64) -> crate::Result<()> {
+        let percent = self.min + (self.max - self.min) * level;
+        let value = utils::percent_to_value(percent, light.max().into());
+        let value = L::Value::try_from(value).unwrap_or_default();
+        if value != light.current() {
+            light.write_value(value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{with_test_env, MockInterface};
+
+    #[test]
+    fn waveform_levels() {
+        let cases = [
+            (Waveform::Breathe, 0.0, 0.0),
+            (Waveform::Breathe, 0.5, 1.0),
+            (Waveform::Blink, 0.25, 1.0),
+            (Waveform::Blink, 0.75, 0.0),
+            (Waveform::Pulse, 0.15, 1.0),
+            (Waveform::Pulse, 1.0, 0.0),
+            (Waveform::Fade, 0.5, 0.5),
+        ];
+        for (i, (waveform, progress, expected)) in cases.into_iter().enumerate() {
+            let level = waveform.level(progress);
+            assert!((level - expected).abs() < 1e-9, "case {i} failed: {level}");
+        }
+    }
+
+    #[test]
+    fn restores_original() {
+        with_test_env(&["generic"], || {
+            let mut d = MockInterface::new("generic");
+            Effect::new(Waveform::Blink)
+                .period(Duration::from_millis(20))
+                .frame_delay(Delay::from_millis(5))
+                .amplitude(10.0, 90.0)
+                .repeat(2)
+                .run(&mut d)
+                .expect("failed to run effect");
+            d.reload();
+            assert_eq!(d.current(), 50, "original brightness was not restored");
+        });
+    }
+
+    #[test]
+    fn stops_on_request() {
+        with_test_env(&["generic"], || {
+            let mut d = MockInterface::new("generic");
+            let stop = AtomicBool::new(true);
+            Effect::new(Waveform::Breathe)
+                .run_until(&mut d, &stop)
+                .expect("failed to run effect");
+            d.reload();
+            assert_eq!(d.current(), 50);
+        });
+    }
+
+    #[test]
+    fn fade_without_restore() {
+        with_test_env(&["generic"], || {
+            let mut d = MockInterface::new("generic");
+            Effect::new(Waveform::Fade)
+                .period(Duration::from_millis(30))
+                .frame_delay(Delay::from_millis(5))
+                .amplitude(90.0, 10.0)
+                .restore(false)
+                .run(&mut d)
+                .expect("failed to run effect");
+            d.reload();
+            assert_eq!(d.current(), 10, "fade did not end at the target level");
+        });
+    }
+}
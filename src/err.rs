@@ -27,6 +27,7 @@ impl Error {
     }
 
     /// Get the [`ErrorKind`] to distinguish between different error types
+    #[must_use]
     pub fn kind(&self) -> &ErrorKind {
         &self.kind
     }
@@ -60,7 +61,9 @@ impl std::error::Error for Error {
 /// Different kinds of possible errors
 ///
 /// The `Display` trait impl provides human-friendly, descriptive messages for each variant.
+/// New variants may be added in minor releases, so matching on it requires a wildcard arm.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum ErrorKind {
     ReadDir {
         dir: &'static str,
@@ -76,6 +79,13 @@ pub enum ErrorKind {
     },
     SweepError,
     NotFound,
+    ReadDeviceInfo,
+    RunCommand {
+        command: String,
+    },
+    CommandOutput {
+        output: String,
+    },
     #[cfg(feature = "locking")]
     LockError {
         blocked: bool,
@@ -85,7 +95,7 @@ pub enum ErrorKind {
 impl std::fmt::Display for ErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ErrorKind::ReadDir { dir } => write!(f, "failed to read {dir} directory",),
+            ErrorKind::ReadDir { dir } => write!(f, "failed to read {dir} directory"),
             ErrorKind::NotFound => write!(f, "no known backlight or LED device detected"),
             ErrorKind::WriteValue { device } => {
                 write!(
@@ -95,6 +105,13 @@ impl std::fmt::Display for ErrorKind {
             }
             ErrorKind::ReadCurrent => write!(f, "failed to read current brightness value"),
             ErrorKind::ReadMax => write!(f, "failed to read max brightness value"),
+            ErrorKind::ReadDeviceInfo => {
+                write!(f, "failed to resolve the hardware device of the LED")
+            }
+            ErrorKind::RunCommand { command } => write!(f, "failed to run command '{command}'"),
+            ErrorKind::CommandOutput { output } => {
+                write!(f, "expected the command to print a number, got '{output}'")
+            }
             ErrorKind::SweepError => {
                 write!(f, "failed to perform a sweep-write on the brightness file")
             }
@@ -0,0 +1,164 @@
+//! Temporary brightness changes that are undone when they go out of scope
+
+use std::ops::{Deref, DerefMut};
+
+use crate::{Delay, Dimmable, Light, Result};
+
+/// Restores the brightness a light had when the guard was created once it's dropped
+///
+/// Created with [`Light::guard`]. The guard dereferences to the light, so the brightness can be changed through it
+/// as usual, e.g. to raise it while a video plays. Errors while restoring on drop are ignored, use
+/// [`BrightnessGuard::restore`] to handle them instead, or [`BrightnessGuard::keep`] to leave the brightness as is.
+///
+/// The original brightness is written as is, ignoring the [clamp policy][Light::clamp_policy] of the light.
+///
+/// # Examples
+/// ```no_run
+/// use blight::{Delay, Device, Light};
+///
+/// fn main() -> blight::Result<()> {
+///     let mut dev = Device::new(None)?;
+///     let max = dev.max();
+///     let mut guard = dev.guard().sweep(Delay::default());
+///     guard.write_value(max)?;
+///     // ... present something
+///     guard.restore() // smoothly dims back to the original brightness
+/// }
+/// ```
+pub struct BrightnessGuard<'a, L: Light + ?Sized> {
+    light: &'a mut L,
+    original: L::Value,
+    sweep: Option<(Delay, SweepFn<L>)>,
+    armed: bool,
+}
+
+type SweepFn<L> = fn(&mut L, <L as Light>::Value, Delay) -> Result<()>;
+
+impl<'a, L: Light + ?Sized> BrightnessGuard<'a, L> {
+    /// Guard the current brightness of a light, same as [`Light::guard`]
+    pub fn new(light: &'a mut L) -> Self {
+        let original = light.current();
+        Self {
+            light,
+            original,
+            sweep: None,
+            armed: true,
+        }
+    }
+
+    /// The brightness that will be restored
+    #[must_use]
+    pub fn original(&self) -> L::Value {
+        self.original
+    }
+
+    /// Restore the original brightness now, reporting any errors
+    ///
+    /// The brightness is reloaded first, so changes made by other programs in the meantime are undone as well.
+    ///
+    /// # Errors
+    /// - All possible errors returned by [`Light::write_value_unclamped`] and [`Light::sweep_write_unclamped`]
+    pub fn restore(mut self) -> Result<()> {
+        self.armed = false;
+        self.write_original()
+    }
+
+    /// Keep the current brightness instead of restoring the original one
+    pub fn keep(mut self) {
+        self.armed = false;
+    }
+
+    fn write_original(&mut self) -> Result<()> {
+        // The brightness may have been changed by another program, which should be undone too
+        self.light.try_reload()?;
+        if self.light.current() == self.original {
+            return Ok(());
+        }
+        match self.sweep {
+            Some((delay, sweep)) => sweep(self.light, self.original, delay),
+            None => self.light.write_value_unclamped(self.original),
+        }
+    }
+}
+
+impl<L: Light + Dimmable + ?Sized> BrightnessGuard<'_, L> {
+    /// Sweep back to the original brightness instead of changing it at once
+    #[must_use]
+    pub fn sweep(mut self, delay: Delay) -> Self {
+        self.sweep = Some((delay, |light, value, delay| {
+            light.sweep_write_unclamped(value, delay)
+        }));
+        self
+    }
+}
+
+impl<L: Light + ?Sized> Deref for BrightnessGuard<'_, L> {
+    type Target = L;
+
+    fn deref(&self) -> &Self::Target {
+        self.light
+    }
+}
+
+impl<L: Light + ?Sized> DerefMut for BrightnessGuard<'_, L> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.light
+    }
+}
+
+impl<L: Light + ?Sized> Drop for BrightnessGuard<'_, L> {
+    fn drop(&mut self) {
+        if self.armed {
+            let _ = self.write_original();
+        }
+    }
+}
+
+impl<L: Light + ?Sized> std::fmt::Debug for BrightnessGuard<'_, L> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BrightnessGuard")
+            .field("light", &self.light.name())
+            .field("original", &self.original)
+            .field("sweep", &self.sweep.map(|(delay, _)| delay))
+            .field("armed", &self.armed)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::with_test_env, Device};
+
+    #[test]
+    fn restores_on_drop() {
+        with_test_env(&["generic"], || {
+            let mut d = Device::new(Some("generic".into())).unwrap();
+            {
+                let mut guard = d.guard();
+                guard.write_value(90).unwrap();
+                assert_eq!(guard.current(), 90);
+            }
+            d.reload();
+            assert_eq!(d.current(), 50);
+
+            let mut guard = d.guard().sweep(Delay::from_millis(0));
+            guard.write_value(10).unwrap();
+            guard.restore().unwrap();
+            d.reload();
+            assert_eq!(d.current(), 50);
+        });
+    }
+
+    #[test]
+    fn keep() {
+        with_test_env(&["generic"], || {
+            let mut d = Device::new(Some("generic".into())).unwrap();
+            let mut guard = d.guard();
+            guard.write_value(80).unwrap();
+            guard.keep();
+            d.reload();
+            assert_eq!(d.current(), 80);
+        });
+    }
+}
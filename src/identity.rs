@@ -0,0 +1,205 @@
+//! Identity of backlight devices that doesn't depend on their name
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Attributes that identify a backlight device independently of its name
+///
+/// Backlight names depend on the driver and the order in which devices are probed, so they can change after a kernel
+/// update (e.g. `amdgpu_bl0` becoming `amdgpu_bl1`). The identity describes the device itself:
+/// - kind: the `type` attribute of the backlight (`raw`, `platform` or `firmware`)
+/// - connector: the display connector of the panel, e.g. `eDP-1`
+/// - driver: the kernel driver of the graphics device, e.g. `amdgpu` or `i915`
+///
+/// All attributes are optional, as not every device exposes them.
+/// See [`Device::identity`][crate::Device::identity] and [`Device::find`][crate::Device::find] for usage.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceIdentity {
+    kind: Option<String>,
+    connector: Option<String>,
+    driver: Option<String>,
+}
+
+impl DeviceIdentity {
+    /// Create an identity from previously read attributes, e.g. ones stored in a save file
+    #[must_use]
+    pub fn new(kind: Option<String>, connector: Option<String>, driver: Option<String>) -> Self {
+        Self {
+            kind,
+            connector,
+            driver,
+        }
+    }
+
+    /// Read the identity of the backlight device located at `path`
+    pub(crate) fn read(path: &Path) -> Self {
+        let device = fs::canonicalize(path.join("device")).ok();
+        Self {
+            kind: read_attr(&path.join("type")),
+            connector: device.as_deref().and_then(connector),
+            driver: device.as_deref().and_then(driver),
+        }
+    }
+
+    /// Type of the backlight interface: `raw`, `platform` or `firmware`
+    #[must_use]
+    pub fn kind(&self) -> Option<&str> {
+        self.kind.as_deref()
+    }
+
+    /// Display connector of the panel, e.g. `eDP-1`
+    #[must_use]
+    pub fn connector(&self) -> Option<&str> {
+        self.connector.as_deref()
+    }
+
+    /// Kernel driver of the graphics device, e.g. `amdgpu`
+    #[must_use]
+    pub fn driver(&self) -> Option<&str> {
+        self.driver.as_deref()
+    }
+
+    /// Whether none of the attributes are known
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.kind.is_none() && self.connector.is_none() && self.driver.is_none()
+    }
+
+    /// How well another identity matches this one, `None` if it belongs to a different device
+    ///
+    /// Attributes known on both sides add to the score when they're equal. A different connector or driver means a
+    /// different panel or GPU, while a different type only lowers the score.
+    pub(crate) fn score(&self, other: &Self) -> Option<i32> {
+        let mut score = 0;
+        let attributes = [
+            (&self.kind, &other.kind, false),
+            (&self.connector, &other.connector, true),
+            (&self.driver, &other.driver, true),
+        ];
+        for (a, b, decisive) in attributes {
+            match (a, b) {
+                (Some(a), Some(b)) if a == b => score += 2,
+                (Some(_), Some(_)) if decisive => return None,
+                (Some(_), Some(_)) => score -= 2,
+                _ => (),
+            }
+        }
+        Some(score)
+    }
+}
+
+fn read_attr(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_owned())
+        .filter(|s| !s.is_empty())
+}
+
+/// DRM backlights either belong to a connector (`card0-eDP-1`), or to the GPU, in which case the connected internal
+/// panel connector is looked up in its `drm` directory
+fn connector(device: &Path) -> Option<String> {
+    let connector_name = |path: &Path| {
+        let name = path.file_name()?.to_str()?;
+        let (card, connector) = name.split_once('-')?;
+        card.starts_with("card").then(|| connector.to_owned())
+    };
+    if let Some(connector) = connector_name(device) {
+        return Some(connector);
+    }
+    let mut connectors: Vec<PathBuf> = fs::read_dir(device.join("drm"))
+        .ok()?
+        .flatten()
+        .flat_map(|card| fs::read_dir(card.path()).into_iter().flatten().flatten())
+        .map(|entry| entry.path())
+        .collect();
+    connectors.sort();
+    connectors.into_iter().find_map(|path| {
+        let name = connector_name(&path)?;
+        let internal = ["eDP", "LVDS", "DSI"].iter().any(|p| name.starts_with(p));
+        let connected = read_attr(&path.join("status")).is_some_and(|s| s == "connected");
+        (internal && connected).then_some(name)
+    })
+}
+
+/// Driver of the device, or of its closest parent that has one (connectors don't have a driver)
+fn driver(device: &Path) -> Option<String> {
+    device.ancestors().take(3).find_map(|dir| {
+        let driver = fs::canonicalize(dir.join("driver")).ok()?;
+        Some(driver.file_name()?.to_string_lossy().into_owned())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::with_test_env, Device, Light, BLDIR};
+    use std::os::unix::fs::symlink;
+
+    /// Give a test device a GPU with a driver and a connected eDP panel
+    fn fake_gpu(name: &str, driver: &str, connector: &str) {
+        let gpu = format!("{BLDIR}/{name}/gpu");
+        fs::create_dir_all(format!("{gpu}/drm/card0/card0-{connector}")).unwrap();
+        fs::write(
+            format!("{gpu}/drm/card0/card0-{connector}/status"),
+            "connected\n",
+        )
+        .unwrap();
+        fs::create_dir_all(format!("{BLDIR}/{name}/drivers/{driver}")).unwrap();
+        symlink(format!("../drivers/{driver}"), format!("{gpu}/driver")).unwrap();
+        symlink("gpu", format!("{BLDIR}/{name}/device")).unwrap();
+        fs::write(format!("{BLDIR}/{name}/type"), "raw\n").unwrap();
+    }
+
+    #[test]
+    fn find_renamed_device() {
+        with_test_env(&["amdgpu_bl1", "nvidia_0"], || {
+            fake_gpu("amdgpu_bl1", "amdgpu", "eDP-1");
+            fake_gpu("nvidia_0", "nvidia", "eDP-2");
+
+            let identity = Device::new(Some("amdgpu_bl1".into())).unwrap().identity();
+            assert_eq!(identity.kind(), Some("raw"));
+            assert_eq!(identity.connector(), Some("eDP-1"));
+            assert_eq!(identity.driver(), Some("amdgpu"));
+
+            let found = Device::find("amdgpu_bl0", &identity).unwrap();
+            assert_eq!(found.name(), "amdgpu_bl1");
+
+            // A device with the same name but a different identity isn't used
+            let other = DeviceIdentity::new(None, Some("eDP-1".into()), Some("i915".into()));
+            assert!(Device::find("nvidia_0", &other).is_err());
+            // Without an identity, the name is all there is to go by
+            let found = Device::find("nvidia_0", &DeviceIdentity::default()).unwrap();
+            assert_eq!(found.name(), "nvidia_0");
+        });
+    }
+
+    #[test]
+    fn conflicting_attributes_disqualify() {
+        with_test_env(&["amdgpu_bl0"], || {
+            fake_gpu("amdgpu_bl0", "amdgpu", "eDP-1");
+            let identity = |connector: Option<&str>, driver: &str| {
+                DeviceIdentity::new(
+                    Some("raw".into()),
+                    connector.map(Into::into),
+                    Some(driver.into()),
+                )
+            };
+
+            // Same type and connector, but a different driver
+            assert!(Device::find("amdgpu_bl1", &identity(Some("eDP-1"), "i915")).is_err());
+            // Same type and name, but a different driver
+            assert!(Device::find("amdgpu_bl0", &identity(None, "i915")).is_err());
+
+            let found = Device::find("amdgpu_bl1", &identity(None, "amdgpu")).unwrap();
+            assert_eq!(found.name(), "amdgpu_bl0");
+            // A different type only counts against a device
+            let other_type =
+                DeviceIdentity::new(Some("firmware".into()), Some("eDP-1".into()), None);
+            assert!(Device::find("amdgpu_bl1", &other_type).is_err());
+            let found = Device::find("amdgpu_bl0", &other_type).unwrap();
+            assert_eq!(found.name(), "amdgpu_bl0");
+        });
+    }
+}
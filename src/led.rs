@@ -26,7 +26,7 @@
 //!     // Find Capslock LED and alter its state
 //!     if let Some(caps) = leds
 //!         .into_iter()
-//!         .find(|n| n.function() == led::Function::Capslock)
+//!         .find(|n| *n.function() == led::Function::Capslock)
 //!     {
 //!         // This is the same as `led::Led::from_name(caps)?`
 //!         match caps.initialize()? {
@@ -49,6 +49,7 @@
 //! ```
 use std::{
     borrow::Cow,
+    fmt::Display,
     fs::File,
     marker::PhantomData,
     path::{Path, PathBuf},
@@ -60,6 +61,11 @@ use crate::{
     private, utils, Light,
 };
 
+mod device;
+mod group;
+pub use device::{Bus, DeviceInfo};
+pub use group::{LedFilter, LedGroup};
+
 /// Linux LED interface directory
 #[cfg(not(test))]
 pub const LEDDIR: &str = "/sys/class/leds";
@@ -95,8 +101,8 @@ pub struct NonDimmable;
 #[derive(Debug)]
 pub struct Led<Type> {
     name: LedName<'static>,
-    max: u8,
-    current: u8,
+    max: u32,
+    current: u32,
     path: PathBuf,
     brightness: File,
     marker: PhantomData<Type>,
@@ -114,6 +120,7 @@ impl Led<()> {
     /// - [`ErrorKind::NotFound`] - an LED dir of the given name is not found
     /// - [`ErrorKind::ReadMax`] - failure to read the max brightness value
     /// - [`ErrorKind::ReadCurrent`] - failure to read the current brightness value
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(name: Cow<str>) -> crate::Result<LedType> {
         Self::new_inner(LedName::parse(name), None)
     }
@@ -150,8 +157,6 @@ impl Led<()> {
             brightness,
             path,
         } = utils::read_info(LEDDIR, &name.raw, lock)?;
-        #[allow(clippy::cast_possible_truncation)]
-        let (max, current) = (max as _, current as _);
         let name = name.into_owned();
         let led = if max == 1 {
             LedType::NonDimmable(Led {
@@ -196,23 +201,84 @@ impl<Type> Led<Type> {
     /// Supported color of the LED
     ///
     /// See type level docs of [`LedName`] for additional details on parsing
-    pub fn color(&self) -> Color {
-        self.name.color
+    #[must_use]
+    pub fn color(&self) -> &Color {
+        &self.name.color
     }
 
     /// Function of the LED, such as Capslock and Numlock
     ///
     /// See type level docs of [`LedName`] for additional details on parsing
-    pub fn function(&self) -> Function {
-        self.name.function
+    #[must_use]
+    pub fn function(&self) -> &Function {
+        &self.name.function
     }
 
     /// Name of the LED that was parsed from the full device name using the standard Linux LED naming convention
     ///
     /// See type level docs of [`LedName`] for additional details on parsing
+    #[must_use]
     pub fn parsed_name(&self) -> Option<&str> {
         self.name.parsed_name()
     }
+
+    /// The [`LedName`] this LED was initialized with
+    #[must_use]
+    pub fn led_name(&self) -> &LedName<'static> {
+        &self.name
+    }
+
+    /// Resolve the hardware device the LED belongs to
+    ///
+    /// This follows the `device` link in the LED's directory to find out which physical device owns it,
+    /// such as the keyboard behind `input3::capslock`. See [`DeviceInfo`] for the available details.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn main() -> blight::Result<()> {
+    /// if let blight::led::LedType::NonDimmable(led) = blight::led::Led::new("input3::capslock".into())? {
+    ///     let info = led.device_info()?;
+    ///     println!("{} belongs to {}", led.led_name().raw_name(), info.display_name());
+    /// }
+    /// #   Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// - [`ErrorKind::ReadDeviceInfo`] - the LED is not linked to a device or the link could not be resolved
+    pub fn device_info(&self) -> crate::Result<DeviceInfo> {
+        DeviceInfo::read(&self.path)
+    }
+}
+
+impl LedType {
+    /// Returns `true` if the LED supports brightness values other than `0` and `1`
+    #[must_use]
+    pub fn is_dimmable(&self) -> bool {
+        matches!(self, LedType::Dimmable(_))
+    }
+
+    /// The [`LedName`] the LED was initialized with
+    #[must_use]
+    pub fn led_name(&self) -> &LedName<'static> {
+        match self {
+            LedType::Dimmable(led) => led.led_name(),
+            LedType::NonDimmable(led) => led.led_name(),
+        }
+    }
+
+    /// Resolve the hardware device the LED belongs to
+    ///
+    /// See [`Led::device_info`] for details.
+    ///
+    /// # Errors
+    /// - All possible errors returned by [`Led::device_info`]
+    pub fn device_info(&self) -> crate::Result<DeviceInfo> {
+        match self {
+            LedType::Dimmable(led) => led.device_info(),
+            LedType::NonDimmable(led) => led.device_info(),
+        }
+    }
 }
 
 impl<Type> private::Sealed for Led<Type> {}
@@ -224,7 +290,7 @@ impl super::Toggleable for Led<Dimmable> {}
 impl super::Toggleable for Led<NonDimmable> {}
 
 impl<Type> Light for Led<Type> {
-    type Value = u8;
+    type Value = u32;
 
     /// Full name of the LED device
     ///
@@ -257,6 +323,61 @@ impl<Type> Light for Led<Type> {
     }
 }
 
+impl private::Sealed for LedType {}
+
+impl super::Toggleable for LedType {}
+
+/// Allows working with LEDs without matching on their type first
+///
+/// Only the functionality shared by dimmable and non-dimmable LEDs is available through this impl.
+impl Light for LedType {
+    type Value = u32;
+
+    fn name(&self) -> &str {
+        match self {
+            LedType::Dimmable(led) => led.name(),
+            LedType::NonDimmable(led) => led.name(),
+        }
+    }
+
+    fn current(&self) -> Self::Value {
+        match self {
+            LedType::Dimmable(led) => led.current(),
+            LedType::NonDimmable(led) => led.current(),
+        }
+    }
+
+    fn max(&self) -> Self::Value {
+        match self {
+            LedType::Dimmable(led) => led.max(),
+            LedType::NonDimmable(led) => led.max(),
+        }
+    }
+
+    #[doc(hidden)]
+    fn set_current(&mut self, internal: crate::private::Internal, current: Self::Value) {
+        match self {
+            LedType::Dimmable(led) => led.set_current(internal, current),
+            LedType::NonDimmable(led) => led.set_current(internal, current),
+        }
+    }
+
+    #[doc(hidden)]
+    fn brightness_file(&mut self, internal: crate::private::Internal) -> &mut File {
+        match self {
+            LedType::Dimmable(led) => led.brightness_file(internal),
+            LedType::NonDimmable(led) => led.brightness_file(internal),
+        }
+    }
+
+    fn device_path(&self) -> &Path {
+        match self {
+            LedType::Dimmable(led) => led.device_path(),
+            LedType::NonDimmable(led) => led.device_path(),
+        }
+    }
+}
+
 /// Abstraction that represents the name of an LED device
 ///
 /// If [`LedName`] was initialized with a an LED name formatted according to the
@@ -278,6 +399,7 @@ impl<'a> LedName<'a> {
     /// This function is infallible, which means any string will be accepted.
     /// However, only names formatted according to the Linux LED naming convention will be parsed correctly
     /// to get color, name and function information. See [type][LedName] level docs for additional details.
+    #[must_use]
     pub fn parse(name: Cow<'a, str>) -> Self {
         let mut name = Self {
             raw: name,
@@ -304,27 +426,42 @@ impl<'a> LedName<'a> {
     /// Color of the LED which was parsed from the name
     ///
     /// See type level docs for details on LED naming convention.
-    pub fn color(&self) -> Color {
-        self.color
+    #[must_use]
+    pub fn color(&self) -> &Color {
+        &self.color
     }
 
     /// Function of the LED which was parsed from the name (Capslock, Scrollock, Numlock, etc)
     ///
     /// See type level docs for details on LED naming convention.
-    pub fn function(&self) -> Function {
-        self.function
+    #[must_use]
+    pub fn function(&self) -> &Function {
+        &self.function
     }
 
     /// The full unparsed name of the LED (same as the string used to initialize the `LedName`)
     ///
     /// See type level docs for details on LED naming convention.
+    #[must_use]
     pub fn raw_name(&self) -> &str {
         &self.raw
     }
 
+    /// The `devicename` section of the LED name, such as `input3` in `input3::capslock`
+    ///
+    /// For LEDs of input devices, this is the name of the input handler, which can be resolved to the
+    /// physical device with [`Led::device_info`]. This is the same as [`Self::parsed_name`].
+    ///
+    /// See type level docs for details on LED naming convention.
+    #[must_use]
+    pub fn devicename(&self) -> Option<&str> {
+        self.parsed_name()
+    }
+
     /// Parsed name of the LED
     ///
     /// See type level docs for details on LED naming convention.
+    #[must_use]
     pub fn parsed_name(&self) -> Option<&str> {
         (self.len != 0).then_some(&self.raw[..self.len])
     }
@@ -340,6 +477,7 @@ impl<'a> LedName<'a> {
     }
 
     /// Convert an LED name containing borrowed data into an owned instance
+    #[must_use]
     pub fn into_owned(self) -> LedName<'static> {
         LedName {
             raw: self.raw.into_owned().into(),
@@ -352,28 +490,71 @@ impl<'a> LedName<'a> {
 ///
 /// Use [`LedName`] to parse an LED name (string) to inspect its supported color. The same is also
 /// done automatically when initializing an LED with [`Led::new`].
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+///
+/// Parsing is lossless: colors that are not known to blight are preserved as [`Color::Other`],
+/// and [`Color::as_str`] (as well as the `Display` impl) returns the spelling used by the kernel.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
 pub enum Color {
-    White = 0,
-    Red = 1,
-    Green = 2,
-    Blue = 3,
-    Amber = 4,
-    Violet = 5,
-    Yellow = 6,
-    Ir = 7,
-    Multi = 8,
-    Rgb = 9,
-    Purple = 10,
-    Orange = 11,
-    Pink = 12,
-    Cyan = 13,
-    Lime = 14,
-    Max = 15,
+    White,
+    Red,
+    Green,
+    Blue,
+    Amber,
+    Violet,
+    Yellow,
+    Ir,
+    Multi,
+    Rgb,
+    Purple,
+    Orange,
+    Pink,
+    Cyan,
+    Lime,
+    /// Sentinel value used by the kernel (`LED_COLOR_ID_MAX`), not an actual LED color
+    Max,
+    /// A color that is not known to blight, containing the name as it appeared in the LED name
+    Other(String),
+    /// No color was present in the LED name
     #[default]
     Unknown,
 }
 
+impl Color {
+    /// Name of the color as spelled by the kernel
+    ///
+    /// [`Color::Unknown`] returns an empty string, as that's how a missing color appears in an LED name.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        match self {
+            Color::White => "white",
+            Color::Red => "red",
+            Color::Green => "green",
+            Color::Blue => "blue",
+            Color::Amber => "amber",
+            Color::Violet => "violet",
+            Color::Yellow => "yellow",
+            Color::Ir => "ir",
+            Color::Multi => "multicolor",
+            Color::Rgb => "rgb",
+            Color::Purple => "purple",
+            Color::Orange => "orange",
+            Color::Pink => "pink",
+            Color::Cyan => "cyan",
+            Color::Lime => "lime",
+            Color::Max => "max",
+            Color::Other(other) => other,
+            Color::Unknown => "",
+        }
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 impl FromStr for Color {
     type Err = std::convert::Infallible;
 
@@ -387,7 +568,7 @@ impl FromStr for Color {
             "violet" => Color::Violet,
             "yellow" => Color::Yellow,
             "ir" => Color::Ir,
-            "multi" => Color::Multi,
+            "multicolor" | "multi" => Color::Multi,
             "rgb" => Color::Rgb,
             "purple" => Color::Purple,
             "orange" => Color::Orange,
@@ -395,7 +576,8 @@ impl FromStr for Color {
             "cyan" => Color::Cyan,
             "lime" => Color::Lime,
             "max" => Color::Max,
-            _ => Color::default(),
+            "" => Color::Unknown,
+            other => Color::Other(other.to_owned()),
         };
         Ok(clr)
     }
@@ -405,7 +587,11 @@ impl FromStr for Color {
 ///
 /// Use [`LedName`] to parse an LED name (string) to inspect its function. The same is also
 /// done automatically when initializing an LED with [`Led::new`].
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+///
+/// Parsing is lossless: functions that are not known to blight (such as ones added in newer kernels)
+/// are preserved as [`Function::Other`], and [`Function::as_str`] (as well as the `Display` impl) returns the spelling used by the kernel.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
 pub enum Function {
     Capslock,
     Scrolllock,
@@ -459,10 +645,86 @@ pub enum Function {
     Wlan5ghz,
     Wlan6ghz,
     Wps,
+    /// A function that is not known to blight, containing the name as it appeared in the LED name
+    Other(String),
+    /// No function was present in the LED name
     #[default]
     Unknown,
 }
 
+impl Function {
+    /// Name of the function as spelled by the kernel (e.g. `kbd_backlight`)
+    ///
+    /// [`Function::Unknown`] returns an empty string, as that's how a missing function appears in an LED name.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        #[allow(clippy::enum_glob_use)]
+        use Function::*;
+        match self {
+            Capslock => "capslock",
+            Scrolllock => "scrolllock",
+            Numlock => "numlock",
+            Fnlock => "fnlock",
+            KbdBacklight => "kbd_backlight",
+            Power => "power",
+            Disk => "disk",
+            Charging => "charging",
+            Status => "status",
+            Micmute => "micmute",
+            Mute => "mute",
+            Player1 => "player-1",
+            Player2 => "player-2",
+            Player3 => "player-3",
+            Player4 => "player-4",
+            Player5 => "player-5",
+            Activity => "activity",
+            Alarm => "alarm",
+            Backlight => "backlight",
+            Bluetooth => "bluetooth",
+            Boot => "boot",
+            Cpu => "cpu",
+            Debug => "debug",
+            DiskActivity => "disk-activity",
+            DiskErr => "disk-err",
+            DiskRead => "disk-read",
+            DiskWrite => "disk-write",
+            Fault => "fault",
+            Flash => "flash",
+            Heartbeat => "heartbeat",
+            Indicator => "indicator",
+            Lan => "lan",
+            Mail => "mail",
+            Mobile => "mobile",
+            Mtd => "mtd",
+            Panic => "panic",
+            Programming => "programming",
+            Rx => "rx",
+            Sd => "sd",
+            SpeedLan => "speed-lan",
+            SpeedWan => "speed-wan",
+            Standby => "standby",
+            Torch => "torch",
+            Tx => "tx",
+            Usb => "usb",
+            Wan => "wan",
+            WanOnline => "wan-online",
+            Wlan => "wlan",
+            Wlan2ghz => "wlan-2ghz",
+            Wlan5ghz => "wlan-5ghz",
+            Wlan6ghz => "wlan-6ghz",
+            Wps => "wps",
+            Other(other) => other,
+            Unknown => "",
+        }
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 impl FromStr for Function {
     type Err = std::convert::Infallible;
 
@@ -522,7 +784,8 @@ impl FromStr for Function {
             "wlan-5ghz" => Wlan5ghz,
             "wlan-6ghz" => Wlan6ghz,
             "wps" => Wps,
-            _ => Unknown,
+            "" => Unknown,
+            other => Other(other.to_owned()),
         };
         Ok(func)
     }
@@ -551,6 +814,7 @@ pub fn led_names() -> crate::Result<Vec<LedName<'static>>> {
 /// Helper function to initialize all the LED devices available in `/sys/class/leds`
 ///
 /// This function will return an error if any single LED fails to initialize.
+/// Use [`try_leds`] to get every LED that was successfully initialized along with the errors of the ones that weren't.
 ///
 /// # Errors
 /// - All possible errors returned by [`led_names`]
@@ -562,6 +826,7 @@ pub fn leds() -> crate::Result<Vec<LedType>> {
 /// Helper function to initialize all LEDs from an iterator over [`LedName`]s
 ///
 /// This function will return an error if any single LED fails to initialize.
+/// Use [`try_leds_from_names`] to get a separate result for each LED instead.
 ///
 /// # Examples
 /// ```no_run
@@ -569,7 +834,7 @@ pub fn leds() -> crate::Result<Vec<LedType>> {
 /// let leds = blight::led::leds_from_names(
 ///     blight::led::led_names()?
 ///         .into_iter()
-///         .filter(|n| n.color() == blight::led::Color::Rgb),
+///         .filter(|n| *n.color() == blight::led::Color::Rgb),
 /// )?;
 /// for led in leds {
 /// // do something here
@@ -586,6 +851,45 @@ pub fn leds_from_names<'a>(
     names.into_iter().map(Led::from_name).collect()
 }
 
+/// Helper function to initialize all the LED devices available in `/sys/class/leds`, with a separate result for each LED
+///
+/// Unlike [`leds`], a single LED failing to initialize (for example, due to an unreadable brightness file)
+/// does not prevent the rest from being returned. Each LED name is paired with the result of its initialization.
+///
+/// # Examples
+/// ```no_run
+/// # fn main() -> blight::Result<()> {
+/// for (name, led) in blight::led::try_leds()? {
+///     match led {
+///         Ok(led) => { /* do something with the LED */ }
+///         Err(err) => eprintln!("{}: {err}", name.raw_name()),
+///     }
+/// }
+/// #   Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+/// - All possible errors returned by [`led_names`]
+pub fn try_leds() -> crate::Result<Vec<(LedName<'static>, crate::Result<LedType>)>> {
+    led_names().map(try_leds_from_names)
+}
+
+/// Helper function to initialize all LEDs from an iterator over [`LedName`]s, with a separate result for each LED
+///
+/// See [`try_leds`] for details.
+pub fn try_leds_from_names<'a>(
+    names: impl IntoIterator<Item = LedName<'a>>,
+) -> Vec<(LedName<'a>, crate::Result<LedType>)> {
+    names
+        .into_iter()
+        .map(|name| {
+            let led = Led::from_name(name.clone());
+            (name, led)
+        })
+        .collect()
+}
+
 /// Helper function to turn an LED on/off
 ///
 /// `State`: true = on (brightness = max), false = off (brightness = 0)
@@ -595,7 +899,7 @@ pub fn leds_from_names<'a>(
 pub fn set_led_state(led_name: &str, state: bool) -> crate::Result<()> {
     match Led::new(led_name.into())? {
         LedType::Dimmable(mut led) => led.write_value(if state { led.max() } else { 0 }),
-        LedType::NonDimmable(mut led) => led.write_value(u8::from(state)),
+        LedType::NonDimmable(mut led) => led.write_value(u32::from(state)),
     }
 }
 
@@ -615,9 +919,12 @@ pub fn get_led_state(led_name: &str) -> crate::Result<bool> {
 
 /// Helper function to set LED brightness value
 ///
+/// The value can be anything between `0` and the `max_brightness` of the LED, which is not limited
+/// to `255` (PWM LEDs and some keyboard backlights support values up to `4095` or more).
+///
 /// # Errors
 /// - All possible errors returned by [`Led::new`] and [`Led::write_value`]
-pub fn set_led_value(led_name: &str, value: u8) -> crate::Result<()> {
+pub fn set_led_value(led_name: &str, value: u32) -> crate::Result<()> {
     match Led::new(led_name.into())? {
         LedType::Dimmable(mut led) => led.write_value(value),
         LedType::NonDimmable(mut led) => led.write_value(value),
@@ -690,6 +997,112 @@ mod tests {
         }
     }
 
+    #[test]
+    fn kernel_names_round_trip() {
+        // As documented in include/dt-bindings/leds/common.h
+        let functions = [
+            "capslock",
+            "scrolllock",
+            "numlock",
+            "fnlock",
+            "kbd_backlight",
+            "power",
+            "disk",
+            "charging",
+            "status",
+            "micmute",
+            "mute",
+            "player-1",
+            "player-2",
+            "player-3",
+            "player-4",
+            "player-5",
+            "activity",
+            "alarm",
+            "backlight",
+            "bluetooth",
+            "boot",
+            "cpu",
+            "debug",
+            "disk-activity",
+            "disk-err",
+            "disk-read",
+            "disk-write",
+            "fault",
+            "flash",
+            "heartbeat",
+            "indicator",
+            "lan",
+            "mail",
+            "mobile",
+            "mtd",
+            "panic",
+            "programming",
+            "rx",
+            "sd",
+            "speed-lan",
+            "speed-wan",
+            "standby",
+            "torch",
+            "tx",
+            "usb",
+            "wan",
+            "wan-online",
+            "wlan",
+            "wlan-2ghz",
+            "wlan-5ghz",
+            "wlan-6ghz",
+            "wps",
+        ];
+        for name in functions {
+            let Ok(func): Result<Function, _> = name.parse();
+            assert!(
+                !matches!(func, Function::Other(_) | Function::Unknown),
+                "'{name}' was not recognized"
+            );
+            assert_eq!(func.to_string(), name);
+            assert_eq!(func.as_str().parse::<Function>().unwrap(), func);
+        }
+        let colors = [
+            "white",
+            "red",
+            "green",
+            "blue",
+            "amber",
+            "violet",
+            "yellow",
+            "ir",
+            "multicolor",
+            "rgb",
+            "purple",
+            "orange",
+            "pink",
+            "cyan",
+            "lime",
+        ];
+        for name in colors {
+            let Ok(color): Result<Color, _> = name.parse();
+            assert!(
+                !matches!(color, Color::Other(_) | Color::Unknown),
+                "'{name}' was not recognized"
+            );
+            assert_eq!(color.to_string(), name);
+            assert_eq!(color.as_str().parse::<Color>().unwrap(), color);
+        }
+    }
+
+    #[test]
+    fn unknown_names_preserved() {
+        let name = LedName::parse("input3:teal:new-function".into());
+        assert_eq!(name.function(), &Function::Other("new-function".into()));
+        assert_eq!(name.color(), &Color::Other("teal".into()));
+        assert_eq!(name.function().to_string(), "new-function");
+        assert_eq!(name.color().to_string(), "teal");
+        let name = LedName::parse("input3::".into());
+        assert_eq!(name.function(), &Function::Unknown);
+        assert_eq!(name.function().to_string(), "");
+    }
+
     #[test]
     fn initialize_dimmable() {
         clean_up();
@@ -736,6 +1149,26 @@ mod tests {
         clean_up();
     }
 
+    #[test]
+    fn partial_enumeration() {
+        clean_up();
+        let names = ["led1", "led2", "led3"];
+        setup_test_env(&names, 0, 1);
+        std::fs::remove_file(format!("{LEDDIR}/led2/brightness"))
+            .expect("failed to remove brightness file");
+        assert!(leds().is_err(), "a broken LED should fail full enumeration");
+        let mut results = try_leds().expect("failed to enumerate LEDs");
+        results.sort_by(|(a, _), (b, _)| a.raw_name().cmp(b.raw_name()));
+        let status: Vec<(&str, bool)> = results
+            .iter()
+            .map(|(name, led)| (name.raw_name(), led.is_ok()))
+            .collect();
+        assert_eq!(status, [("led1", true), ("led2", false), ("led3", true)]);
+        let (_, broken) = &results[1];
+        assert_eq!(broken.as_ref().unwrap_err().kind(), &ErrorKind::ReadCurrent);
+        clean_up();
+    }
+
     #[test]
     fn set_state() {
         clean_up();
@@ -787,7 +1220,7 @@ mod tests {
         else {
             unreachable!()
         };
-        let values = [0, 1, 2, 3, u8::MAX];
+        let values = [0, 1, 2, 3, 255];
         for val in values {
             set_led_value(name, val).expect("failed to set led value");
             led.reload();
@@ -795,4 +1228,30 @@ mod tests {
         }
         clean_up();
     }
+
+    #[test]
+    fn set_value_beyond_u8() {
+        clean_up();
+        let name = "generic";
+        setup_test_env(&[name], 0, 4095);
+        let LedType::Dimmable(mut led) = Led::new(name.into()).expect("failed to initialize LED")
+        else {
+            unreachable!()
+        };
+        assert_eq!(led.max(), 4095, "max brightness was truncated");
+        for val in [256, 1023, 4095] {
+            set_led_value(name, val).expect("failed to set led value");
+            led.reload();
+            assert_eq!(led.current(), val);
+        }
+        let err = set_led_value(name, 4096).expect_err("value larger than max was accepted");
+        assert_eq!(
+            err.kind(),
+            &ErrorKind::ValueTooLarge {
+                given: 4096,
+                supported: 4095
+            }
+        );
+        clean_up();
+    }
 }
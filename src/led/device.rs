@@ -0,0 +1,239 @@
+//! Resolving the hardware device an LED belongs to
+
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::err::{Error, ErrorKind};
+
+/// Information about the hardware device an LED belongs to
+///
+/// LED names such as `input3::capslock` only contain the name of the kernel device handler, which says
+/// nothing about the physical device. This type is resolved by following the `device` link of the LED in
+/// `/sys/class/leds` and reading the attributes of the device it points to.
+///
+/// All attributes are optional, as the available information depends on the kind of the device.
+/// Input devices (keyboards) usually provide all of them.
+///
+/// See [`Led::device_info`][super::Led::device_info] for usage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceInfo {
+    path: PathBuf,
+    name: Option<String>,
+    subsystem: Option<String>,
+    bus: Option<Bus>,
+    vendor: Option<u16>,
+    product: Option<u16>,
+}
+
+impl DeviceInfo {
+    /// Resolve the device info of the LED located at `led_path`
+    pub(crate) fn read(led_path: &Path) -> crate::Result<Self> {
+        let link = led_path.join("device");
+        let path = fs::canonicalize(&link)
+            .map_err(|err| Error::from(ErrorKind::ReadDeviceInfo).with_source(err))?;
+        let read_attr = |attr: &str| {
+            fs::read_to_string(path.join(attr))
+                .ok()
+                .map(|s| s.trim().to_owned())
+                .filter(|s| !s.is_empty())
+        };
+        let read_id = |attr: &str| read_attr(attr).and_then(|id| u16::from_str_radix(&id, 16).ok());
+        let subsystem = fs::read_link(path.join("subsystem"))
+            .ok()
+            .and_then(|s| s.file_name().map(|s| s.to_string_lossy().into_owned()));
+        let mut info = Self {
+            name: read_attr("name"),
+            bus: read_id("id/bustype").map(Bus::from),
+            vendor: read_id("id/vendor"),
+            product: read_id("id/product"),
+            subsystem,
+            path,
+        };
+        if info.bus.is_none() {
+            info.fill_ids_from_hid_name();
+        }
+        Ok(info)
+    }
+
+    /// HID devices don't expose ids as attributes, but their names are formatted as `BBBB:VVVV:PPPP.NNNN`
+    fn fill_ids_from_hid_name(&mut self) {
+        let Some(dir) = self.path.file_name().and_then(|n| n.to_str()) else {
+            return;
+        };
+        let Some((ids, _)) = dir.split_once('.') else {
+            return;
+        };
+        let mut ids = ids.split(':').map(|id| u16::from_str_radix(id, 16));
+        if let (Some(Ok(bus)), Some(Ok(vendor)), Some(Ok(product)), None) =
+            (ids.next(), ids.next(), ids.next(), ids.next())
+        {
+            self.bus = Some(Bus::from(bus));
+            self.vendor = Some(vendor);
+            self.product = Some(product);
+        }
+    }
+
+    /// Absolute path to the device in `/sys/devices`
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Human-readable name of the device (e.g. "AT Translated Set 2 keyboard")
+    ///
+    /// Usually only available for input devices.
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Kernel subsystem the device belongs to (e.g. `input`, `platform`, `hid`)
+    #[must_use]
+    pub fn subsystem(&self) -> Option<&str> {
+        self.subsystem.as_deref()
+    }
+
+    /// Bus the device is connected through
+    #[must_use]
+    pub fn bus(&self) -> Option<Bus> {
+        self.bus
+    }
+
+    /// Vendor ID of the device
+    #[must_use]
+    pub fn vendor(&self) -> Option<u16> {
+        self.vendor
+    }
+
+    /// Product ID of the device
+    #[must_use]
+    pub fn product(&self) -> Option<u16> {
+        self.product
+    }
+
+    /// The most descriptive name available for the device
+    ///
+    /// This is the human-readable [name][Self::name] if present, or the name of the device directory in `/sys/devices` otherwise.
+    #[must_use]
+    pub fn display_name(&self) -> std::borrow::Cow<'_, str> {
+        match (&self.name, self.path.file_name()) {
+            (Some(name), _) => name.into(),
+            (None, Some(dir)) => dir.to_string_lossy(),
+            (None, None) => self.path.to_string_lossy(),
+        }
+    }
+}
+
+/// Bus type of a device, as defined in `include/uapi/linux/input.h`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bus {
+    Pci,
+    Usb,
+    Bluetooth,
+    Virtual,
+    I8042,
+    I2c,
+    Host,
+    Spi,
+    /// A bus type not known to blight, containing the raw bus ID
+    Other(u16),
+}
+
+impl From<u16> for Bus {
+    fn from(value: u16) -> Self {
+        match value {
+            0x01 => Bus::Pci,
+            0x03 => Bus::Usb,
+            0x05 => Bus::Bluetooth,
+            0x06 => Bus::Virtual,
+            0x11 => Bus::I8042,
+            0x18 => Bus::I2c,
+            0x19 => Bus::Host,
+            0x1C => Bus::Spi,
+            other => Bus::Other(other),
+        }
+    }
+}
+
+impl Display for Bus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Bus::Pci => write!(f, "pci"),
+            Bus::Usb => write!(f, "usb"),
+            Bus::Bluetooth => write!(f, "bluetooth"),
+            Bus::Virtual => write!(f, "virtual"),
+            Bus::I8042 => write!(f, "i8042"),
+            Bus::I2c => write!(f, "i2c"),
+            Bus::Host => write!(f, "host"),
+            Bus::Spi => write!(f, "spi"),
+            Bus::Other(id) => write!(f, "{id:#06x}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        led::{Led, LEDDIR},
+        tests::{clean_up, setup_test_env},
+    };
+
+    #[test]
+    fn input_device() {
+        clean_up();
+        let name = "input3::capslock";
+        setup_test_env(&[name], 0, 1);
+        let device = format!("{LEDDIR}/{name}/device");
+        fs::create_dir_all(format!("{device}/id")).unwrap();
+        fs::write(format!("{device}/name"), "AT Translated Set 2 keyboard\n").unwrap();
+        fs::write(format!("{device}/id/bustype"), "0011\n").unwrap();
+        fs::write(format!("{device}/id/vendor"), "0001\n").unwrap();
+        fs::write(format!("{device}/id/product"), "0001\n").unwrap();
+        let info = Led::new(name.into())
+            .expect("failed to initialize LED")
+            .device_info()
+            .expect("failed to read device info");
+        assert_eq!(info.name(), Some("AT Translated Set 2 keyboard"));
+        assert_eq!(info.bus(), Some(Bus::I8042));
+        assert_eq!(info.vendor(), Some(1));
+        assert_eq!(info.product(), Some(1));
+        clean_up();
+    }
+
+    #[test]
+    fn hid_device() {
+        clean_up();
+        let name = "0003:046D:C31C.0002:green:numlock";
+        setup_test_env(&[name], 0, 1);
+        let device = format!("{LEDDIR}/0003:046D:C31C.0002");
+        fs::create_dir(&device).unwrap();
+        std::os::unix::fs::symlink(
+            fs::canonicalize(&device).unwrap(),
+            format!("{LEDDIR}/{name}/device"),
+        )
+        .unwrap();
+        let info = Led::new(name.into()).unwrap().device_info().unwrap();
+        assert_eq!(info.name(), None);
+        assert_eq!(info.display_name(), "0003:046D:C31C.0002");
+        assert_eq!(info.bus(), Some(Bus::Usb));
+        assert_eq!(info.vendor(), Some(0x046D));
+        assert_eq!(info.product(), Some(0xC31C));
+        clean_up();
+    }
+
+    #[test]
+    fn missing_device() {
+        clean_up();
+        setup_test_env(&["platform::mute"], 0, 1);
+        let err = Led::new("platform::mute".into())
+            .unwrap()
+            .device_info()
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ReadDeviceInfo);
+        clean_up();
+    }
+}
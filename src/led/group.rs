@@ -0,0 +1,450 @@
+//! Selecting multiple LEDs at once and controlling them as a single unit
+
+use super::{led_names, Color, Function, Led, LedName, LedType};
+use crate::{err::ErrorKind, utils, Delay, Level, Light};
+
+/// Criteria used to select the LEDs that make up an [`LedGroup`]
+///
+/// An empty filter (the [`Default`]) matches every LED. Each additional criterion narrows down the selection,
+/// and an LED has to match all of them to be selected.
+///
+/// # Examples
+/// ```no_run
+/// use blight::led::{Function, LedFilter};
+///
+/// // All capslock LEDs of every connected keyboard
+/// let caps = LedFilter::new().function(Function::Capslock);
+/// // All dimmable LEDs that belong to input devices
+/// let dimmable_input = LedFilter::new().device("input*").dimmable(true);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LedFilter {
+    function: Option<Function>,
+    color: Option<Color>,
+    device: Option<String>,
+    dimmable: Option<bool>,
+}
+
+impl LedFilter {
+    /// Create a filter that matches every LED
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only select LEDs with the given [`Function`]
+    #[must_use]
+    pub fn function(mut self, function: Function) -> Self {
+        self.function = Some(function);
+        self
+    }
+
+    /// Only select LEDs with the given [`Color`]
+    #[must_use]
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Only select LEDs whose device name matches the given glob pattern
+    ///
+    /// The pattern is matched against the [parsed name][LedName::parsed_name] of the LED, or its full raw name if the name
+    /// does not follow the kernel's naming convention. `*` matches any sequence of characters and `?` matches a single character.
+    #[must_use]
+    pub fn device(mut self, pattern: impl Into<String>) -> Self {
+        self.device = Some(pattern.into());
+        self
+    }
+
+    /// Only select dimmable (`true`) or non-dimmable (`false`) LEDs
+    #[must_use]
+    pub fn dimmable(mut self, dimmable: bool) -> Self {
+        self.dimmable = Some(dimmable);
+        self
+    }
+
+    /// Check whether an LED name satisfies the filter
+    ///
+    /// Dimmability cannot be determined from the name alone and is therefore ignored by this method.
+    #[must_use]
+    pub fn matches_name(&self, name: &LedName) -> bool {
+        self.function.as_ref().is_none_or(|f| f == name.function())
+            && self.color.as_ref().is_none_or(|c| c == name.color())
+            && self.device.as_deref().is_none_or(|pattern| {
+                utils::glob_match(pattern, name.parsed_name().unwrap_or(name.raw_name()))
+            })
+    }
+
+    /// Check whether an initialized LED satisfies the filter
+    #[must_use]
+    pub fn matches(&self, led: &LedType) -> bool {
+        self.matches_name(led.led_name())
+            && self
+                .dimmable
+                .is_none_or(|dimmable| dimmable == led.is_dimmable())
+    }
+}
+
+/// A set of LEDs that can be controlled as a single unit
+///
+/// Useful when the same change needs to be applied to multiple LEDs, such as the capslock LEDs of
+/// multiple keyboards, or the zones of a multi-zone keyboard backlight.
+///
+/// Values are applied to each LED relative to its own max brightness. Non-dimmable LEDs in the group
+/// are turned on for any non-zero value or percentage.
+///
+/// # Examples
+/// ```no_run
+/// use blight::{led::{Function, LedFilter, LedGroup}, Delay};
+///
+/// fn main() -> blight::Result<()> {
+///     let mut caps = LedGroup::new(&LedFilter::new().function(Function::Capslock))?;
+///     caps.toggle()?; // toggle all capslock LEDs together
+///
+///     let mut kbd = LedGroup::new(&LedFilter::new().function(Function::KbdBacklight))?;
+///     kbd.sweep_percent(50.0, Delay::default())?; // smoothly dim all zones to 50%
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct LedGroup {
+    leds: Vec<LedType>,
+}
+
+impl LedGroup {
+    /// Initialize all the LEDs in `/sys/class/leds` that match the given [`LedFilter`]
+    ///
+    /// # Errors
+    /// - [`ErrorKind::NotFound`] - no LED matched the filter
+    /// - All possible errors returned by [`led_names`] and [`Led::from_name`]
+    pub fn new(filter: &LedFilter) -> crate::Result<Self> {
+        let mut leds = vec![];
+        for name in led_names()?.into_iter().filter(|n| filter.matches_name(n)) {
+            let led = Led::from_name(name)?;
+            if filter.matches(&led) {
+                leds.push(led);
+            }
+        }
+        if leds.is_empty() {
+            return Err(ErrorKind::NotFound.into());
+        }
+        leds.sort_by(|a, b| a.name().cmp(b.name()));
+        Ok(Self { leds })
+    }
+
+    /// Create a group out of already initialized LEDs
+    #[must_use]
+    pub fn from_leds(leds: Vec<LedType>) -> Self {
+        Self { leds }
+    }
+
+    /// LEDs that are part of the group
+    #[must_use]
+    pub fn leds(&self) -> &[LedType] {
+        &self.leds
+    }
+
+    /// Iterator over the LEDs that are part of the group
+    pub fn iter(&self) -> std::slice::Iter<'_, LedType> {
+        self.leds.iter()
+    }
+
+    /// Consume the group and return the LEDs that were part of it
+    #[must_use]
+    pub fn into_leds(self) -> Vec<LedType> {
+        self.leds
+    }
+
+    /// Number of LEDs in the group
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.leds.len()
+    }
+
+    /// Returns `true` if the group contains no LEDs
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.leds.is_empty()
+    }
+
+    /// Returns `true` if any LED in the group is on
+    #[must_use]
+    pub fn is_on(&self) -> bool {
+        self.leds.iter().any(|led| led.current() != 0)
+    }
+
+    /// Average brightness percentage of all the LEDs in the group (not rounded)
+    #[must_use]
+    pub fn current_percent(&self) -> f64 {
+        if self.leds.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = self
+            .leds
+            .iter()
+            .map(|led| f64::from(led.current()) / f64::from(led.max()) * 100.0)
+            .sum();
+        #[allow(clippy::cast_precision_loss)]
+        let len = self.leds.len() as f64;
+        sum / len
+    }
+
+    /// Turn all LEDs on (max brightness) or off
+    ///
+    /// # Errors
+    /// - All possible errors returned by [`Light::write_value`]
+    pub fn set_state(&mut self, on: bool) -> crate::Result<()> {
+        for led in &mut self.leds {
+            led.write_value(if on { led.max() } else { 0 })?;
+        }
+        Ok(())
+    }
+
+    /// Turn all LEDs off if any of them is on, otherwise turn them all on
+    ///
+    /// Unlike toggling each LED individually, this keeps all the LEDs of the group in sync.
+    ///
+    /// # Errors
+    /// - All possible errors returned by [`Light::write_value`]
+    pub fn toggle(&mut self) -> crate::Result<()> {
+        self.set_state(!self.is_on())
+    }
+
+    /// Write a raw brightness value to all LEDs
+    ///
+    /// The value is capped at the max brightness of each individual LED.
+    ///
+    /// # Errors
+    /// - All possible errors returned by [`Light::write_value`]
+    pub fn write_value(&mut self, value: u32) -> crate::Result<()> {
+        for led in &mut self.leds {
+            let value = if led.is_dimmable() {
+                value.min(led.max())
+            } else {
+                u32::from(value != 0)
+            };
+            led.write_value(value)?;
+        }
+        Ok(())
+    }
+
+    /// Set the brightness of all LEDs to the given [`Level`], resolved separately for each LED
+    ///
+    /// Raw values larger than the max brightness of an LED are clamped, and non-dimmable LEDs
+    /// are turned on for any level above 0, e.g. `40%` or `+1` (same as [`LedGroup::set_percent`]).
+    ///
+    /// # Errors
+    /// - All possible errors returned by [`Light::write_value`]
+    pub fn set_level(&mut self, level: Level) -> crate::Result<()> {
+        for led in &mut self.leds {
+            let value = Self::level_value(led, level);
+            led.write_value(value)?;
+        }
+        Ok(())
+    }
+
+    /// Set the brightness of all LEDs to the given percentage of their max brightness
+    ///
+    /// # Errors
+    /// - All possible errors returned by [`Light::write_value`]
+    pub fn set_percent(&mut self, percent: f64) -> crate::Result<()> {
+        for led in &mut self.leds {
+            let value = Self::percent_value(led, percent);
+            led.write_value(value)?;
+        }
+        Ok(())
+    }
+
+    /// Smoothly change the brightness of all LEDs to the given percentage of their max brightness
+    ///
+    /// All LEDs are changed in lockstep in increments of 1%, with the given delay between each step.
+    /// See [`Light::sweep_write`] for details.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::SweepError`] - on write failure
+    pub fn sweep_percent(&mut self, percent: f64, delay: Delay) -> crate::Result<()> {
+        const STEPS: u32 = 100;
+        let plan: Vec<(u32, u32)> = self
+            .leds
+            .iter()
+            .map(|led| (led.current(), Self::percent_value(led, percent)))
+            .collect();
+        let sweep_err = |err: crate::Error| match err.kind() {
+            ErrorKind::WriteValue { .. } => crate::Error::from(ErrorKind::SweepError),
+            _ => err,
+        };
+        for step in 1..=STEPS {
+            let mut changed = false;
+            for (led, &(start, target)) in self.leds.iter_mut().zip(&plan) {
+                let value = utils::interpolate(start, target, f64::from(step) / f64::from(STEPS));
+                if value != led.current() {
+                    led.write_value(value).map_err(sweep_err)?;
+                    changed = true;
+                }
+            }
+            if changed && step != STEPS {
+                std::thread::sleep(*delay);
+            }
+        }
+        Ok(())
+    }
+
+    fn level_value(led: &LedType, level: Level) -> u32 {
+        match level {
+            Level::Raw(value) if led.is_dimmable() => value.min(led.max()),
+            Level::Raw(value) => u32::from(value != 0),
+            Level::Percent(percent) => Self::percent_value(led, percent),
+            _ if led.is_dimmable() => level.resolve_for(led),
+            // Resolving would round small levels of a non-dimmable LED down to off
+            Level::RelativeRaw(delta) => u32::from(i64::from(led.current()) + delta > 0),
+            Level::RelativePercent(delta) => {
+                let current = f64::from(led.current()) / f64::from(led.max().max(1)) * 100.0;
+                u32::from(current + delta > 0.0)
+            }
+        }
+    }
+
+    fn percent_value(led: &LedType, percent: f64) -> u32 {
+        if led.is_dimmable() {
+            utils::percent_to_value(percent, led.max())
+        } else {
+            u32::from(percent > 0.0)
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a LedGroup {
+    type Item = &'a LedType;
+    type IntoIter = std::slice::Iter<'a, LedType>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.leds.iter()
+    }
+}
+
+impl IntoIterator for LedGroup {
+    type Item = LedType;
+    type IntoIter = std::vec::IntoIter<LedType>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.leds.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        led::LEDDIR,
+        tests::{clean_up, setup_test_env},
+    };
+
+    #[test]
+    fn filter_names() {
+        let caps = LedName::parse("input3::capslock".into());
+        let kbd = LedName::parse("platform:white:kbd_backlight".into());
+        let filter = LedFilter::new().function(Function::Capslock);
+        assert!(filter.matches_name(&caps));
+        assert!(!filter.matches_name(&kbd));
+        let filter = LedFilter::new().device("input*");
+        assert!(filter.matches_name(&caps));
+        assert!(!filter.matches_name(&kbd));
+        let filter = LedFilter::new()
+            .color(Color::White)
+            .function(Function::KbdBacklight);
+        assert!(filter.matches_name(&kbd));
+        assert!(LedFilter::new().matches_name(&caps));
+    }
+
+    #[test]
+    fn group_toggle() {
+        clean_up();
+        let names = ["input3::capslock", "input7::capslock", "input3::numlock"];
+        setup_test_env(&names, 0, 1);
+        let mut group = LedGroup::new(&LedFilter::new().function(Function::Capslock))
+            .expect("failed to initialize LED group");
+        assert_eq!(group.len(), 2);
+        group.leds[0].write_value(1).unwrap();
+        // One LED is on, so toggling turns all of them off
+        group.toggle().expect("failed to toggle group");
+        assert!(group.leds().iter().all(|led| led.current() == 0));
+        group.toggle().expect("failed to toggle group");
+        assert!(group.leds().iter().all(|led| led.current() == 1));
+        let LedType::NonDimmable(numlock) = Led::new("input3::numlock".into()).unwrap() else {
+            unreachable!()
+        };
+        assert_eq!(numlock.current(), 0, "LED outside of the group was changed");
+        clean_up();
+    }
+
+    #[test]
+    fn group_percent() {
+        clean_up();
+        let names = ["zone1::kbd_backlight", "zone2::kbd_backlight"];
+        setup_test_env(&names, 0, 1023);
+        let mut group = LedGroup::new(&LedFilter::new().dimmable(true))
+            .expect("failed to initialize LED group");
+        group.set_percent(50.0).expect("failed to set percent");
+        for led in &mut group.leds {
+            led.reload();
+            assert_eq!(led.current(), 512);
+        }
+        group
+            .sweep_percent(100.0, Delay::from_millis(0))
+            .expect("failed to sweep");
+        for led in &mut group.leds {
+            led.reload();
+            assert_eq!(led.current(), 1023);
+        }
+        assert!((group.current_percent() - 100.0).abs() < f64::EPSILON);
+        clean_up();
+    }
+
+    #[test]
+    fn group_level() {
+        clean_up();
+        let names = ["input3::capslock", "zone1::kbd_backlight"];
+        setup_test_env(&names, 0, 1);
+        std::fs::write(
+            format!("{LEDDIR}/zone1::kbd_backlight/max_brightness"),
+            "200",
+        )
+        .unwrap();
+        let mut group = LedGroup::new(&LedFilter::new()).expect("failed to initialize LED group");
+        group.set_level(Level::Raw(150)).unwrap();
+        assert_eq!(group.leds[0].current(), 1);
+        assert_eq!(group.leds[1].current(), 150);
+        group.set_level(Level::RelativePercent(-50.0)).unwrap();
+        assert_eq!(group.leds[0].current(), 1);
+        assert_eq!(group.leds[1].current(), 50);
+        group.set_level(Level::RelativePercent(-100.0)).unwrap();
+        assert_eq!(group.leds[0].current(), 0);
+        group.set_level(Level::RelativeRaw(1)).unwrap();
+        assert_eq!(group.leds[0].current(), 1);
+        group.set_level(Level::Raw(500)).unwrap();
+        assert_eq!(group.leds[1].current(), 200);
+        clean_up();
+    }
+
+    #[test]
+    fn non_dimmable_level() {
+        clean_up();
+        setup_test_env(&["input3::capslock"], 0, 1);
+        let mut group = LedGroup::new(&LedFilter::new()).expect("failed to initialize LED group");
+        // 40% of a max brightness of 1 would round down to off
+        group.set_level(Level::Percent(40.0)).unwrap();
+        assert_eq!(group.leds[0].current(), 1);
+        group.set_level(Level::Percent(0.0)).unwrap();
+        assert_eq!(group.leds[0].current(), 0);
+        clean_up();
+    }
+
+    #[test]
+    fn empty_group() {
+        clean_up();
+        setup_test_env(&["input3::numlock"], 0, 1);
+        let err = LedGroup::new(&LedFilter::new().function(Function::Capslock)).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::NotFound);
+        clean_up();
+    }
+}
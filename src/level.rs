@@ -0,0 +1,210 @@
+//! Brightness levels written as raw values, percentages or relative changes
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::{utils, Light};
+
+/// A brightness level that can be absolute or relative to the current brightness, in raw values or percentages
+///
+/// Levels are usually parsed from user input, which accepts the following forms:
+///
+/// | Input | Level |
+/// |-------|-------|
+/// | `128` | [`Level::Raw`] - raw brightness value |
+/// | `40%` | [`Level::Percent`] - percentage of the max brightness |
+/// | `0.35` | [`Level::Percent`] - fraction of the max brightness (`0.0` to `1.0`, must contain a decimal point) |
+/// | `+10%`, `-5%` | [`Level::RelativePercent`] - change by a percentage of the max brightness |
+/// | `+10`, `-5` | [`Level::RelativeRaw`] - change by a raw value |
+///
+/// Use [`Level::resolve`] or [`Level::resolve_for`] to get the raw value to write to a light.
+///
+/// # Examples
+/// ```no_run
+/// use blight::{Device, Level, Light};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut dev = Device::new(None)?;
+///     let level: Level = "+10%".parse()?;
+///     dev.write_value(level.resolve_for(&dev))?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Raw(u32),
+    Percent(f64),
+    RelativeRaw(i64),
+    RelativePercent(f64),
+}
+
+impl Level {
+    /// Whether the level is relative to the current brightness
+    #[must_use]
+    pub fn is_relative(self) -> bool {
+        matches!(self, Level::RelativeRaw(_) | Level::RelativePercent(_))
+    }
+
+    /// Resolve the level to a raw brightness value, given the current and max brightness
+    ///
+    /// Percentages and relative changes are clamped between `0` and `max`. Absolute raw values are returned as is,
+    /// so that writing a value larger than `max` is still reported as an error.
+    #[must_use]
+    pub fn resolve(self, current: u32, max: u32) -> u32 {
+        let relative = |delta: i64| {
+            let value = (i64::from(current) + delta).clamp(0, i64::from(max));
+            u32::try_from(value).unwrap_or_default()
+        };
+        match self {
+            Level::Raw(value) => value,
+            Level::Percent(percent) => utils::percent_to_value(percent, max),
+            Level::RelativeRaw(delta) => relative(delta),
+            Level::RelativePercent(percent) => {
+                let delta = i64::from(utils::percent_to_value(percent.abs(), max));
+                relative(if percent < 0.0 { -delta } else { delta })
+            }
+        }
+    }
+
+    /// Resolve the level to a raw brightness value for the given light
+    ///
+    /// See [`Level::resolve`] for details.
+    #[must_use]
+    pub fn resolve_for<L: Light>(self, light: &L) -> L::Value {
+        let value = self.resolve(light.current().into(), light.max().into());
+        L::Value::try_from(value).unwrap_or_default()
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Level::Raw(value) => write!(f, "{value}"),
+            Level::Percent(percent) => write!(f, "{percent}%"),
+            Level::RelativeRaw(delta) => write!(f, "{delta:+}"),
+            Level::RelativePercent(percent) => write!(f, "{percent:+}%"),
+        }
+    }
+}
+
+impl FromStr for Level {
+    type Err = ParseLevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |reason| ParseLevelError {
+            input: s.to_owned(),
+            reason,
+        };
+        // `Some(true)` for a negative relative change, `None` for an absolute level
+        let (negative, unsigned) = match s.as_bytes().first() {
+            Some(b'+') => (Some(false), &s[1..]),
+            Some(b'-') => (Some(true), &s[1..]),
+            _ => (None, s),
+        };
+        // Reject inputs such as "+-5" or "+ 5", which the number parsers would otherwise handle
+        if unsigned.is_empty() || !unsigned.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+            return Err(err("expected a number"));
+        }
+        if let Some(percent) = unsigned.strip_suffix('%') {
+            let percent: f64 = percent
+                .parse()
+                .ok()
+                .filter(|p: &f64| p.is_finite())
+                .ok_or(err("expected a number before '%'"))?;
+            if percent > 100.0 {
+                return Err(err("percentages can't be larger than 100%"));
+            }
+            return Ok(match negative {
+                Some(true) => Level::RelativePercent(-percent),
+                Some(false) => Level::RelativePercent(percent),
+                None => Level::Percent(percent),
+            });
+        }
+        if unsigned.contains('.') {
+            let fraction: f64 = unsigned.parse().map_err(|_| err("expected a number"))?;
+            return match negative {
+                Some(_) => Err(err("relative changes must be whole numbers or percentages")),
+                None if (0.0..=1.0).contains(&fraction) => Ok(Level::Percent(fraction * 100.0)),
+                None => Err(err("fractions must be between 0.0 and 1.0")),
+            };
+        }
+        let value: u32 = unsigned
+            .parse()
+            .map_err(|_| err("expected a whole number"))?;
+        Ok(match negative {
+            Some(true) => Level::RelativeRaw(-i64::from(value)),
+            Some(false) => Level::RelativeRaw(i64::from(value)),
+            None => Level::Raw(value),
+        })
+    }
+}
+
+/// Error returned when parsing a [`Level`] fails, explaining the valid forms
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLevelError {
+    input: String,
+    reason: &'static str,
+}
+
+impl Display for ParseLevelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid brightness '{}' ({}). Valid forms are: 128 (raw value), 40% (percentage), 0.35 (fraction), +10% or -5% (relative percentage), +10 or -5 (relative raw value)",
+            self.input, self.reason
+        )
+    }
+}
+
+impl std::error::Error for ParseLevelError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_forms() {
+        let cases = [
+            ("128", Level::Raw(128)),
+            ("40%", Level::Percent(40.0)),
+            ("12.5%", Level::Percent(12.5)),
+            ("0.35", Level::Percent(35.0)),
+            ("1.0", Level::Percent(100.0)),
+            ("+10%", Level::RelativePercent(10.0)),
+            ("-5%", Level::RelativePercent(-5.0)),
+            ("+10", Level::RelativeRaw(10)),
+            ("-5", Level::RelativeRaw(-5)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                input.parse::<Level>(),
+                Ok(expected),
+                "failed to parse {input}"
+            );
+        }
+        for invalid in [
+            "", "+", "abc", "101%", "1.5", "+0.5", "5x", "+-5", "%", "-1.0",
+        ] {
+            assert!(invalid.parse::<Level>().is_err(), "{invalid:?} was parsed");
+        }
+    }
+
+    #[test]
+    fn resolve() {
+        let cases = [
+            (Level::Raw(300), 300),
+            (Level::Percent(40.0), 80),
+            (Level::RelativeRaw(-150), 0),
+            (Level::RelativeRaw(30), 130),
+            (Level::RelativePercent(10.0), 120),
+            (Level::RelativePercent(-10.0), 80),
+            (Level::RelativePercent(100.0), 200),
+        ];
+        for (level, expected) in cases {
+            assert_eq!(
+                level.resolve(100, 200),
+                expected,
+                "failed to resolve {level}"
+            );
+        }
+    }
+}
@@ -17,6 +17,11 @@
 //! > If you're only using blight as a dependency, you can read about gaining file permissions [here](https://wiki.archlinux.org/title/Backlight#ACPI).
 //!
 //! **For LED specific documentation and usage, see [led module][led].**
+//! Time-based brightness effects such as breathing and blinking are available in the [effects module][effects],
+//! and brightness patterns can be scripted with a small text language using the [sequence module][sequence].
+//! Brightness changes made by other programs can be followed with the [watch module][watch],
+//! and the brightness of every backlight and LED can be captured and restored later with a [`Snapshot`].
+//! Temporary changes can be undone automatically with a [`BrightnessGuard`].
 //!
 //! # Usage
 //! ```no_run
@@ -52,12 +57,30 @@ use std::{
     time::Duration,
 };
 
+pub mod bind;
+pub mod effects;
 pub mod err;
 pub mod led;
+pub mod sequence;
+pub mod snapshot;
+pub mod watch;
 pub use err::{Error, ErrorKind, Result};
 
+mod clamp;
+mod guard;
+mod identity;
+mod level;
+pub use clamp::{Bound, ClampPolicy};
+pub use guard::BrightnessGuard;
+pub use identity::DeviceIdentity;
+pub use level::{Level, ParseLevelError};
+pub use snapshot::Snapshot;
+
 /// Linux backlight directory location. All backlight hardware devices appear here.
+#[cfg(not(test))]
 pub const BLDIR: &str = "/sys/class/backlight";
+#[cfg(test)]
+pub const BLDIR: &str = "testbldir";
 const CURRENT_FILE: &str = "brightness";
 const MAX_FILE: &str = "max_brightness";
 
@@ -107,11 +130,49 @@ impl Default for Delay {
 }
 
 impl Delay {
+    #[must_use]
     pub fn from_millis(millis: u64) -> Self {
         Self(Duration::from_millis(millis))
     }
 }
 
+/// Parses a duration written as a number followed by a unit: `ms`, `s` or `m` (e.g. `200ms`, `1.5s`, `2m`)
+impl std::str::FromStr for Delay {
+    type Err = ParseDelayError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let split = s
+            .find(|c: char| c.is_ascii_alphabetic())
+            .ok_or(ParseDelayError)?;
+        let (value, unit) = s.split_at(split);
+        let value: f64 = value.parse().map_err(|_| ParseDelayError)?;
+        let secs = match unit {
+            "ms" => value / 1000.0,
+            "s" => value,
+            "m" => value * 60.0,
+            _ => return Err(ParseDelayError),
+        };
+        Duration::try_from_secs_f64(secs)
+            .map(Self)
+            .map_err(|_| ParseDelayError)
+    }
+}
+
+/// Error returned when parsing a [`Delay`] from a string fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseDelayError;
+
+impl Display for ParseDelayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid duration, expected a number followed by a unit (e.g. 200ms, 1.5s, 2m)"
+        )
+    }
+}
+
+impl std::error::Error for ParseDelayError {}
+
 /// An abstraction of a backlight device containing a name, current and max backlight values, and some related functionality.
 ///
 /// A Device instance is created by using the [constructor][Device::new], values are read from /sys/class/backlight/ directory based on the detected GPU device.
@@ -142,6 +203,7 @@ pub struct Device {
     max: u32,
     path: PathBuf,
     brightness: File,
+    clamp: ClampPolicy,
 }
 
 impl Device {
@@ -166,6 +228,7 @@ impl Device {
             path: info.path,
             name: name.into_owned(),
             brightness: info.brightness,
+            clamp: ClampPolicy::default(),
         })
     }
 
@@ -202,9 +265,58 @@ impl Device {
             path: info.path,
             name: name.into_owned(),
             brightness: info.brightness,
+            clamp: ClampPolicy::default(),
         })
     }
 
+    /// Find the backlight device that best matches a name and [identity][DeviceIdentity], e.g. ones saved before a kernel update renamed the device
+    ///
+    /// A device with a matching identity is preferred over the one with the given name, so a renamed device is still found
+    /// and a device that took over the name of a different one isn't used. Devices with a different connector or driver
+    /// are never used, even with the given name. Without a known identity, only the name is matched.
+    /// # Errors
+    /// Possible errors that can result from this function include:
+    /// * [``ErrorKind::NotFound``] - no device matches
+    /// * [``ErrorKind::ReadDir``]
+    /// * [``ErrorKind::ReadCurrent``]
+    /// * [``ErrorKind::ReadMax``]
+    pub fn find(name: &str, identity: &DeviceIdentity) -> Result<Device> {
+        // Names are sorted, the reversed order makes the first of equally good matches win
+        let best = device_names()?
+            .into_iter()
+            .rev()
+            .filter_map(|candidate| {
+                let path = utils::construct_path(BLDIR, &candidate);
+                let score =
+                    identity.score(&DeviceIdentity::read(&path))? + i32::from(candidate == name);
+                Some((score, candidate))
+            })
+            .filter(|(score, _)| *score > 0)
+            .max_by_key(|(score, _)| *score);
+        match best {
+            Some((_, name)) => Device::new(Some(name.into())),
+            None => Err(ErrorKind::NotFound.into()),
+        }
+    }
+
+    /// Limit the brightness the device can be set to, see [`ClampPolicy`]
+    pub fn set_clamp_policy(&mut self, policy: ClampPolicy) {
+        self.clamp = policy;
+    }
+
+    /// Builder style variant of [`Device::set_clamp_policy`]
+    #[must_use]
+    pub fn with_clamp_policy(mut self, policy: ClampPolicy) -> Self {
+        self.clamp = policy;
+        self
+    }
+
+    /// Identity of the device, which can be used to [find][Device::find] it again after it has been renamed
+    #[must_use]
+    pub fn identity(&self) -> DeviceIdentity {
+        DeviceIdentity::read(&self.path)
+    }
+
     fn detect_device(bldir: &str) -> Result<String> {
         let dirs: Vec<_> = fs::read_dir(bldir)
             .map_err(|err| Error::from(ErrorKind::ReadDir { dir: BLDIR }).with_source(err))?
@@ -255,6 +367,10 @@ impl Light for Device {
         self.max
     }
 
+    fn clamp_policy(&self) -> ClampPolicy {
+        self.clamp
+    }
+
     fn set_current(&mut self, _: private::Internal, current: Self::Value) {
         self.current = current;
     }
@@ -301,6 +417,13 @@ pub trait Light: private::Sealed {
     /// Absolute path to the device interface in `/sys/class/..` directory
     fn device_path(&self) -> &Path;
 
+    /// Lowest and highest brightness that writes are limited to
+    ///
+    /// Only backlight devices can be limited (see [`Device::set_clamp_policy`]), other lights are never limited.
+    fn clamp_policy(&self) -> ClampPolicy {
+        ClampPolicy::default()
+    }
+
     #[doc(hidden)]
     fn set_current(&mut self, _: private::Internal, current: Self::Value);
     #[doc(hidden)]
@@ -327,6 +450,9 @@ pub trait Light: private::Sealed {
     }
 
     /// Reloads current brightness value for the device by reading the brightness file
+    ///
+    /// # Errors
+    /// - [``ErrorKind::ReadCurrent``] - on read failure
     fn try_reload(&mut self) -> Result<()> {
         let current = utils::read_ascii_u32(self.brightness_file(private::Internal))
             .map_err(|err| Error::from(ErrorKind::ReadCurrent).with_source(err))?;
@@ -339,6 +465,9 @@ pub trait Light: private::Sealed {
 
     /// Write the given value to the brightness file of the device
     ///
+    /// The value is moved within the [clamp policy][Light::clamp_policy] of the device, use
+    /// [`Light::write_value_unclamped`] to write it as is.
+    ///
     /// **Note: this method updates the `current` brightness value in `self` to the final
     /// value that was successfully written to the brightness file. If there is a chance that the brightness
     /// file was modified by some other process after this function was called, consider force reloading the
@@ -348,6 +477,16 @@ pub trait Light: private::Sealed {
     /// - [``ErrorKind::ValueTooLarge``] - if provided value is larger than the supported value
     /// - [``ErrorKind::WriteValue``] - on write failure
     fn write_value(&mut self, value: Self::Value) -> Result<()> {
+        let value = utils::clamped(self, value);
+        self.write_value_unclamped(value)
+    }
+
+    /// Write the given value to the brightness file of the device, ignoring its [clamp policy][Light::clamp_policy]
+    ///
+    /// # Errors
+    /// - [``ErrorKind::ValueTooLarge``] - if provided value is larger than the supported value
+    /// - [``ErrorKind::WriteValue``] - on write failure
+    fn write_value_unclamped(&mut self, value: Self::Value) -> Result<()> {
         let (val, max): (u32, u32) = (value.into(), self.max().into());
         if val > max {
             return Err(ErrorKind::ValueTooLarge {
@@ -359,7 +498,7 @@ pub trait Light: private::Sealed {
         let name = self.name().into();
         let convert = |err| Error::from(ErrorKind::WriteValue { device: name }).with_source(err);
         let file = self.brightness_file(private::Internal);
-        write!(file, "{val}",).map_err(convert.clone())?;
+        write!(file, "{val}").map_err(convert.clone())?;
         file.rewind().map_err(convert)?;
         self.set_current(private::Internal, value);
         Ok(())
@@ -374,6 +513,9 @@ pub trait Light: private::Sealed {
     ///
     /// No file writes are performed and `Ok(())` is returned if `value` == `self.current()`
     ///
+    /// The target value is moved within the [clamp policy][Light::clamp_policy] of the device, use
+    /// [`Light::sweep_write_unclamped`] to sweep to it as is.
+    ///
     /// **Note: this method updates the `current` brightness value in `self` to the final
     /// value that was successfully written to the brightness file. If there is a chance that the brightness
     /// file was modified by some other process after this function was called, consider force reloading the
@@ -392,6 +534,20 @@ pub trait Light: private::Sealed {
     /// Possible errors that can result from this function include:
     /// * [``ErrorKind::SweepError``]
     fn sweep_write(&mut self, value: Self::Value, delay: Delay) -> Result<()>
+    where
+        Self: Dimmable,
+    {
+        let value = utils::clamped(self, value);
+        self.sweep_write_unclamped(value, delay)
+    }
+
+    /// Sweep to the given value like [`Light::sweep_write`], ignoring the [clamp policy][Light::clamp_policy] of the device
+    ///
+    /// # Errors
+    /// Possible errors that can result from this function include:
+    /// * [``ErrorKind::ValueTooLarge``]
+    /// * [``ErrorKind::SweepError``]
+    fn sweep_write_unclamped(&mut self, value: Self::Value, delay: Delay) -> Result<()>
     where
         Self: Dimmable,
     {
@@ -404,8 +560,9 @@ pub trait Light: private::Sealed {
             }
             .into());
         }
+        // Devices with a max brightness below 100 still need to move by at least 1 per step
         #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
-        let mut rate = (f64::from(max) * 0.01) as u32;
+        let mut rate = ((f64::from(max) * 0.01) as u32).max(1);
         let dir = if val > current {
             Direction::Inc
         } else {
@@ -443,7 +600,8 @@ pub trait Light: private::Sealed {
     /// using the current and max values of the backlight/LED device. (Always guaranteed to be valid)
     ///
     /// For example, if the current value is 10 and max is 100, and you want to increase it by 10% (`step_size`),
-    /// the method will return 20, which can be directly written to the device.
+    /// the method will return 20, which can be directly written to the device. The result is kept within the
+    /// [clamp policy][Light::clamp_policy] of the device.
     fn calculate_change(&self, step_size: Self::Value, dir: Direction) -> Self::Value
     where
         Self: Dimmable,
@@ -457,12 +615,14 @@ pub trait Light: private::Sealed {
             Direction::Dec => current.saturating_sub(step),
         }
         .min(max); // return max if calculated value is > max
+        let change = self.clamp_policy().apply(change, max);
         Self::Value::try_from(change).unwrap_or_default()
     }
 
     /// Toggle between `0` and the [`max`](Light::max) brightness value of the device
     ///
-    /// This method is mainly intended for toggling LEDs on/off.
+    /// This method is mainly intended for toggling LEDs on/off. Devices with a [clamp policy][Light::clamp_policy]
+    /// toggle between its floor and ceiling instead.
     ///
     /// ## Errors
     /// - All possible errors that can occur when calling [`Light::write_value`]
@@ -470,15 +630,68 @@ pub trait Light: private::Sealed {
     where
         Self: Toggleable,
     {
-        let value = if self.current() == self.max() {
-            Self::Value::default()
+        let (floor, ceiling) = self.clamp_policy().range(self.max().into());
+        let value = if self.current().into() >= ceiling {
+            floor
         } else {
-            self.max()
+            ceiling
         };
-        self.write_value(value)
+        self.write_value(Self::Value::try_from(value).unwrap_or_default())
+    }
+
+    /// Remember the current brightness and restore it once the returned [`BrightnessGuard`] is dropped
+    ///
+    /// Useful for temporary changes, e.g. raising the brightness for a presentation.
+    fn guard(&mut self) -> BrightnessGuard<'_, Self> {
+        BrightnessGuard::new(self)
     }
 }
 
+/// Helper function to read the names of all the backlight devices available in `/sys/class/backlight`
+///
+/// # Errors
+/// - [`ErrorKind::ReadDir`] - failure to read [`BLDIR`]
+pub fn device_names() -> Result<Vec<String>> {
+    let read_dir_err = |err| Error::from(ErrorKind::ReadDir { dir: BLDIR }).with_source(err);
+    let mut names = vec![];
+    for entry in fs::read_dir(BLDIR).map_err(read_dir_err)? {
+        let entry = entry.map_err(read_dir_err)?;
+        names.push(entry.file_name().to_string_lossy().into_owned());
+    }
+    names.sort_unstable();
+    Ok(names)
+}
+
+/// Helper function to initialize all the backlight devices available in `/sys/class/backlight`, with a separate result for each device
+///
+/// A single device failing to initialize (for example, due to an unreadable brightness file) does not prevent the rest
+/// from being returned. Each device name is paired with the result of its initialization.
+///
+/// # Examples
+/// ```no_run
+/// # fn main() -> blight::Result<()> {
+/// for (name, device) in blight::try_devices()? {
+///     match device {
+///         Ok(device) => { /* do something with the device */ }
+///         Err(err) => eprintln!("{name}: {err}"),
+///     }
+/// }
+/// #   Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+/// - All possible errors returned by [`device_names`]
+pub fn try_devices() -> Result<Vec<(String, Result<Device>)>> {
+    Ok(device_names()?
+        .into_iter()
+        .map(|name| {
+            let device = Device::new(Some(Cow::from(name.as_str())));
+            (name, device)
+        })
+        .collect())
+}
+
 /// A helper function to change backlight based on step-size (percentage), [Change] type and [Direction].
 ///
 /// Regular change uses [calculated change][Device::calculate_change] value based on step size and is applied instantly.
@@ -494,7 +707,33 @@ pub fn change_bl(
     dir: Direction,
     device_name: Option<Cow<str>>,
 ) -> crate::Result<()> {
-    let mut device = Device::new(device_name)?;
+    change_bl_clamped(step_size, ch, dir, device_name, ClampPolicy::default())
+}
+
+/// Same as [`change_bl`], with the brightness kept within a [`ClampPolicy`]
+///
+/// # Examples
+/// ```no_run
+/// # use blight::{Bound, Change, ClampPolicy, Direction};
+/// # fn main() -> blight::Result<()> {
+/// // Never dim the screen below 5%
+/// let policy = ClampPolicy::new().min(Bound::Percent(5.0));
+/// blight::change_bl_clamped(10, Change::Regular, Direction::Dec, None, policy)?;
+/// # Ok(())
+/// # }
+/// ```
+/// # Errors
+/// Possible errors that can result from this function include:
+/// * All errors that can result from [``Device::new``]
+/// * [``ErrorKind::WriteValue``]
+pub fn change_bl_clamped(
+    step_size: u32,
+    ch: Change,
+    dir: Direction,
+    device_name: Option<Cow<str>>,
+    policy: ClampPolicy,
+) -> crate::Result<()> {
+    let mut device = Device::new(device_name)?.with_clamp_policy(policy);
 
     let change = device.calculate_change(step_size, dir);
     if change != device.current {
@@ -509,7 +748,7 @@ pub fn change_bl(
 /// A helper function which takes a brightness value and writes the value to the brightness file
 /// as long as the given value falls under the min and max bounds of the detected backlight device and is different from the current value.
 ///
-/// *Note: Unlike [change_bl], this function does not calculate any change, it writes the given value directly.*
+/// *Note: Unlike [`change_bl`], this function does not calculate any change, it writes the given value directly.*
 /// # Examples
 /// ```no_run
 /// # fn main() -> blight::Result<()> {
@@ -528,7 +767,18 @@ pub fn change_bl(
 /// * All errors that can result from [``Device::new``]
 /// * All errors that can result from [`Light::write_value`]
 pub fn set_bl(val: u32, device_name: Option<Cow<str>>) -> Result<()> {
-    let mut device = Device::new(device_name)?;
+    set_bl_clamped(val, device_name, ClampPolicy::default())
+}
+
+/// Same as [`set_bl`], with the value moved within a [`ClampPolicy`]
+///
+/// # Errors
+/// Possible errors that can result from this function include:
+/// * All errors that can result from [``Device::new``]
+/// * All errors that can result from [`Light::write_value`]
+pub fn set_bl_clamped(val: u32, device_name: Option<Cow<str>>, policy: ClampPolicy) -> Result<()> {
+    let mut device = Device::new(device_name)?.with_clamp_policy(policy);
+    let val = utils::clamped(&device, val);
     if val != device.current {
         device.write_value(val)?;
     }
@@ -541,9 +791,12 @@ mod utils {
         fs::File,
         io::{Read, Seek},
         path::PathBuf,
+        sync::atomic::{AtomicBool, Ordering},
+        thread,
+        time::{Duration, Instant},
     };
 
-    use crate::{CURRENT_FILE, MAX_FILE};
+    use crate::{Delay, Light, CURRENT_FILE, MAX_FILE};
 
     pub(crate) struct Info {
         pub(crate) current: u32,
@@ -560,7 +813,8 @@ mod utils {
     }
 
     /// Read all the necessary info from the backlight/led interface directory
-    pub(crate) fn read_info(dir: &str, interface: &str, _lock: Option<Lock>) -> Result<Info> {
+    #[cfg_attr(not(feature = "locking"), allow(unused_variables))]
+    pub(crate) fn read_info(dir: &str, interface: &str, lock: Option<Lock>) -> Result<Info> {
         let mut path = construct_path(dir, interface);
         if !path.is_dir() {
             return Err(ErrorKind::NotFound.into());
@@ -583,7 +837,7 @@ mod utils {
                 .open(&path)
                 .map_err(err.clone())?;
             #[cfg(feature = "locking")]
-            if let Some(lock) = _lock {
+            if let Some(lock) = lock {
                 acquire_lock(&mut current_file, lock)?;
             }
             let current = read_ascii_u32(&mut current_file).map_err(err)?;
@@ -611,7 +865,7 @@ mod utils {
         };
         match lock {
             Lock::NonBlocking => match file.try_lock() {
-                Ok(_) => Ok(()),
+                Ok(()) => Ok(()),
                 Err(std::fs::TryLockError::WouldBlock) => Err(lock_err(true, None)),
                 Err(std::fs::TryLockError::Error(src)) => Err(lock_err(false, Some(src))),
             },
@@ -656,6 +910,74 @@ mod utils {
         buf.push(device_name);
         buf
     }
+
+    /// Convert a brightness percentage into a raw value for a device with the given max brightness
+    ///
+    /// The percentage is clamped between 0 and 100 and the result is rounded to the nearest integer.
+    #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+    pub(crate) fn percent_to_value(percent: f64, max: u32) -> u32 {
+        (f64::from(max) * (percent.clamp(0.0, 100.0) / 100.0)).round() as u32
+    }
+
+    /// The value moved within the clamp policy of the light, values larger than the max are left as they are so
+    /// writing them is still an error
+    pub(crate) fn clamped<L: Light + ?Sized>(light: &L, value: L::Value) -> L::Value {
+        let (val, max): (u32, u32) = (value.into(), light.max().into());
+        if val > max {
+            return value;
+        }
+        L::Value::try_from(light.clamp_policy().apply(val, max)).unwrap_or(value)
+    }
+
+    /// Linearly interpolate between two raw brightness values, where `progress` ranges from `0.0` to `1.0`
+    #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+    pub(crate) fn interpolate(from: u32, to: u32, progress: f64) -> u32 {
+        let (from, to) = (f64::from(from), f64::from(to));
+        (from + (to - from) * progress.clamp(0.0, 1.0)).round() as u32
+    }
+
+    /// Match text against a simple glob pattern where `*` matches any sequence of characters
+    /// and `?` matches any single character
+    pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+        let (pattern, text): (Vec<char>, Vec<char>) =
+            (pattern.chars().collect(), text.chars().collect());
+        let (mut p, mut t) = (0, 0);
+        // Position of the last `*` in the pattern and the text position it was tried at
+        let mut backtrack: Option<(usize, usize)> = None;
+        while t < text.len() {
+            match pattern.get(p) {
+                Some('*') => {
+                    backtrack = Some((p, t));
+                    p += 1;
+                }
+                Some(&c) if c == '?' || c == text[t] => {
+                    p += 1;
+                    t += 1;
+                }
+                _ => match backtrack {
+                    Some((star, tried)) => {
+                        p = star + 1;
+                        t = tried + 1;
+                        backtrack = Some((star, tried + 1));
+                    }
+                    None => return false,
+                },
+            }
+        }
+        pattern[p..].iter().all(|&c| c == '*')
+    }
+
+    /// Sleep for the given duration, returning early if `stop` is set
+    pub(crate) fn sleep_until_stopped(duration: Duration, stop: &AtomicBool) {
+        let end = Instant::now() + duration;
+        while !stop.load(Ordering::Relaxed) {
+            let remaining = end.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            thread::sleep(remaining.min(*Delay::default()));
+        }
+    }
 }
 
 // NOTE: tests that read from and write to the disk should not be run in parallel
@@ -665,11 +987,11 @@ mod tests {
     use super::*;
     pub(crate) const BLDIR: &str = "testbldir";
 
-    struct MockInterface(utils::Info);
+    pub(crate) struct MockInterface(utils::Info);
 
     impl MockInterface {
         /// Reads from disk, for testing reads and writes
-        fn new(name: &str) -> Self {
+        pub(crate) fn new(name: &str) -> Self {
             Self(utils::read_info(BLDIR, name, None).expect("failed to initialize mock interface"))
         }
         /// Dummy instance with specified values that points to an empty temp file
@@ -758,6 +1080,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_delay() {
+        let parse = |s: &str| s.parse::<Delay>().map(|d| *d);
+        assert_eq!(parse("200ms"), Ok(Duration::from_millis(200)));
+        assert_eq!(parse("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse("2m"), Ok(Duration::from_mins(2)));
+        for invalid in ["", "200", "ms", "1h", "-1s", "1 s"] {
+            assert_eq!(
+                parse(invalid),
+                Err(ParseDelayError),
+                "{invalid:?} was parsed"
+            );
+        }
+    }
+
+    #[test]
+    fn glob_matching() {
+        let cases = [
+            ("input*", "input13", true),
+            ("input*", "platform", false),
+            ("*::capslock", "input3::capslock", true),
+            ("input?", "input3", true),
+            ("input?", "input13", false),
+            ("*kbd*", "tpacpi::kbd_backlight", true),
+            ("*", "", true),
+            ("exact", "exact", true),
+            ("a*b*c", "aXbYbZc", true),
+            ("a*b*c", "aXbYbZ", false),
+        ];
+        for (i, (pattern, text, expected)) in cases.into_iter().enumerate() {
+            assert_eq!(
+                utils::glob_match(pattern, text),
+                expected,
+                "case {i} failed"
+            );
+        }
+    }
+
+    #[test]
+    fn partial_device_enumeration() {
+        let test = || {
+            fs::remove_file(format!("{BLDIR}/broken/max_brightness"))
+                .expect("failed to remove max brightness file");
+            let results = try_devices().expect("failed to enumerate devices");
+            let status: Vec<(&str, bool)> = results
+                .iter()
+                .map(|(name, device)| (name.as_str(), device.is_ok()))
+                .collect();
+            assert_eq!(status, [("amdgpu_bl0", true), ("broken", false)]);
+            assert_eq!(
+                results[1].1.as_ref().unwrap_err().kind(),
+                &ErrorKind::ReadMax
+            );
+        };
+        with_test_env(&["amdgpu_bl0", "broken"], test);
+    }
+
     #[test]
     fn detecting_device_nvidia() {
         let interfaces = ["nvidia_0", "generic"];
@@ -864,6 +1243,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::float_cmp)]
     fn current_percent() {
         let percent = MockInterface::dummy(5, 255).current_percent().round();
         assert_eq!(percent, 2.0);
@@ -912,6 +1292,18 @@ mod tests {
         with_test_env(&[name], test);
     }
 
+    #[test]
+    fn sweep_small_max() {
+        let name = "generic";
+        clean_up();
+        setup_test_env(&[name], 1, 10);
+        let mut d = MockInterface::new(name);
+        d.sweep_write(10, Delay::default()).unwrap();
+        d.reload();
+        assert_eq!(d.current(), 10);
+        clean_up();
+    }
+
     #[test]
     fn sweep_bounds() {
         let name = "generic";
@@ -1,22 +1,30 @@
 #![cfg(feature = "cli")]
 
-use std::env;
+use std::{env, process::ExitCode};
 
 mod cli;
 
-fn main() {
+fn main() -> ExitCode {
     cli::PanicReporter::init();
 
     let config = match cli::parse(env::args().skip(1)) {
         Ok(c) => c,
         Err(e) => {
+            let code = cli::exit_code(&e);
             cli::print_err(e);
-            return;
+            return code;
         }
     };
 
     match cli::execute(config) {
-        Err(e) => cli::print_err(e),
-        Ok(msg) => cli::print_ok(msg),
+        Err(e) => {
+            let code = cli::exit_code(&e);
+            cli::print_err(e);
+            code
+        }
+        Ok(msg) => {
+            cli::print_ok(msg);
+            ExitCode::SUCCESS
+        }
     }
 }
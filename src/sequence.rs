@@ -0,0 +1,714 @@
+//! A small text language for scripting brightness sequences (e.g. status signalling with LEDs)
+//!
+//! A sequence is a list of steps separated by semicolons or newlines. Everything after a `#` is a comment.
+//!
+//! | Step | Description |
+//! |------|-------------|
+//! | `on [<time>]` | Set max brightness, then wait for the given time |
+//! | `off [<time>]` | Set brightness to 0, then wait for the given time |
+//! | `set <value> [<time>]` | Set a raw brightness value (e.g. `128`), a percentage (e.g. `50%`) or a fraction (e.g. `0.5`), then wait |
+//! | `fade [<from>->]<to> <time>` | Gradually change brightness over the given time, starting from the current value if `from` is omitted |
+//! | `wait <time>` | Keep the current brightness for the given time |
+//! | `repeat <n>` \| `repeat forever` | Run all steps since the start of the sequence (or the previous `repeat`) `n` times in total |
+//!
+//! Times are written as a number followed by a unit: `ms`, `s` or `m` (e.g. `200ms`, `1.5s`).
+//! Brightness values are absolute [`Level`]s, relative changes such as `+10%` aren't accepted.
+//! Values larger than the max brightness of a light are clamped to its max.
+//!
+//! By default, each step applies to every light the sequence is run on. A step can be limited to specific
+//! lights by prefixing it with `@` and a comma separated list of light names, which may contain `*` and `?` wildcards
+//! (e.g. `@input3::capslock,input3::numlock on 200ms`).
+//!
+//! # Usage
+//! ```no_run
+//! use blight::{led, sequence::Sequence};
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let sequence: Sequence = "
+//!         # Alternate between capslock and numlock LEDs
+//!         @*::capslock on; @*::numlock off; wait 200ms
+//!         @*::capslock off; @*::numlock on; wait 200ms
+//!         repeat 5
+//!         off
+//!     ".parse()?;
+//!     let mut leds = led::LedGroup::new(&led::LedFilter::new().device("input3"))?.into_leds();
+//!     sequence.check_targets(&leds)?;
+//!     sequence.run(&mut leds)?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::{
+    fmt::Display,
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{utils, Delay, Level, Light};
+
+/// A parsed brightness sequence
+///
+/// See [module][self] level docs for the syntax and usage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sequence {
+    blocks: Vec<Block>,
+}
+
+/// Steps that are repeated together
+#[derive(Debug, Clone, PartialEq)]
+struct Block {
+    steps: Vec<Step>,
+    /// `None` repeats the block forever
+    times: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    targets: Option<Vec<Target>>,
+    action: Action,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Target {
+    pattern: String,
+    pos: Position,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Action {
+    Set {
+        value: Level,
+        hold: Duration,
+    },
+    Fade {
+        from: Option<Level>,
+        to: Level,
+        duration: Duration,
+    },
+    Wait(Duration),
+}
+
+/// Absolute brightness, clamped to the max of each light
+fn resolve(level: Level, max: u32) -> u32 {
+    level.resolve(0, max).min(max)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+/// Error returned when a sequence is invalid, pointing to the position of the problem in the source text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl ParseError {
+    fn new(pos: Position, message: impl Into<String>) -> Self {
+        Self {
+            line: pos.line,
+            column: pos.column,
+            message: message.into(),
+        }
+    }
+
+    /// Line number of the problem (starting from 1)
+    #[must_use]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Column number of the problem in characters (starting from 1)
+    #[must_use]
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Description of the problem, without the position
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    pos: Position,
+}
+
+impl Token<'_> {
+    /// Position right after the token, used to report missing arguments
+    fn end(&self) -> Position {
+        Position {
+            line: self.pos.line,
+            column: self.pos.column + self.text.chars().count(),
+        }
+    }
+}
+
+/// Split the source into steps, each step being a list of whitespace separated tokens
+fn tokenize(src: &str) -> Vec<Vec<Token<'_>>> {
+    let mut steps = Vec::new();
+    for (line_idx, line) in src.lines().enumerate() {
+        let line = line.split_once('#').map_or(line, |(code, _)| code);
+        let mut offset = 0;
+        for segment in line.split(';') {
+            let mut tokens = Vec::new();
+            let mut rest = segment;
+            while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+                let len = rest[start..]
+                    .find(char::is_whitespace)
+                    .unwrap_or(rest.len() - start);
+                let byte_pos = offset + (segment.len() - rest.len()) + start;
+                tokens.push(Token {
+                    text: &rest[start..start + len],
+                    pos: Position {
+                        line: line_idx + 1,
+                        column: line[..byte_pos].chars().count() + 1,
+                    },
+                });
+                rest = &rest[start + len..];
+            }
+            if !tokens.is_empty() {
+                steps.push(tokens);
+            }
+            offset += segment.len() + 1;
+        }
+    }
+    steps
+}
+
+fn parse_duration(token: &Token) -> Result<Duration, ParseError> {
+    token
+        .text
+        .parse::<Delay>()
+        .map(|delay| *delay)
+        .map_err(|_| {
+            ParseError::new(
+                token.pos,
+                format!(
+                    "invalid time '{}', expected a number followed by ms, s or m (e.g. 200ms, 1s)",
+                    token.text
+                ),
+            )
+        })
+}
+
+/// Parse an absolute [`Level`], relative changes don't make sense in a sequence
+fn parse_value(text: &str, pos: Position) -> Result<Level, ParseError> {
+    text.parse::<Level>()
+        .ok()
+        .filter(|level| !level.is_relative())
+        .ok_or_else(|| {
+            ParseError::new(
+                pos,
+                format!("invalid brightness '{text}', expected a raw value (e.g. 128), a percentage (e.g. 50%) or a fraction (e.g. 0.5)"),
+            )
+        })
+}
+
+/// Parse an optional hold time, making sure there are no arguments left after it
+fn parse_hold(command: &Token, args: &[Token]) -> Result<Duration, ParseError> {
+    match args {
+        [] => Ok(Duration::ZERO),
+        [time] => parse_duration(time),
+        [_, extra, ..] => Err(ParseError::new(
+            extra.pos,
+            format!("unexpected '{}' after '{}'", extra.text, command.text),
+        )),
+    }
+}
+
+fn parse_fade(command: &Token, args: &[Token]) -> Result<Action, ParseError> {
+    let Some((time, values)) = args.split_last().filter(|(_, values)| !values.is_empty()) else {
+        return Err(ParseError::new(
+            args.last().unwrap_or(command).end(),
+            "expected a target brightness and a time (e.g. 'fade 0->255 1s')",
+        ));
+    };
+    let duration = parse_duration(time)?;
+    // Allow spaces around the arrow, e.g. `fade 0 -> 255 1s`
+    let text: String = values.iter().map(|t| t.text).collect();
+    let pos = values[0].pos;
+    let (from, to) = match text.split_once("->") {
+        Some(("", to)) => (None, to),
+        Some((from, to)) => (Some(parse_value(from, pos)?), to),
+        None => (None, text.as_str()),
+    };
+    if to.is_empty() {
+        return Err(ParseError::new(
+            values[values.len() - 1].end(),
+            "expected a target brightness after '->'",
+        ));
+    }
+    Ok(Action::Fade {
+        from,
+        to: parse_value(to, pos)?,
+        duration,
+    })
+}
+
+fn parse_action(command: &Token, args: &[Token]) -> Result<Action, ParseError> {
+    let action = match command.text {
+        "on" => Action::Set {
+            value: Level::Percent(100.0),
+            hold: parse_hold(command, args)?,
+        },
+        "off" => Action::Set {
+            value: Level::Raw(0),
+            hold: parse_hold(command, args)?,
+        },
+        "set" => {
+            let Some((value, rest)) = args.split_first() else {
+                return Err(ParseError::new(
+                    command.end(),
+                    "expected a brightness value (e.g. 'set 128' or 'set 50%')",
+                ));
+            };
+            Action::Set {
+                value: parse_value(value.text, value.pos)?,
+                hold: parse_hold(value, rest)?,
+            }
+        }
+        "fade" => parse_fade(command, args)?,
+        "wait" => match args {
+            [time] => Action::Wait(parse_duration(time)?),
+            [] => {
+                return Err(ParseError::new(
+                    command.end(),
+                    "expected a time to wait (e.g. 'wait 500ms')",
+                ))
+            }
+            [_, extra, ..] => {
+                return Err(ParseError::new(
+                    extra.pos,
+                    format!("unexpected '{}' after 'wait'", extra.text),
+                ))
+            }
+        },
+        other => {
+            return Err(ParseError::new(
+                command.pos,
+                format!(
+                    "unknown step '{other}', expected one of: on, off, set, fade, wait, repeat"
+                ),
+            ))
+        }
+    };
+    Ok(action)
+}
+
+/// Parse the repeat count, `None` meaning forever
+fn parse_repeat(command: &Token, args: &[Token]) -> Result<Option<u32>, ParseError> {
+    match args {
+        [count] if count.text == "forever" => Ok(None),
+        [count] => match count.text.parse() {
+            Ok(0) | Err(_) => Err(ParseError::new(
+                count.pos,
+                format!(
+                    "invalid repeat count '{}', expected a positive number or 'forever'",
+                    count.text
+                ),
+            )),
+            Ok(n) => Ok(Some(n)),
+        },
+        [] => Err(ParseError::new(
+            command.end(),
+            "expected a repeat count (e.g. 'repeat 3' or 'repeat forever')",
+        )),
+        [_, extra, ..] => Err(ParseError::new(
+            extra.pos,
+            format!("unexpected '{}' after 'repeat'", extra.text),
+        )),
+    }
+}
+
+fn parse_targets(token: &Token) -> Result<Vec<Target>, ParseError> {
+    let mut targets = Vec::new();
+    let mut column = token.pos.column + 1;
+    for pattern in token.text[1..].split(',') {
+        let pos = Position {
+            line: token.pos.line,
+            column,
+        };
+        if pattern.is_empty() {
+            return Err(ParseError::new(
+                pos,
+                "expected a light name after '@' or ','",
+            ));
+        }
+        targets.push(Target {
+            pattern: pattern.to_owned(),
+            pos,
+        });
+        column += pattern.chars().count() + 1;
+    }
+    Ok(targets)
+}
+
+impl FromStr for Sequence {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Sequence {
+    /// Parse a sequence from its text representation
+    ///
+    /// # Errors
+    /// - [`ParseError`] - if the sequence is empty or contains an invalid step
+    pub fn parse(src: &str) -> Result<Self, ParseError> {
+        let mut blocks = Vec::new();
+        let mut steps = Vec::new();
+        let mut forever: Option<Position> = None;
+        for step_tokens in tokenize(src) {
+            let (targets, tokens) = match step_tokens.split_first() {
+                Some((first, rest)) if first.text.starts_with('@') => {
+                    (Some(parse_targets(first)?), rest)
+                }
+                _ => (None, step_tokens.as_slice()),
+            };
+            let Some((command, args)) = tokens.split_first() else {
+                // Only reachable if the step consists of the targets alone
+                let targets = &step_tokens[0];
+                return Err(ParseError::new(
+                    targets.end(),
+                    "expected a step after the light names",
+                ));
+            };
+            if let Some(pos) = forever {
+                return Err(ParseError::new(
+                    command.pos,
+                    format!(
+                        "this step would never run, as the sequence repeats forever (line {}, column {})",
+                        pos.line, pos.column
+                    ),
+                ));
+            }
+            if command.text == "repeat" {
+                if let Some(target) = targets.as_ref().and_then(|t| t.first()) {
+                    return Err(ParseError::new(
+                        target.pos,
+                        "'repeat' applies to all lights and can't be limited with '@'",
+                    ));
+                }
+                let times = parse_repeat(command, args)?;
+                if steps.is_empty() {
+                    return Err(ParseError::new(
+                        command.pos,
+                        "nothing to repeat, 'repeat' must come after other steps",
+                    ));
+                }
+                if times.is_none() {
+                    forever = Some(command.pos);
+                }
+                blocks.push(Block {
+                    steps: std::mem::take(&mut steps),
+                    times,
+                });
+                continue;
+            }
+            let action = parse_action(command, args)?;
+            steps.push(Step { targets, action });
+        }
+        if !steps.is_empty() {
+            blocks.push(Block {
+                steps,
+                times: Some(1),
+            });
+        }
+        if blocks.is_empty() {
+            return Err(ParseError::new(
+                Position { line: 1, column: 1 },
+                "the sequence is empty",
+            ));
+        }
+        Ok(Self { blocks })
+    }
+
+    /// Total run time of the sequence, or `None` if it repeats forever
+    #[must_use]
+    pub fn duration(&self) -> Option<Duration> {
+        self.blocks.iter().try_fold(Duration::ZERO, |total, block| {
+            let once: Duration = block
+                .steps
+                .iter()
+                .map(|step| match step.action {
+                    Action::Set { hold, .. } => hold,
+                    Action::Fade { duration, .. } | Action::Wait(duration) => duration,
+                })
+                .sum();
+            Some(total + once * block.times?)
+        })
+    }
+
+    /// Number of steps in the sequence, not counting repetitions
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.blocks.iter().map(|b| b.steps.len()).sum()
+    }
+
+    /// Always returns `false`, as parsing an empty sequence fails
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Light name patterns used with `@` in the sequence (may contain duplicates)
+    pub fn targets(&self) -> impl Iterator<Item = &str> {
+        self.blocks
+            .iter()
+            .flat_map(|b| &b.steps)
+            .filter_map(|s| s.targets.as_ref())
+            .flatten()
+            .map(|t| t.pattern.as_str())
+    }
+
+    /// Whether the light with the given name matches any of the `@` targets of the sequence
+    #[must_use]
+    pub fn is_targeted(&self, name: &str) -> bool {
+        self.targets()
+            .any(|pattern| utils::glob_match(pattern, name))
+    }
+
+    /// Check that every `@` target of the sequence matches at least one of the given lights
+    ///
+    /// Steps with targets that don't match any light are silently skipped while running,
+    /// so this is useful to catch typos in light names before running a sequence.
+    ///
+    /// # Errors
+    /// - [`ParseError`] - pointing to the first target that doesn't match any light
+    pub fn check_targets<L: Light>(&self, lights: &[L]) -> Result<(), ParseError> {
+        let unmatched = self
+            .blocks
+            .iter()
+            .flat_map(|b| &b.steps)
+            .filter_map(|s| s.targets.as_ref())
+            .flatten()
+            .find(|t| {
+                !lights
+                    .iter()
+                    .any(|l| utils::glob_match(&t.pattern, l.name()))
+            });
+        match unmatched {
+            Some(target) => Err(ParseError::new(
+                target.pos,
+                format!("'{}' doesn't match any of the lights", target.pattern),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Run the sequence on the given lights, blocking until it completes
+    ///
+    /// Sequences that repeat forever never complete, use [`Sequence::run_until`] to stop them.
+    ///
+    /// # Errors
+    /// - All possible errors returned by [`Light::write_value`]
+    pub fn run<L: Light>(&self, lights: &mut [L]) -> crate::Result<()> {
+        self.run_until(lights, &AtomicBool::new(false))
+    }
+
+    /// Run the sequence on the given lights until it completes or `stop` is set to `true`
+    ///
+    /// Lights are left at their last brightness once the sequence stops.
+    ///
+    /// # Errors
+    /// - All possible errors returned by [`Light::write_value`]
+    pub fn run_until<L: Light>(&self, lights: &mut [L], stop: &AtomicBool) -> crate::Result<()> {
+        for block in &self.blocks {
+            let mut runs = 0;
+            while block.times.is_none_or(|times| runs < times) {
+                for step in &block.steps {
+                    if stop.load(Ordering::Relaxed) {
+                        return Ok(());
+                    }
+                    step.run(lights, stop)?;
+                }
+                runs += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Step {
+    fn run<L: Light>(&self, lights: &mut [L], stop: &AtomicBool) -> crate::Result<()> {
+        let mut selected: Vec<&mut L> = lights
+            .iter_mut()
+            .filter(|light| {
+                self.targets.as_ref().is_none_or(|targets| {
+                    targets
+                        .iter()
+                        .any(|t| utils::glob_match(&t.pattern, light.name()))
+                })
+            })
+            .collect();
+        match self.action {
+            Action::Set { value, hold } => {
+                for light in &mut selected {
+                    let value = resolve(value, light.max().into());
+                    write(*light, value)?;
+                }
+                utils::sleep_until_stopped(hold, stop);
+            }
+            Action::Fade { from, to, duration } => {
+                let ranges: Vec<(u32, u32)> = selected
+                    .iter()
+                    .map(|light| {
+                        let max = light.max().into();
+                        let from = from.map_or(light.current().into(), |from| resolve(from, max));
+                        (from, resolve(to, max))
+                    })
+                    .collect();
+                let start = Instant::now();
+                loop {
+                    let progress = if duration.is_zero() {
+                        1.0
+                    } else {
+                        start.elapsed().as_secs_f64() / duration.as_secs_f64()
+                    };
+                    for (light, &(from, to)) in selected.iter_mut().zip(&ranges) {
+                        write(*light, utils::interpolate(from, to, progress))?;
+                    }
+                    if progress >= 1.0 || stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    thread::sleep(*Delay::default());
+                }
+            }
+            Action::Wait(duration) => utils::sleep_until_stopped(duration, stop),
+        }
+        Ok(())
+    }
+}
+
+/// Write the value to the light, only if it differs from the current value
+fn write<L: Light>(light: &mut L, value: u32) -> crate::Result<()> {
+    let value = L::Value::try_from(value).unwrap_or_default();
+    if value != light.current() {
+        light.write_value(value)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{with_test_env, MockInterface};
+
+    fn parse_err(src: &str) -> (usize, usize) {
+        let err = Sequence::parse(src).expect_err("invalid sequence was parsed");
+        (err.line(), err.column())
+    }
+
+    #[test]
+    fn parse_steps() {
+        let seq: Sequence = "on 200ms; off 100ms; fade 0->255 1s; repeat 3"
+            .parse()
+            .unwrap();
+        assert_eq!(seq.len(), 3);
+        assert_eq!(seq.duration(), Some(Duration::from_millis(3900)));
+
+        let seq = Sequence::parse(
+            "# comment\n@a,b set 50% 1s\n\nfade 0 -> 100% 500ms # trailing\nwait 2s; fade 0 1s",
+        )
+        .unwrap();
+        assert_eq!(seq.len(), 4);
+        assert_eq!(seq.targets().collect::<Vec<_>>(), ["a", "b"]);
+        assert!(seq.is_targeted("b"));
+        assert!(!seq.is_targeted("c"));
+        assert_eq!(seq.duration(), Some(Duration::from_millis(4500)));
+
+        let seq = Sequence::parse("on 1s; off 1s; repeat forever").unwrap();
+        assert_eq!(seq.duration(), None);
+    }
+
+    #[test]
+    fn error_positions() {
+        assert_eq!(parse_err(""), (1, 1));
+        assert_eq!(parse_err("on 200ms; blink"), (1, 11));
+        assert_eq!(parse_err("on 200"), (1, 4));
+        assert_eq!(parse_err("on\nfade 0->"), (2, 9));
+        assert_eq!(parse_err("on\nfade 0->255"), (2, 12));
+        assert_eq!(parse_err("on\nfade 0-> 1s"), (2, 9));
+        assert_eq!(parse_err("@a"), (1, 3));
+        assert_eq!(parse_err("set 101% 1s"), (1, 5));
+        assert_eq!(parse_err("on\nset +10% 1s"), (2, 5));
+        assert_eq!(parse_err("fade -5->0 1s"), (1, 6));
+        assert_eq!(parse_err("repeat 2"), (1, 1));
+        assert_eq!(parse_err("on; repeat 0"), (1, 12));
+        assert_eq!(parse_err("on; repeat forever; off"), (1, 21));
+        assert_eq!(parse_err("@a,,b on"), (1, 4));
+        assert_eq!(parse_err("wait 1s 2s"), (1, 9));
+    }
+
+    #[test]
+    fn run_on_targets() {
+        with_test_env(&["a", "b"], || {
+            let mut lights = [MockInterface::new("a"), MockInterface::new("b")];
+            let seq = Sequence::parse("@a off; @b on; set 10% 5ms; @b fade 100->20 20ms").unwrap();
+            seq.check_targets(&lights).unwrap();
+            seq.run(&mut lights).expect("failed to run sequence");
+            assert_eq!(lights[0].current(), 10);
+            assert_eq!(lights[1].current(), 20);
+            Sequence::parse("set 0.25")
+                .unwrap()
+                .run(&mut lights)
+                .unwrap();
+            assert_eq!(lights[0].current(), 25);
+
+            let err = Sequence::parse("@a on; @c off")
+                .unwrap()
+                .check_targets(&lights)
+                .unwrap_err();
+            assert_eq!((err.line(), err.column()), (1, 9));
+        });
+    }
+
+    #[test]
+    fn clamps_to_max() {
+        with_test_env(&["a"], || {
+            let mut lights = [MockInterface::new("a")];
+            Sequence::parse("set 5000")
+                .unwrap()
+                .run(&mut lights)
+                .unwrap();
+            assert_eq!(lights[0].current(), 100);
+        });
+    }
+
+    #[test]
+    fn stops_on_request() {
+        with_test_env(&["a"], || {
+            let mut lights = [MockInterface::new("a")];
+            let stop = AtomicBool::new(true);
+            Sequence::parse("on 1s; repeat forever")
+                .unwrap()
+                .run_until(&mut lights, &stop)
+                .unwrap();
+            assert_eq!(lights[0].current(), 50);
+        });
+    }
+}
@@ -0,0 +1,598 @@
+//! Capture the brightness of backlights and LEDs, and put it back later
+//!
+//! A [`Snapshot`] stores the brightness of a set of lights, selected with a [`SnapshotFilter`].
+//! Applying a snapshot restores every light it contains, even if some of them fail, and reports the first error.
+//!
+//! Backlights are stored with their [identity][DeviceIdentity], so they're still found after being renamed, and with
+//! their brightness percentage, which is used when the max brightness of the device changed since the capture.
+//!
+//! # Text format
+//! Snapshots can be written with [`Display`] and read back with [`FromStr`]. The text starts with a version line,
+//! followed by one line per light made of space separated `key=value` pairs:
+//! ```text
+//! version=2
+//! backlight=amdgpu_bl0 value=120 max=255 percent=47.06 type=raw connector=eDP-1 driver=amdgpu
+//! led=input3::capslock value=1 max=1 percent=100.00
+//! ```
+//! Unknown keys are ignored, so new fields can be added without bumping the version.
+//! The single `<device> <value>` line written by older versions of the CLI is read as a snapshot of one backlight.
+//!
+//! # Usage
+//! ```no_run
+//! use blight::{led::{Function, LedFilter}, snapshot::SnapshotFilter, Change, Snapshot};
+//!
+//! fn main() -> blight::Result<()> {
+//!     let filter = SnapshotFilter::new().leds(LedFilter::new().function(Function::KbdBacklight));
+//!     let snapshot = Snapshot::capture(&filter)?;
+//!     // Store the snapshot as text, e.g. in a file
+//!     let saved = snapshot.to_string();
+//!
+//!     // ...
+//!
+//!     let snapshot: Snapshot = saved.parse().expect("snapshot was written by blight");
+//!     for diff in snapshot.diff() {
+//!         println!("{} changed from {} to {:?}", diff.name(), diff.saved(), diff.current());
+//!     }
+//!     snapshot.apply(Change::Sweep)
+//! }
+//! ```
+
+use std::{borrow::Cow, fmt::Display, str::FromStr};
+
+use crate::{
+    led::{self, LedFilter, LedType},
+    try_devices, Change, Delay, Device, DeviceIdentity, Level, Light,
+};
+
+const VERSION: u32 = 2;
+
+/// Selects the lights captured in a [`Snapshot`]
+///
+/// By default, every backlight device and no LEDs are selected.
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotFilter {
+    /// `None` selects every backlight
+    backlights: Option<Vec<String>>,
+    leds: Option<LedFilter>,
+}
+
+impl SnapshotFilter {
+    /// Select every backlight device and no LEDs
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Select every backlight device and every LED
+    #[must_use]
+    pub fn all() -> Self {
+        Self::new().leds(LedFilter::new())
+    }
+
+    /// Only select the given backlight device instead of every one, can be called multiple times
+    #[must_use]
+    pub fn device(mut self, name: impl Into<String>) -> Self {
+        self.backlights
+            .get_or_insert_with(Vec::new)
+            .push(name.into());
+        self
+    }
+
+    /// Don't select any backlight device
+    #[must_use]
+    pub fn no_devices(mut self) -> Self {
+        self.backlights = Some(vec![]);
+        self
+    }
+
+    /// Select the LEDs matching the filter
+    #[must_use]
+    pub fn leds(mut self, filter: LedFilter) -> Self {
+        self.leds = Some(filter);
+        self
+    }
+}
+
+/// Kind of a light in a [`Snapshot`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightKind {
+    Backlight,
+    Led,
+}
+
+impl LightKind {
+    /// Name of the kind as it appears in the text format: `backlight` or `led`
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LightKind::Backlight => "backlight",
+            LightKind::Led => "led",
+        }
+    }
+}
+
+/// Captured brightness of a single light
+#[derive(Debug, Clone, PartialEq)]
+pub struct LightState {
+    kind: LightKind,
+    name: String,
+    value: u32,
+    max: Option<u32>,
+    percent: Option<f64>,
+    identity: DeviceIdentity,
+}
+
+impl LightState {
+    fn of(kind: LightKind, light: &impl Light<Value = u32>) -> Self {
+        let percent =
+            (light.max() > 0).then(|| f64::from(light.current()) / f64::from(light.max()) * 100.0);
+        Self {
+            kind,
+            name: light.name().to_owned(),
+            value: light.current(),
+            max: Some(light.max()),
+            percent,
+            identity: DeviceIdentity::default(),
+        }
+    }
+
+    fn backlight(device: &Device) -> Self {
+        Self {
+            identity: device.identity(),
+            ..Self::of(LightKind::Backlight, device)
+        }
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> LightKind {
+        self.kind
+    }
+
+    /// Name of the light at the time of the capture
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Raw brightness value
+    #[must_use]
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    /// Max brightness value, unknown for snapshots in the legacy format
+    #[must_use]
+    pub fn max(&self) -> Option<u32> {
+        self.max
+    }
+
+    /// Brightness percentage, unknown for snapshots in the legacy format
+    #[must_use]
+    pub fn percent(&self) -> Option<f64> {
+        self.percent
+    }
+
+    /// Identity of the backlight device, empty for LEDs
+    #[must_use]
+    pub fn identity(&self) -> &DeviceIdentity {
+        &self.identity
+    }
+
+    /// The captured brightness rescaled to a light with the given max brightness
+    fn value_for(&self, max: u32) -> u32 {
+        let percent = self.percent.or_else(|| {
+            self.max
+                .filter(|max| *max > 0)
+                .map(|saved_max| f64::from(self.value) / f64::from(saved_max) * 100.0)
+        });
+        match (self.max, percent) {
+            (Some(saved_max), _) if saved_max == max => self.value,
+            (_, Some(percent)) => Level::Percent(percent).resolve(0, max),
+            // Legacy snapshots only have the raw value
+            _ => self.value,
+        }
+    }
+
+    /// Current brightness of the light, `None` if it can't be found anymore
+    fn current(&self) -> Option<u32> {
+        match self.kind {
+            LightKind::Backlight => Device::find(&self.name, &self.identity)
+                .ok()
+                .map(|device| device.current()),
+            LightKind::Led => led::Led::new(Cow::from(self.name.as_str()))
+                .ok()
+                .map(|led| led.current()),
+        }
+    }
+
+    fn apply(&self, change: Change) -> crate::Result<()> {
+        match self.kind {
+            LightKind::Backlight => {
+                let mut device = Device::find(&self.name, &self.identity)?;
+                let value = self.value_for(device.max());
+                match change {
+                    Change::Sweep => device.sweep_write(value, Delay::default()),
+                    Change::Regular => device.write_value(value),
+                }
+            }
+            LightKind::Led => match led::Led::new(Cow::from(self.name.as_str()))? {
+                LedType::Dimmable(mut led) if change == Change::Sweep => {
+                    led.sweep_write(self.value_for(led.max()), Delay::default())
+                }
+                mut led => led.write_value(self.value_for(led.max())),
+            },
+        }
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut pairs = line.split_whitespace().map(|pair| pair.split_once('='));
+        let (kind, name) = match pairs.next()?? {
+            ("backlight", name) => (LightKind::Backlight, name),
+            ("led", name) => (LightKind::Led, name),
+            _ => return None,
+        };
+        let (mut value, mut max, mut percent) = (None, None, None);
+        let (mut device_type, mut connector, mut driver) = (None, None, None);
+        for pair in pairs {
+            match pair? {
+                ("value", v) => value = Some(v.parse().ok()?),
+                ("max", v) => max = Some(v.parse().ok()?),
+                ("percent", v) => percent = Some(v.parse().ok()?),
+                ("type", v) => device_type = Some(v.to_owned()),
+                ("connector", v) => connector = Some(v.to_owned()),
+                ("driver", v) => driver = Some(v.to_owned()),
+                _ => (),
+            }
+        }
+        Some(Self {
+            kind,
+            name: name.to_owned(),
+            value: value?,
+            max,
+            percent,
+            identity: DeviceIdentity::new(device_type, connector, driver),
+        })
+    }
+}
+
+impl Display for LightState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}={} value={}",
+            self.kind.as_str(),
+            self.name,
+            self.value
+        )?;
+        if let Some(max) = self.max {
+            write!(f, " max={max}")?;
+        }
+        if let Some(percent) = self.percent {
+            write!(f, " percent={percent:.2}")?;
+        }
+        let identity = [
+            ("type", self.identity.kind()),
+            ("connector", self.identity.connector()),
+            ("driver", self.identity.driver()),
+        ];
+        for (key, value) in identity {
+            if let Some(value) = value {
+                write!(f, " {key}={value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A light whose brightness changed since it was captured
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    kind: LightKind,
+    name: String,
+    saved: u32,
+    current: Option<u32>,
+}
+
+impl Difference {
+    #[must_use]
+    pub fn kind(&self) -> LightKind {
+        self.kind
+    }
+
+    /// Name of the light at the time of the capture
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Raw brightness value in the snapshot
+    #[must_use]
+    pub fn saved(&self) -> u32 {
+        self.saved
+    }
+
+    /// Current raw brightness value, `None` if the light can't be found anymore
+    #[must_use]
+    pub fn current(&self) -> Option<u32> {
+        self.current
+    }
+}
+
+/// Brightness of a set of lights at a point in time
+///
+/// See [module][self] level docs for usage and the text format.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    lights: Vec<LightState>,
+}
+
+impl Snapshot {
+    /// Capture the brightness of the lights selected by the filter
+    ///
+    /// Lights that fail to initialize are left out of the snapshot, use [`Snapshot::try_capture`] to find out which.
+    /// An LED filter that doesn't match any LED selects none.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::ReadDir`][crate::ErrorKind::ReadDir] - failure to read the backlight or LED directory
+    pub fn capture(filter: &SnapshotFilter) -> crate::Result<Self> {
+        Self::try_capture(filter).map(|(snapshot, _)| snapshot)
+    }
+
+    /// Capture the brightness of the lights selected by the filter, along with the lights that failed
+    ///
+    /// Same as [`Snapshot::capture`], but each light that couldn't be captured is returned with its name and the error
+    /// it failed with, e.g. [`ErrorKind::NotFound`][crate::ErrorKind::NotFound] for a backlight selected with
+    /// [`SnapshotFilter::device`] that doesn't exist.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::ReadDir`][crate::ErrorKind::ReadDir] - failure to read the backlight or LED directory
+    pub fn try_capture(
+        filter: &SnapshotFilter,
+    ) -> crate::Result<(Self, Vec<(String, crate::Error)>)> {
+        let devices = match &filter.backlights {
+            Some(names) => names
+                .iter()
+                .map(|name| (name.clone(), Device::new(Some(name.into()))))
+                .collect(),
+            None => try_devices()?,
+        };
+        let mut lights = vec![];
+        let mut failed = vec![];
+        for (name, device) in devices {
+            match device {
+                Ok(device) => lights.push(LightState::backlight(&device)),
+                Err(err) => failed.push((name, err)),
+            }
+        }
+        if let Some(filter) = &filter.leds {
+            let names = led::led_names()?
+                .into_iter()
+                .filter(|name| filter.matches_name(name));
+            let mut leds = vec![];
+            for (name, led) in led::try_leds_from_names(names) {
+                match led {
+                    Ok(led) if filter.matches(&led) => leds.push(led),
+                    Ok(_) => (),
+                    Err(err) => failed.push((name.raw_name().to_owned(), err)),
+                }
+            }
+            leds.sort_by(|a, b| a.name().cmp(b.name()));
+            lights.extend(leds.iter().map(|led| LightState::of(LightKind::Led, led)));
+        }
+        Ok((Self { lights }, failed))
+    }
+
+    /// Captured lights in the order they were captured
+    #[must_use]
+    pub fn lights(&self) -> &[LightState] {
+        &self.lights
+    }
+
+    /// Restore the brightness of every light in the snapshot, instantly or with a [sweep][Light::sweep_write]
+    ///
+    /// Backlights are found by [name and identity][Device::find], and their brightness is rescaled when their max
+    /// brightness changed. Lights that fail don't stop the rest from being restored.
+    ///
+    /// # Errors
+    /// The first error that occurred while restoring the lights, e.g.:
+    /// - [`ErrorKind::NotFound`][crate::ErrorKind::NotFound] - a backlight device can't be found
+    /// - [`ErrorKind::WriteValue`][crate::ErrorKind::WriteValue] - on write failure
+    pub fn apply(&self, change: Change) -> crate::Result<()> {
+        let mut first_err = None;
+        for light in &self.lights {
+            if let Err(err) = light.apply(change) {
+                first_err.get_or_insert(err);
+            }
+        }
+        first_err.map_or(Ok(()), Err)
+    }
+
+    /// Lights whose current brightness is different from the one in the snapshot
+    ///
+    /// Lights that can't be found anymore are reported with an unknown current brightness.
+    #[must_use]
+    pub fn diff(&self) -> Vec<Difference> {
+        self.lights
+            .iter()
+            .filter_map(|light| {
+                let current = light.current();
+                (current != Some(light.value)).then(|| Difference {
+                    kind: light.kind,
+                    name: light.name.clone(),
+                    saved: light.value,
+                    current,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Display for Snapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "version={VERSION}")?;
+        for light in &self.lights {
+            writeln!(f, "{light}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Snapshot {
+    type Err = ParseSnapshotError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+        let Some((_, first)) = lines.next() else {
+            return Err(ParseSnapshotError::Empty);
+        };
+        let Some(version) = first.strip_prefix("version=") else {
+            return parse_legacy(first).ok_or(ParseSnapshotError::InvalidLine(1));
+        };
+        match version.parse() {
+            Ok(version) if version <= VERSION => (),
+            Ok(version) => return Err(ParseSnapshotError::UnsupportedVersion(version)),
+            Err(_) => return Err(ParseSnapshotError::InvalidLine(1)),
+        }
+        let lights = lines
+            .map(|(i, line)| LightState::parse(line).ok_or(ParseSnapshotError::InvalidLine(i)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { lights })
+    }
+}
+
+/// The `<device> <value>` line written by older versions of the CLI
+fn parse_legacy(line: &str) -> Option<Snapshot> {
+    let (name, value) = line.split_once(' ')?;
+    let light = LightState {
+        kind: LightKind::Backlight,
+        name: name.to_owned(),
+        value: value.trim().parse().ok()?,
+        max: None,
+        percent: None,
+        identity: DeviceIdentity::default(),
+    };
+    Some(Snapshot {
+        lights: vec![light],
+    })
+}
+
+/// Error returned when parsing a [`Snapshot`] from text fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseSnapshotError {
+    Empty,
+    /// The snapshot was written by a newer version
+    UnsupportedVersion(u32),
+    /// Line number (starting at 1) that couldn't be parsed
+    InvalidLine(usize),
+}
+
+impl Display for ParseSnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseSnapshotError::Empty => write!(f, "snapshot is empty"),
+            ParseSnapshotError::UnsupportedVersion(version) => write!(
+                f,
+                "snapshot version {version} is not supported (latest supported version is {VERSION})"
+            ),
+            ParseSnapshotError::InvalidLine(line) => write!(f, "invalid snapshot at line {line}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseSnapshotError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::with_test_env;
+
+    #[test]
+    fn text_round_trip() {
+        let text = "version=2\n\
+            backlight=amdgpu_bl0 value=120 max=255 percent=47.06 type=raw connector=eDP-1 driver=amdgpu\n\
+            led=input3::capslock value=1 max=1 percent=100.00\n";
+        let snapshot: Snapshot = text.parse().unwrap();
+        assert_eq!(snapshot.lights().len(), 2);
+        assert_eq!(snapshot.lights()[0].identity().connector(), Some("eDP-1"));
+        assert_eq!(snapshot.lights()[1].kind(), LightKind::Led);
+        assert_eq!(snapshot.to_string(), text);
+
+        let legacy: Snapshot = "acpi_video0 7".parse().unwrap();
+        assert_eq!(legacy.lights()[0].name(), "acpi_video0");
+        assert_eq!(legacy.lights()[0].max(), None);
+
+        assert_eq!("".parse::<Snapshot>(), Err(ParseSnapshotError::Empty));
+        assert_eq!(
+            "version=3".parse::<Snapshot>(),
+            Err(ParseSnapshotError::UnsupportedVersion(3))
+        );
+        assert_eq!(
+            "version=2\nbacklight=a\n".parse::<Snapshot>(),
+            Err(ParseSnapshotError::InvalidLine(2))
+        );
+    }
+
+    #[test]
+    fn rescale_to_new_max() {
+        let light = LightState::parse("backlight=a value=120 max=240 percent=50.00").unwrap();
+        assert_eq!(light.value_for(240), 120);
+        assert_eq!(light.value_for(100), 50);
+        let legacy = parse_legacy("a 30").unwrap();
+        assert_eq!(legacy.lights()[0].value_for(100), 30);
+    }
+
+    #[test]
+    fn capture_apply_diff() {
+        with_test_env(&["first", "second"], || {
+            let snapshot = Snapshot::capture(&SnapshotFilter::new()).unwrap();
+            assert_eq!(snapshot.lights().len(), 2);
+            assert!(snapshot.diff().is_empty());
+
+            Device::new(Some("second".into()))
+                .unwrap()
+                .write_value(80)
+                .unwrap();
+            let diff = snapshot.diff();
+            assert_eq!(diff.len(), 1);
+            assert_eq!(
+                (diff[0].name(), diff[0].saved(), diff[0].current()),
+                ("second", 50, Some(80))
+            );
+
+            snapshot.apply(Change::Regular).unwrap();
+            assert_eq!(Device::new(Some("second".into())).unwrap().current(), 50);
+
+            let only_first = Snapshot::capture(&SnapshotFilter::new().device("first")).unwrap();
+            assert_eq!(only_first.lights().len(), 1);
+        });
+    }
+
+    #[test]
+    fn capture_skips_failures() {
+        with_test_env(&["first", "second"], || {
+            std::fs::remove_file(format!("{}/second/brightness", crate::tests::BLDIR)).unwrap();
+            let (snapshot, failed) = Snapshot::try_capture(&SnapshotFilter::new()).unwrap();
+            assert_eq!(snapshot.lights().len(), 1);
+            assert_eq!(snapshot.lights()[0].name(), "first");
+            assert_eq!(failed.len(), 1);
+            assert_eq!(failed[0].0, "second");
+            assert_eq!(failed[0].1.kind(), &crate::ErrorKind::ReadCurrent);
+
+            let filter = SnapshotFilter::new().device("first").device("missing");
+            let (snapshot, failed) = Snapshot::try_capture(&filter).unwrap();
+            assert_eq!(snapshot.lights().len(), 1);
+            assert_eq!(failed[0].0, "missing");
+
+            // No LED matching the filter isn't an error
+            let filter = SnapshotFilter::new()
+                .device("first")
+                .leds(LedFilter::new().function(led::Function::KbdBacklight));
+            let snapshot = Snapshot::capture(&filter).unwrap();
+            assert_eq!(snapshot.lights().len(), 1);
+        });
+    }
+}
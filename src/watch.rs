@@ -0,0 +1,132 @@
+//! Watch a light for brightness changes
+//!
+//! sysfs brightness files can't be watched with `inotify`, so a [`Watcher`] periodically re-reads the brightness file
+//! and only reports a value when it changed.
+//! Reading a single small sysfs file is cheap, which makes polling at a few hundred milliseconds practical for status bars.
+//!
+//! Changes made through [`Watcher::light_mut`] are reported the same way as external ones.
+//!
+//! # Usage
+//! ```no_run
+//! use std::{sync::atomic::AtomicBool, time::Duration};
+//! use blight::{watch::Watcher, Device, Light};
+//!
+//! fn main() -> blight::Result<()> {
+//!     let mut watcher = Watcher::new(Device::new(None)?).interval(Duration::from_millis(500));
+//!     let stop = AtomicBool::new(false);
+//!     // The first call returns the current brightness right away
+//!     while let Some(value) = watcher.wait_until(&stop)? {
+//!         println!("{value}/{}", watcher.light().max());
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use crate::{utils, Light};
+
+/// Reports brightness changes of a light by polling its brightness file
+///
+/// See [module][self] level docs for usage.
+#[derive(Debug)]
+pub struct Watcher<L: Light> {
+    light: L,
+    interval: Duration,
+    last: Option<L::Value>,
+}
+
+impl<L: Light> Watcher<L> {
+    /// Create a new watcher for a light, checking for changes every 250ms by default
+    #[must_use]
+    pub fn new(light: L) -> Self {
+        Self {
+            light,
+            interval: Duration::from_millis(250),
+            last: None,
+        }
+    }
+
+    /// Time to wait between each check of the brightness file in [`Watcher::wait_until`]
+    #[must_use]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// The watched light, with the brightness value read by the last check
+    #[must_use]
+    pub fn light(&self) -> &L {
+        &self.light
+    }
+
+    /// Mutable access to the watched light, e.g. to change its brightness in response to user input
+    pub fn light_mut(&mut self) -> &mut L {
+        &mut self.light
+    }
+
+    /// Consume the watcher, returning the watched light
+    #[must_use]
+    pub fn into_inner(self) -> L {
+        self.light
+    }
+
+    /// Check the brightness once, returning the new value if it changed since the last check
+    ///
+    /// The first check always returns the current value.
+    ///
+    /// # Errors
+    /// - [`ErrorKind::ReadCurrent`][crate::ErrorKind::ReadCurrent] - on read failure
+    pub fn poll(&mut self) -> crate::Result<Option<L::Value>> {
+        self.light.try_reload()?;
+        let current = self.light.current();
+        if self.last == Some(current) {
+            return Ok(None);
+        }
+        self.last = Some(current);
+        Ok(Some(current))
+    }
+
+    /// Block until the brightness changes and return the new value, or return `None` once `stop` is set to `true`
+    ///
+    /// # Errors
+    /// - [`ErrorKind::ReadCurrent`][crate::ErrorKind::ReadCurrent] - on read failure
+    pub fn wait_until(&mut self, stop: &AtomicBool) -> crate::Result<Option<L::Value>> {
+        while !stop.load(Ordering::Relaxed) {
+            if let Some(value) = self.poll()? {
+                return Ok(Some(value));
+            }
+            utils::sleep_until_stopped(self.interval, stop);
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{with_test_env, MockInterface};
+
+    #[test]
+    fn reports_changes() {
+        with_test_env(&["watched"], || {
+            let mut watcher = Watcher::new(MockInterface::new("watched"));
+            assert_eq!(watcher.poll().unwrap(), Some(50));
+            assert_eq!(watcher.poll().unwrap(), None);
+
+            MockInterface::new("watched").write_value(60).unwrap();
+            assert_eq!(watcher.poll().unwrap(), Some(60));
+            assert_eq!(watcher.poll().unwrap(), None);
+
+            watcher.light_mut().write_value(40).unwrap();
+            let stop = AtomicBool::new(false);
+            assert_eq!(watcher.wait_until(&stop).unwrap(), Some(40));
+
+            stop.store(true, Ordering::Relaxed);
+            assert_eq!(watcher.wait_until(&stop).unwrap(), None);
+        });
+    }
+}